@@ -0,0 +1,100 @@
+// Generated from the laskea-engine types. Do not edit by hand.
+type Text = string;
+type Sequence<T> = Array<T>;
+type EvaluationError = { "type": "failed", message: Text, } | { "type": "upstream-failed", 
+/**
+ * The node the failure originated from.
+ */
+node: Text, message: Text, };
+type Secret = string;
+type Instant = string;
+type Duration = number;
+type Object = { [key in Text]?: Value };
+type Value = { "type": "number", "value": number } | { "type": "string", "value": Text } | { "type": "boolean", "value": boolean } | { "type": "instant", "value": Instant } | { "type": "duration", "value": Duration } | { "type": "object", "value": Object } | { "type": "array", "value": Sequence<Value> } | { "type": "secret", "value": Secret } | { "type": "indeterminate" };
+type Metrics = { 
+/**
+ * When the request was sent.
+ */
+timestamp: Instant, 
+/**
+ * How long it took to receive the whole response, including any
+ * redirects.
+ */
+total: Duration, 
+/**
+ * How long it took to receive the first byte of the final response.
+ */
+ttfb?: Duration, 
+/**
+ * How long it took to look up the server's address. This isn't known
+ * when an existing connection was reused.
+ */
+dns?: Duration, 
+/**
+ * How long it took to connect to the server.
+ *
+ * Not every host can measure this (e.g. the CLI's HTTP client doesn't
+ * report it), so it is `None` unless the host says otherwise.
+ */
+connect?: Duration, 
+/**
+ * The size of the response body, in bytes.
+ */
+size: number, 
+/**
+ * Every URL which was redirected from, in the order they were visited.
+ */
+redirects: Sequence<Text>, };
+type Response = { status: number, status_text: Text, url: Text, body: Value, 
+/**
+ * Measurements taken while the request was being sent, if the host
+ * recorded them.
+ */
+metrics?: Metrics, };
+type ApiKeyLocation = "header" | "query";
+type Auth = { "type": "basic", username: Text, password: Text, } | { "type": "bearer", token: Text, } | { "type": "api-key", name: Text, value: Text, location: ApiKeyLocation, } | { "type": "oauth2", token: Text, };
+type Argument = { "type": "node", name: Text, } | { "type": "constant", value: Value, };
+type Operation = "map" | "filter" | "any" | "all" | "count" | "sum" | "min" | "max" | "sort-by" | "group-by" | "unique" | "first" | "last";
+type Lambda = { parameter: Text, body: Sequence<Node>, };
+type SchemaSource = { "type": "inline", schema: unknown, } | { "type": "file", path: Text, };
+type Expression = { "type": "string", value: Text, } | { "type": "request", url: Text, auth?: Auth, 
+/**
+ * How often the request should be re-sent when the workspace is
+ * being monitored (see [`Scheduler`][crate::Scheduler]).
+ */
+refresh?: Duration, response?: Response, error?: EvaluationError, } | { "type": "equals", target: Text, value: Value, } | { "type": "get-property", target: Text, field: Text, } | { "type": "assert", target: Text, message: Text, } | { "type": "variable", name: Text, } | { "type": "secret", name: Text, } | { "type": "oauth2-token", token_url: Text, client_id: Text, client_secret: Text, scope?: Text, response?: Response, error?: EvaluationError, } | { "type": "matches", target: Text, pattern: Text, } | { "type": "capture", target: Text, pattern: Text, group?: Text, } | { "type": "call", function: Text, arguments: Sequence<Argument>, } | { "type": "collection", operation: Operation, target: Text, lambda?: Lambda, } | { "type": "validate-schema", target: Text, schema: SchemaSource, } | { "type": "previous", target: Text, } | { "type": "changed", target: Text, };
+type Node = { name: Text, expression: Expression, };
+type Environment = { [key in Text]?: Text };
+type Type = { "type": "unknown" } | { "type": "number" } | { "type": "string" } | { "type": "boolean" } | { "type": "instant" } | { "type": "duration" } | { "type": "object", 
+/**
+ * The type of each field, or `None` if the object's fields aren't
+ * known.
+ */
+fields?: { [key in Text]?: Type }, } | { "type": "array", 
+/**
+ * The type of every item in the array.
+ */
+item?: Type, };
+type Parameter = { name: Text, 
+/**
+ * The type of value this parameter accepts.
+ */
+type?: Type, };
+type UserFunction = { parameters: Sequence<Parameter>, body: Sequence<Node>, };
+type EvaluationResult = { Ok : Value } | { Err : EvaluationError };
+type TypeResult = { Ok : Type } | { Err : EvaluationError };
+type PreparedRequest = { method: Text, url: Text, query: Array<[Text, Secret]>, headers: Array<[Text, Secret]>, body?: Secret, 
+/**
+ * The [`Expression::OAuth2Token`] node to refresh if the server rejects
+ * the request with a `401 Unauthorized`.
+ */
+refresh?: Text, };
+type PreparedRequestResult = { Ok : PreparedRequest } | { Err : EvaluationError };
+type Change = { "type": "updated", index: number, name: Text, result: { Ok : Value } | { Err : EvaluationError }, } | { "type": "removed", index: number, name: Text, };
+type Diagnostic = { node: Text, message: Text, };
+type Derivation = { "type": "constant" } | { "type": "request-pending" } | { "type": "request-succeeded" } | { "type": "request-failed" } | { "type": "compared", equal: boolean, } | { "type": "property-found" } | { "type": "missing-property", field: Text, } | { "type": "not-an-object" } | { "type": "assertion-passed" } | { "type": "assertion-failed" } | { "type": "not-a-boolean" } | { "type": "matched", matched: boolean, } | { "type": "captured" } | { "type": "nothing-captured" } | { "type": "invalid-pattern" } | { "type": "not-a-string" } | { "type": "called", function: Text, } | { "type": "iterated", operation: Operation, } | { "type": "not-an-array" } | { "type": "validated", valid: boolean, } | { "type": "invalid-schema" } | { "type": "recalled" } | { "type": "no-history" } | { "type": "compared-with-previous", changed: boolean, } | { "type": "secret" } | { "type": "variable", environment: Text | null, } | { "type": "upstream-failed", node: Text, } | { "type": "upstream-indeterminate", node: Text, } | { "type": "unknown-node", name: Text, } | { "type": "cycle", path: Sequence<Text>, };
+type Explanation = { node: Text, expression: Expression, result: { Ok : Value } | { Err : EvaluationError }, derivation: Derivation, 
+/**
+ * Explanations for the nodes that were consulted.
+ */
+inputs: Sequence<Explanation>, };
@@ -1,22 +1,28 @@
-mod expression;
-mod node;
-mod value;
+mod types;
 
-use std::cell::RefCell;
+use std::cell::{Cell, RefCell};
 
-use crate::{
-    expression::Expression,
-    node::Node,
-    value::{laskea_value, Value},
+use crate::types::{
+    Environment, EvaluationResult, Explanation, Expression, Node, PreparedRequestResult, TypeResult,
 };
 
-use laskea_engine::{Evaluate, EvaluateStorage, Inputs, InputsStorage, Sequence};
+use js_sys::{Array, Function};
+use laskea_engine::{
+    ChangeTracker, EditError, Evaluate, EvaluateStorage, Inputs, InputsExt, InputsStorage, Instant,
+    Secret, Sequence,
+};
+use std::{path::Path, sync::Arc};
 use wasm_bindgen::prelude::*;
 
 /// A high-level wrapper around the [`laskea_engine`].
 #[wasm_bindgen]
 #[derive(Default)]
-pub struct Laskea(RefCell<Database>);
+pub struct Laskea {
+    db: RefCell<Database>,
+    tracker: RefCell<ChangeTracker>,
+    subscribers: RefCell<Vec<(u32, Function)>>,
+    next_subscriber: Cell<u32>,
+}
 
 #[wasm_bindgen]
 impl Laskea {
@@ -25,28 +31,270 @@ impl Laskea {
         Laskea::default()
     }
 
-    pub fn evaluate(&self, items: Vec<Node>) -> Result<Vec<Value>, JsValue> {
+    pub fn evaluate(&self, items: Vec<Node>) -> Result<Vec<EvaluationResult>, JsValue> {
         let nodes = items
             .into_iter()
             .map(|n| n.to_rust())
             .collect::<Result<Sequence<_>, _>>()?;
-        self.0.borrow_mut().set_nodes(nodes);
+        self.db.borrow_mut().set_nodes(nodes);
+        self.load_schemas();
+
+        self.notify_subscribers()?;
 
         Ok(self
-            .0
+            .db
             .borrow()
             .evaluate()
             .iter()
             .cloned()
-            .map(|r| laskea_value(r))
+            .map(types::evaluation_result)
             .collect())
     }
+
+    /// Add a node to the end of the list.
+    #[wasm_bindgen(js_name = "addNode")]
+    pub fn add_node(&self, node: Node) -> Result<(), JsValue> {
+        let node = node.to_rust()?;
+        self.edit(|db| db.add_node(node))
+    }
+
+    /// Replace the expression for an existing node.
+    #[wasm_bindgen(js_name = "updateExpression")]
+    pub fn update_expression(&self, name: &str, expression: Expression) -> Result<(), JsValue> {
+        let expr = Arc::new(expression.to_rust()?);
+        self.edit(|db| db.update_expression(name, expr))
+    }
+
+    #[wasm_bindgen(js_name = "renameNode")]
+    pub fn rename_node(&self, name: &str, new_name: &str) -> Result<(), JsValue> {
+        self.edit(|db| db.rename_node(name, new_name.into()))
+    }
+
+    #[wasm_bindgen(js_name = "removeNode")]
+    pub fn remove_node(&self, name: &str) -> Result<(), JsValue> {
+        self.edit(|db| db.remove_node(name).map(drop))
+    }
+
+    /// Move a node so it is at the specified position in the list.
+    #[wasm_bindgen(js_name = "moveNode")]
+    pub fn move_node(&self, name: &str, index: usize) -> Result<(), JsValue> {
+        self.edit(|db| db.move_node(name, index))
+    }
+
+    /// Evaluate a single node.
+    #[wasm_bindgen(js_name = "getValue")]
+    pub fn get_value(&self, name: &str) -> Result<EvaluationResult, JsValue> {
+        let db = self.db.borrow();
+        let expr = db
+            .expression_of(name.into())
+            .ok_or_else(|| EditError::UnknownNode(name.into()).to_string())?;
+
+        let result = db.eval(name.into(), expr);
+        Ok(types::evaluation_result(result))
+    }
+
+    /// Infer the type of a node's value without evaluating it.
+    #[wasm_bindgen(js_name = "typeOf")]
+    pub fn type_of(&self, name: &str) -> Result<TypeResult, JsValue> {
+        let db = self.db.borrow();
+
+        if db.expression_of(name.into()).is_none() {
+            return Err(EditError::UnknownNode(name.into()).to_string().into());
+        }
+
+        Ok(types::type_result(db.type_of(name.into())))
+    }
+
+    /// Work out the URL, headers, and body needed to send a request or
+    /// fetch an OAuth2 token, including any credentials.
+    #[wasm_bindgen(js_name = "prepareRequest")]
+    pub fn prepare_request(&self, name: &str) -> Result<PreparedRequestResult, JsValue> {
+        let db = self.db.borrow();
+
+        if db.expression_of(name.into()).is_none() {
+            return Err(EditError::UnknownNode(name.into()).to_string().into());
+        }
+
+        Ok(types::prepared_request_result(
+            db.prepare_request(name.into()),
+        ))
+    }
+
+    /// Explain how a node's value was derived.
+    pub fn explain(&self, name: &str) -> Result<Explanation, JsValue> {
+        self.db
+            .borrow()
+            .explain(name.into())
+            .map(types::explanation)
+            .ok_or_else(|| EditError::UnknownNode(name.into()).to_string().into())
+    }
+
+    /// The names of every environment.
+    pub fn environments(&self) -> Vec<String> {
+        self.db
+            .borrow()
+            .environments()
+            .keys()
+            .map(|name| name.to_string())
+            .collect()
+    }
+
+    /// Create or replace an environment's variables.
+    #[wasm_bindgen(js_name = "setEnvironment")]
+    pub fn set_environment(&self, name: &str, variables: Environment) -> Result<(), JsValue> {
+        let variables = variables.to_rust()?;
+        self.edit(|db| {
+            db.set_environment(name.into(), variables);
+            Ok(())
+        })
+    }
+
+    #[wasm_bindgen(js_name = "removeEnvironment")]
+    pub fn remove_environment(&self, name: &str) -> Result<(), JsValue> {
+        self.edit(|db| db.remove_environment(name).map(drop))
+    }
+
+    /// Create or replace a user-defined function.
+    #[wasm_bindgen(js_name = "setFunction")]
+    pub fn set_function(
+        &self,
+        name: &str,
+        function: crate::types::Function,
+    ) -> Result<(), JsValue> {
+        let function = function.to_rust()?;
+        self.edit(|db| db.set_function(name.into(), function))
+    }
+
+    #[wasm_bindgen(js_name = "removeFunction")]
+    pub fn remove_function(&self, name: &str) -> Result<(), JsValue> {
+        self.edit(|db| db.remove_function(name).map(drop))
+    }
+
+    /// The environment variables are currently being read from.
+    #[wasm_bindgen(getter = activeEnvironment)]
+    pub fn active_environment(&self) -> Option<String> {
+        self.db
+            .borrow()
+            .active_environment()
+            .map(|name| name.to_string())
+    }
+
+    /// Switch to another environment, re-evaluating any nodes which use its
+    /// variables. Pass `undefined` to deactivate the current environment.
+    #[wasm_bindgen(js_name = "selectEnvironment")]
+    pub fn select_environment(&self, name: Option<String>) -> Result<(), JsValue> {
+        self.edit(|db| db.select_environment(name.as_deref()))
+    }
+
+    /// Provide the value for a secret. Secrets are always redacted when
+    /// passed back to JavaScript.
+    #[wasm_bindgen(js_name = "setSecret")]
+    pub fn set_secret(&self, name: &str, value: &str) -> Result<(), JsValue> {
+        self.edit(|db| {
+            let mut secrets = db.secrets();
+            secrets.insert(name.into(), Secret::new(value));
+            db.set_secrets(secrets);
+            Ok(())
+        })
+    }
+
+    /// Set the time returned by `now()` to an RFC 3339 timestamp, or to the
+    /// current time if no timestamp is provided.
+    #[wasm_bindgen(js_name = "setNow")]
+    pub fn set_now(&self, timestamp: Option<String>) -> Result<(), JsValue> {
+        let now = match timestamp {
+            Some(timestamp) => timestamp
+                .parse()
+                .map_err(|e| js_sys::Error::new(&format!("Invalid time: {}", e)))?,
+            None => current_time(),
+        };
+
+        self.edit(|db| {
+            db.set_now(now);
+            Ok(())
+        })
+    }
+
+    /// Register a callback which will be given an array of `Change`s
+    /// whenever an evaluation changes the value of one or more nodes.
+    ///
+    /// The returned ID can be passed to [`Laskea::unsubscribe()`].
+    pub fn subscribe(&self, callback: Function) -> u32 {
+        let id = self.next_subscriber.get();
+        self.next_subscriber.set(id + 1);
+        self.subscribers.borrow_mut().push((id, callback));
+
+        id
+    }
+
+    pub fn unsubscribe(&self, id: u32) {
+        self.subscribers
+            .borrow_mut()
+            .retain(|(subscriber, _)| *subscriber != id);
+    }
+
+    fn edit(
+        &self,
+        edit: impl FnOnce(&mut Database) -> Result<(), EditError>,
+    ) -> Result<(), JsValue> {
+        edit(&mut self.db.borrow_mut()).map_err(|e| e.to_string())?;
+        self.load_schemas();
+        self.notify_subscribers()
+    }
+
+    /// Try to read any JSON Schema files the nodes use.
+    ///
+    /// Browsers don't give WebAssembly access to the filesystem, so this
+    /// records an error for each file and the nodes using them will report
+    /// it. Use inline schemas instead.
+    fn load_schemas(&self) {
+        laskea_engine::schemas::load_files(&mut *self.db.borrow_mut(), Path::new("."));
+    }
+
+    fn notify_subscribers(&self) -> Result<(), JsValue> {
+        let changes = self.tracker.borrow_mut().update(&*self.db.borrow());
+
+        if changes.is_empty() {
+            return Ok(());
+        }
+
+        let changes: Array = changes
+            .into_iter()
+            .map(|c| JsValue::from(types::change(c)))
+            .collect();
+
+        // Note: clone the callbacks so subscribers can (un)subscribe while
+        // being notified
+        let subscribers = self.subscribers.borrow().clone();
+
+        for (_, callback) in subscribers {
+            callback.call1(&JsValue::NULL, &changes)?;
+        }
+
+        Ok(())
+    }
 }
 
 #[salsa::database(InputsStorage, EvaluateStorage)]
-#[derive(Default)]
 struct Database {
     storage: salsa::Storage<Self>,
 }
 
+impl Default for Database {
+    fn default() -> Self {
+        let mut db = Database {
+            storage: Default::default(),
+        };
+        laskea_engine::init_inputs(&mut db);
+        db.set_now(current_time());
+        db
+    }
+}
+
+/// `Instant::now()` can't read the system clock from WebAssembly, so we ask
+/// JavaScript instead.
+fn current_time() -> Instant {
+    Instant::from_unix_millis(js_sys::Date::now() as i64).unwrap_or(Instant::UNIX_EPOCH)
+}
+
 impl salsa::Database for Database {}
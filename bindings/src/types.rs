@@ -0,0 +1,104 @@
+//! JavaScript versions of the [`laskea_engine`] types.
+//!
+//! The TypeScript declarations in `laskea.d.ts` are generated from the engine
+//! types (see `tests/typescript.rs`), and values are converted using their
+//! `serde` representation, so the two can't drift apart. Secrets are redacted
+//! before anything is handed to JavaScript.
+
+use gloo_utils::format::JsValueSerdeExt;
+use laskea_engine::{EvaluationError, Redact};
+use serde::{de::DeserializeOwned, Serialize};
+use wasm_bindgen::{prelude::wasm_bindgen, JsCast, JsValue};
+
+#[wasm_bindgen(typescript_custom_section)]
+const TYPES: &str = include_str!("laskea.d.ts");
+
+#[wasm_bindgen]
+extern "C" {
+    #[wasm_bindgen(typescript_type = "Expression")]
+    pub type Expression;
+
+    #[wasm_bindgen(typescript_type = "Node")]
+    pub type Node;
+
+    #[wasm_bindgen(typescript_type = "Environment")]
+    pub type Environment;
+
+    #[wasm_bindgen(typescript_type = "UserFunction")]
+    pub type Function;
+
+    #[wasm_bindgen(typescript_type = "EvaluationResult")]
+    pub type EvaluationResult;
+
+    #[wasm_bindgen(typescript_type = "TypeResult")]
+    pub type TypeResult;
+
+    #[wasm_bindgen(typescript_type = "PreparedRequestResult")]
+    pub type PreparedRequestResult;
+
+    #[wasm_bindgen(typescript_type = "Change")]
+    pub type Change;
+
+    #[wasm_bindgen(typescript_type = "Explanation")]
+    pub type Explanation;
+}
+
+impl Expression {
+    pub fn to_rust(&self) -> Result<laskea_engine::Expression, JsValue> {
+        from_js(self)
+    }
+}
+
+impl Node {
+    pub fn to_rust(&self) -> Result<laskea_engine::Node, JsValue> {
+        from_js(self)
+    }
+}
+
+impl Environment {
+    pub fn to_rust(&self) -> Result<laskea_engine::Environment, JsValue> {
+        from_js(self)
+    }
+}
+
+impl Function {
+    pub fn to_rust(&self) -> Result<laskea_engine::Function, JsValue> {
+        from_js(self)
+    }
+}
+
+pub fn evaluation_result(
+    result: Result<laskea_engine::Value, EvaluationError>,
+) -> EvaluationResult {
+    to_js(&result.redacted())
+}
+
+/// The credentials in a prepared request are deliberately left unredacted so
+/// JavaScript can send it.
+pub fn prepared_request_result(
+    result: Result<laskea_engine::PreparedRequest, EvaluationError>,
+) -> PreparedRequestResult {
+    to_js(&result)
+}
+
+pub fn change(change: laskea_engine::Change) -> Change {
+    to_js(&change.redacted())
+}
+
+pub fn type_result(result: Result<laskea_engine::Type, EvaluationError>) -> TypeResult {
+    to_js(&result)
+}
+
+pub fn explanation(explanation: laskea_engine::Explanation) -> Explanation {
+    to_js(&explanation.redacted())
+}
+
+fn from_js<T: DeserializeOwned>(value: &JsValue) -> Result<T, JsValue> {
+    JsValueSerdeExt::into_serde(value).map_err(|e| JsValue::from(e.to_string()))
+}
+
+fn to_js<T: Serialize, J: JsCast>(value: &T) -> J {
+    <JsValue as JsValueSerdeExt>::from_serde(value)
+        .expect("Unable to serialize to JSON")
+        .unchecked_into()
+}
@@ -0,0 +1,21 @@
+//! Make sure the TypeScript declarations are kept in sync with the engine.
+
+use std::path::Path;
+
+#[test]
+fn typescript_declarations_are_up_to_date() {
+    let path = Path::new(env!("CARGO_MANIFEST_DIR")).join("src/laskea.d.ts");
+    let should_be = laskea_engine::typescript_declarations();
+
+    if std::env::var_os("UPDATE_TYPESCRIPT").is_some() {
+        std::fs::write(&path, &should_be).unwrap();
+    }
+
+    let got = std::fs::read_to_string(&path).unwrap();
+    assert_eq!(
+        got,
+        should_be,
+        "{} is out of date. Re-run this test with UPDATE_TYPESCRIPT=1 to regenerate it.",
+        path.display()
+    );
+}
@@ -0,0 +1,285 @@
+use crate::Error;
+use laskea_engine::{Alert, AlertPolicy};
+use std::{
+    fs::OpenOptions,
+    io::Write,
+    path::{Path, PathBuf},
+    process::{Command, Stdio},
+    thread,
+    time::{self, Duration},
+};
+
+/// How long a sink has to deliver an alert before we give up on it, so a
+/// slow webhook or hung command can't stop the monitor.
+const TIMEOUT: Duration = Duration::from_secs(30);
+
+/// The `alerts` section of a workspace file.
+#[derive(Debug, Default, Clone, PartialEq, serde::Deserialize)]
+pub struct AlertConfig {
+    /// Where alerts should be sent.
+    #[serde(default)]
+    pub sinks: Vec<Sink>,
+    #[serde(flatten)]
+    pub policy: AlertPolicy,
+}
+
+/// Somewhere an [`Alert`] can be sent.
+#[derive(Debug, Clone, PartialEq, Eq, serde::Deserialize)]
+#[serde(tag = "type", rename_all = "kebab-case")]
+pub enum Sink {
+    /// Print a human-readable line to stdout.
+    Stdout,
+    /// Append the alert to a file as a line of JSON.
+    File { path: PathBuf },
+    /// `POST` the alert to a URL as JSON.
+    Webhook { url: String },
+    /// Run a shell command, passing the alert to it using `LASKEA_ALERT_*`
+    /// environment variables and as JSON on stdin.
+    Command { command: String },
+}
+
+impl Sink {
+    pub fn send(&self, alert: &Alert) -> Result<(), Error> {
+        match self {
+            Sink::Stdout => {
+                println!("{}", alert);
+                Ok(())
+            }
+            Sink::File { path } => append(path, alert)
+                .map_err(|e| format!("Unable to write to \"{}\": {}", path.display(), e).into()),
+            Sink::Webhook { url } => {
+                let body = serde_json::to_string(alert)?;
+                ureq::AgentBuilder::new()
+                    .timeout(TIMEOUT)
+                    .build()
+                    .post(url)
+                    .set("Content-Type", "application/json")
+                    .send_string(&body)
+                    .map_err(|e| format!("Unable to send the alert to \"{}\": {}", url, e))?;
+                Ok(())
+            }
+            Sink::Command { command } => run(command, alert, TIMEOUT),
+        }
+    }
+}
+
+/// Send an alert to every sink, reporting any which couldn't be reached on
+/// stderr so one broken sink doesn't stop the others.
+pub fn notify(sinks: &[Sink], alert: &Alert) {
+    for sink in sinks {
+        if let Err(e) = sink.send(alert) {
+            eprintln!("Warning: {}", e);
+        }
+    }
+}
+
+fn append(path: &Path, alert: &Alert) -> Result<(), Error> {
+    let mut file = OpenOptions::new().create(true).append(true).open(path)?;
+    let mut line = serde_json::to_vec(alert)?;
+    line.push(b'\n');
+    file.write_all(&line)?;
+
+    Ok(())
+}
+
+/// Run a command, killing it if it hasn't finished within `timeout`.
+fn run(command: &str, alert: &Alert, timeout: Duration) -> Result<(), Error> {
+    let (shell, flag) = if cfg!(windows) {
+        ("cmd", "/C")
+    } else {
+        ("sh", "-c")
+    };
+    let kind = serde_json::to_value(alert.kind)?;
+    let input = serde_json::to_vec(alert)?;
+
+    let deadline = time::Instant::now() + timeout;
+    let mut child = Command::new(shell)
+        .arg(flag)
+        .arg(command)
+        .env("LASKEA_ALERT_NODE", &*alert.node)
+        .env("LASKEA_ALERT_KIND", kind.as_str().unwrap_or_default())
+        .env("LASKEA_ALERT_MESSAGE", &*alert.message)
+        .env("LASKEA_ALERT_TIMESTAMP", alert.timestamp.to_string())
+        .stdin(Stdio::piped())
+        .spawn()
+        .map_err(|e| format!("Unable to run \"{}\": {}", command, e))?;
+
+    if let Some(mut stdin) = child.stdin.take() {
+        // Write from another thread so a command which never reads its input
+        // can't block us past the deadline
+        thread::spawn(move || {
+            // The command is free to ignore its input
+            let _ = stdin.write_all(&input);
+            // Closing stdin lets the command know there's nothing more to read
+            drop(stdin);
+        });
+    }
+
+    let status = loop {
+        if let Some(status) = child.try_wait()? {
+            break status;
+        }
+
+        if time::Instant::now() >= deadline {
+            let _ = child.kill();
+            let _ = child.wait();
+            return Err(format!("\"{}\" timed out after {:?}", command, timeout).into());
+        }
+
+        thread::sleep(Duration::from_millis(10));
+    };
+
+    if status.success() {
+        Ok(())
+    } else {
+        Err(format!("\"{}\" failed ({})", command, status).into())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use laskea_engine::{AlertKind, Instant};
+    use serde_json::json;
+    use tiny_http::Server;
+
+    fn alert() -> Alert {
+        Alert {
+            node: "health".into(),
+            kind: AlertKind::Errored,
+            message: "Unable to send the request".into(),
+            timestamp: Instant::UNIX_EPOCH,
+        }
+    }
+
+    fn temp_dir(name: &str) -> PathBuf {
+        let dir = std::env::temp_dir().join(format!("laskea-{}-{}", name, std::process::id()));
+        let _ = std::fs::remove_dir_all(&dir);
+        std::fs::create_dir_all(&dir).unwrap();
+        dir
+    }
+
+    #[test]
+    fn parse_the_alerts_section() {
+        let config: AlertConfig = serde_json::from_value(json!({
+            "sinks": [
+                { "type": "stdout" },
+                { "type": "webhook", "url": "http://localhost:8000/alerts" },
+            ],
+            "debounce": 60000,
+        }))
+        .unwrap();
+
+        assert_eq!(
+            config.sinks,
+            [
+                Sink::Stdout,
+                Sink::Webhook {
+                    url: "http://localhost:8000/alerts".into()
+                }
+            ]
+        );
+        assert_eq!(config.policy.debounce.as_millis(), 60_000);
+        assert!(config.policy.recovery);
+    }
+
+    #[test]
+    fn append_to_a_file() {
+        let path = temp_dir("alert-file").join("alerts.log");
+        let sink = Sink::File { path: path.clone() };
+
+        sink.send(&alert()).unwrap();
+        sink.send(&alert()).unwrap();
+
+        let log = std::fs::read_to_string(&path).unwrap();
+        let lines: Vec<Alert> = log
+            .lines()
+            .map(|line| serde_json::from_str(line).unwrap())
+            .collect();
+        assert_eq!(lines, [alert(), alert()]);
+    }
+
+    #[test]
+    fn post_to_a_webhook() {
+        let server = Server::http("127.0.0.1:0").unwrap();
+        let url = format!("http://{}/alerts", server.server_addr().to_ip().unwrap());
+        let handle = std::thread::spawn(move || {
+            let mut request = server.recv().unwrap();
+            let mut body = String::new();
+            request.as_reader().read_to_string(&mut body).unwrap();
+            let method = request.method().to_string();
+            request.respond(tiny_http::Response::empty(204)).unwrap();
+            (method, body)
+        });
+
+        Sink::Webhook { url }.send(&alert()).unwrap();
+
+        let (method, body) = handle.join().unwrap();
+        assert_eq!(method, "POST");
+        let got: serde_json::Value = serde_json::from_str(&body).unwrap();
+        assert_eq!(
+            got,
+            json!({
+                "node": "health",
+                "kind": "errored",
+                "message": "Unable to send the request",
+                "timestamp": "1970-01-01T00:00:00Z",
+            })
+        );
+    }
+
+    #[test]
+    #[cfg(unix)]
+    fn run_a_shell_command() {
+        let path = temp_dir("alert-command").join("alert.txt");
+        let sink = Sink::Command {
+            command: format!(
+                "echo \"$LASKEA_ALERT_KIND $LASKEA_ALERT_NODE\" > '{}'",
+                path.display()
+            ),
+        };
+
+        sink.send(&alert()).unwrap();
+
+        assert_eq!(std::fs::read_to_string(&path).unwrap(), "errored health\n");
+    }
+
+    #[test]
+    #[cfg(unix)]
+    fn failing_commands_are_errors() {
+        let sink = Sink::Command {
+            command: String::from("exit 3"),
+        };
+
+        let err = sink.send(&alert()).unwrap_err();
+
+        assert_eq!(err.to_string(), "\"exit 3\" failed (exit status: 3)");
+    }
+
+    #[test]
+    #[cfg(unix)]
+    fn slow_commands_are_killed() {
+        let started = time::Instant::now();
+
+        let err = run("sleep 10", &alert(), Duration::from_millis(100)).unwrap_err();
+
+        assert_eq!(err.to_string(), "\"sleep 10\" timed out after 100ms");
+        assert!(started.elapsed() < Duration::from_secs(5));
+    }
+
+    #[test]
+    #[cfg(unix)]
+    fn commands_which_never_read_their_input_are_killed() {
+        // Too big to fit in the pipe's buffer
+        let alert = Alert {
+            message: "x".repeat(100_000).into(),
+            ..alert()
+        };
+        let started = time::Instant::now();
+
+        let err = run("sleep 10", &alert, Duration::from_millis(100)).unwrap_err();
+
+        assert_eq!(err.to_string(), "\"sleep 10\" timed out after 100ms");
+        assert!(started.elapsed() < Duration::from_secs(5));
+    }
+}
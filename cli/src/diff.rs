@@ -0,0 +1,80 @@
+use crate::{workspace, Error};
+use clap::{Parser, ValueEnum};
+use laskea_engine::NodeChange;
+use std::{fmt::Write, path::PathBuf};
+
+#[derive(Debug, Parser)]
+pub struct Diff {
+    /// The original workspace file.
+    before: PathBuf,
+    /// The changed workspace file.
+    after: PathBuf,
+    #[arg(short, long, value_enum, default_value_t = Format::Summary)]
+    format: Format,
+}
+
+#[derive(Debug, Copy, Clone, PartialEq, Eq, ValueEnum)]
+enum Format {
+    /// One line for each change.
+    Summary,
+    /// Each change as a line of JSON.
+    Json,
+}
+
+impl Diff {
+    pub fn run(self) -> Result<(), Error> {
+        let (_, before) = workspace::read_nodes(&self.before)?;
+        let (_, after) = workspace::read_nodes(&self.after)?;
+        let changes = laskea_engine::diff(&before, &after);
+
+        print!("{}", render(&changes, self.format)?);
+        Ok(())
+    }
+}
+
+fn render(changes: &[NodeChange], format: Format) -> Result<String, Error> {
+    let mut output = String::new();
+
+    for change in changes {
+        match format {
+            Format::Summary => writeln!(output, "{}", change)?,
+            Format::Json => writeln!(output, "{}", serde_json::to_string(change)?)?,
+        }
+    }
+
+    Ok(output)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use laskea_engine::{Expression, Node};
+
+    #[test]
+    fn render_changes() {
+        let changes = vec![
+            NodeChange::Renamed {
+                from: "url".into(),
+                to: "base-url".into(),
+            },
+            NodeChange::Removed {
+                node: Node {
+                    name: "check".into(),
+                    expr: Expression::assert("is-ok", "Expected a 200").into(),
+                },
+            },
+        ];
+
+        let summary = render(&changes, Format::Summary).unwrap();
+        let json = render(&changes, Format::Json).unwrap();
+
+        assert_eq!(
+            summary,
+            "Renamed \"url\" to \"base-url\"\nRemoved \"check\"\n"
+        );
+        assert_eq!(
+            json.lines().next().unwrap(),
+            r#"{"type":"renamed","from":"url","to":"base-url"}"#
+        );
+    }
+}
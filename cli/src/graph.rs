@@ -0,0 +1,65 @@
+use crate::{workspace::WorkspaceArgs, Error};
+use clap::{Parser, ValueEnum};
+use laskea_engine::{DependencyGraph, Evaluate};
+
+#[derive(Debug, Parser)]
+pub struct Graph {
+    #[command(flatten)]
+    workspace: WorkspaceArgs,
+    #[arg(short, long, value_enum, default_value_t = Format::Dot)]
+    format: Format,
+    /// Include each node's current value or error.
+    #[arg(short, long)]
+    annotate: bool,
+}
+
+#[derive(Debug, Copy, Clone, PartialEq, Eq, ValueEnum)]
+enum Format {
+    /// Graphviz's DOT language.
+    Dot,
+    /// A Mermaid flowchart.
+    Mermaid,
+}
+
+impl Graph {
+    pub fn run(self) -> Result<(), Error> {
+        let db = self.workspace.load()?;
+        print!("{}", render(&db, self.format, self.annotate));
+        Ok(())
+    }
+}
+
+fn render(db: &dyn Evaluate, format: Format, annotate: bool) -> String {
+    let mut graph = DependencyGraph::new(db);
+
+    if annotate {
+        graph = graph.with_results(db);
+    }
+
+    match format {
+        Format::Dot => graph.to_dot(),
+        Format::Mermaid => graph.to_mermaid(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::workspace::Database;
+    use laskea_engine::{Expression, Node};
+
+    #[test]
+    fn annotated_mermaid() {
+        let db = Database::with_nodes(vec![
+            Node::new("greeting", Expression::string("Hello")),
+            Node::new("is-hello", Expression::equals("greeting", "Hello")),
+        ]);
+
+        let got = render(&db, Format::Mermaid, true);
+
+        assert_eq!(
+            got,
+            "flowchart LR\n    n0[\"greeting<br/>= #quot;Hello#quot;\"]\n    n1[\"is-hello<br/>= true\"]\n    n0 --> n1\n"
+        );
+    }
+}
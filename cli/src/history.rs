@@ -0,0 +1,115 @@
+use crate::Error;
+use clap::Parser;
+use laskea_engine::{History, Instant};
+use std::{io::Write, path::PathBuf};
+
+#[derive(Debug, Parser)]
+pub struct HistoryArgs {
+    /// The SQLite database the results were recorded in (see `laskea monitor
+    /// --history`).
+    history: PathBuf,
+    /// The node to look up.
+    node: String,
+    /// Print the percentage of checks the node passed instead of its
+    /// results.
+    #[arg(long)]
+    uptime: bool,
+    /// Only include results recorded at or after this RFC 3339 timestamp.
+    #[arg(long)]
+    since: Option<Instant>,
+}
+
+impl HistoryArgs {
+    pub fn run(self) -> Result<(), Error> {
+        let history = History::open(&self.history)?;
+        let stdout = std::io::stdout();
+
+        print_history(&history, &self, &mut stdout.lock())
+    }
+}
+
+fn print_history(history: &History, args: &HistoryArgs, out: &mut dyn Write) -> Result<(), Error> {
+    if args.uptime {
+        match history.uptime(&args.node, args.since)? {
+            Some(percent) => writeln!(out, "{:.2}%", percent)?,
+            None => return Err(format!("\"{}\" has never been checked", args.node).into()),
+        }
+
+        return Ok(());
+    }
+
+    let since = args.since.unwrap_or(Instant::UNIX_EPOCH);
+
+    for entry in history.entries(&args.node)? {
+        if entry.timestamp >= since {
+            serde_json::to_writer(&mut *out, &entry)?;
+            writeln!(out)?;
+        }
+    }
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::workspace::Database;
+    use laskea_engine::{Expression, Node};
+
+    fn args(uptime: bool, since: Option<&str>) -> HistoryArgs {
+        HistoryArgs {
+            history: PathBuf::new(),
+            node: String::from("assertion"),
+            uptime,
+            since: since.map(|s| s.parse().unwrap()),
+        }
+    }
+
+    fn history() -> History {
+        let mut history = History::in_memory().unwrap();
+
+        for (seconds, status) in [(0, "up"), (60, "down")] {
+            let db = Database::with_nodes(vec![
+                Node::new("status", Expression::string(status)),
+                Node::new("is-up", Expression::equals("status", "up")),
+                Node::new(
+                    "assertion",
+                    Expression::assert("is-up", "The service is down"),
+                ),
+            ]);
+            let timestamp = Instant::from_unix_millis(seconds * 1000).unwrap();
+            history.record(&db, timestamp).unwrap();
+        }
+
+        history
+    }
+
+    #[test]
+    fn print_each_result() {
+        let mut out = Vec::new();
+
+        print_history(
+            &history(),
+            &args(false, Some("1970-01-01T00:01:00Z")),
+            &mut out,
+        )
+        .unwrap();
+
+        assert_eq!(
+            String::from_utf8(out).unwrap(),
+            concat!(
+                r#"{"timestamp":"1970-01-01T00:01:00Z","result":{"Err":{"type":"failed","message":"The service is down"}}}"#,
+                "\n"
+            )
+        );
+    }
+
+    #[test]
+    fn print_the_uptime() {
+        let mut out = Vec::new();
+
+        print_history(&history(), &args(true, None), &mut out).unwrap();
+
+        assert_eq!(String::from_utf8(out).unwrap(), "50.00%\n");
+    }
+}
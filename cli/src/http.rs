@@ -0,0 +1,546 @@
+//! Sending the HTTP requests for a workspace's [`Expression::Request`] and
+//! [`Expression::OAuth2Token`] nodes.
+
+use laskea_engine::{
+    Evaluate, EvaluationError, Expression, InputsExt, Instant, Metrics, PreparedRequest, Response,
+    Secret, Text, Value,
+};
+use std::{
+    collections::BTreeMap,
+    io,
+    net::{SocketAddr, ToSocketAddrs},
+    sync::{Arc, Mutex},
+    time,
+};
+use url::Url;
+
+/// The number of redirects to follow before giving up.
+const MAX_REDIRECTS: usize = 5;
+
+/// Send every pending request in the workspace, storing the responses.
+///
+/// Token requests are sent first so their access tokens are available to the
+/// requests which use them. If a request authenticated with an OAuth2 token
+/// is rejected with a `401 Unauthorized`, the token is fetched again and the
+/// request is retried once.
+pub fn fetch_all<DB>(db: &mut DB)
+where
+    DB: Evaluate + InputsExt,
+{
+    let client = Client::new();
+
+    fetch_tokens(db, &client);
+
+    for name in pending(db, |expr| matches!(expr, Expression::Request { .. })) {
+        resend(db, &client, &name);
+    }
+}
+
+/// Fetch every OAuth2 token which hasn't been fetched yet.
+pub(crate) fn fetch_tokens<DB>(db: &mut DB, client: &Client)
+where
+    DB: Evaluate + InputsExt,
+{
+    for name in pending(db, |expr| matches!(expr, Expression::OAuth2Token { .. })) {
+        fetch(db, client, &name);
+    }
+}
+
+/// Send a request and store its response, fetching a new OAuth2 token and
+/// retrying once if the request is rejected with a `401 Unauthorized`.
+pub(crate) fn resend<DB>(db: &mut DB, client: &Client, name: &Text)
+where
+    DB: Evaluate + InputsExt,
+{
+    let response = fetch(db, client, name);
+
+    if let (
+        Some(401),
+        Ok(PreparedRequest {
+            refresh: Some(token),
+            ..
+        }),
+    ) = (response, db.prepare_request(name.clone()))
+    {
+        fetch(db, client, &token);
+        fetch(db, client, name);
+    }
+}
+
+/// The names of nodes which haven't received a response yet.
+fn pending(db: &dyn Evaluate, predicate: impl Fn(&Expression) -> bool) -> Vec<Text> {
+    db.nodes()
+        .iter()
+        .filter(|node| predicate(&node.expr))
+        .filter(|node| match &*node.expr {
+            Expression::Request {
+                response, error, ..
+            }
+            | Expression::OAuth2Token {
+                response, error, ..
+            } => response.is_none() && error.is_none(),
+            _ => false,
+        })
+        .map(|node| node.name.clone())
+        .collect()
+}
+
+/// Send the request for a node and store the outcome, returning the status
+/// code if the server responded.
+fn fetch<DB>(db: &mut DB, client: &Client, name: &Text) -> Option<i32>
+where
+    DB: Evaluate + InputsExt,
+{
+    let result = db
+        .prepare_request(name.clone())
+        .and_then(|request| client.send(&request));
+    let status = result.as_ref().ok().map(|r| r.status);
+
+    let expression = db.expression_of(name.clone())?;
+    let mut expr = Expression::clone(&expression);
+
+    match &mut expr {
+        Expression::Request {
+            response, error, ..
+        }
+        | Expression::OAuth2Token {
+            response, error, ..
+        } => match result {
+            Ok(r) => {
+                *response = Some(r);
+                *error = None;
+            }
+            Err(e) => {
+                *response = None;
+                *error = Some(e);
+            }
+        },
+        _ => return None,
+    }
+
+    db.update_expression(name, Arc::new(expr)).ok()?;
+
+    status
+}
+
+/// A HTTP client which records the [`Metrics`] for each request.
+pub(crate) struct Client {
+    agent: ureq::Agent,
+    resolver: TimedResolver,
+}
+
+impl Client {
+    pub(crate) fn new() -> Self {
+        let resolver = TimedResolver::default();
+        let agent = ureq::AgentBuilder::new()
+            .timeout(time::Duration::from_secs(30))
+            .resolver(resolver.clone())
+            // Redirects are followed manually so we can record them
+            .redirects(0)
+            .build();
+
+        Client { agent, resolver }
+    }
+
+    fn send(&self, request: &PreparedRequest) -> Result<Response, EvaluationError> {
+        let timestamp = Instant::now();
+        let start = time::Instant::now();
+        self.resolver.take();
+
+        let mut method = request.method.to_string();
+        let mut url = request.url.to_string();
+        let mut query = request.query.as_slice();
+        let mut headers = request.headers.as_slice();
+        let mut body = request.body.as_ref();
+        let mut redirects = Vec::new();
+        let mut dns: Option<time::Duration> = None;
+
+        let (response, ttfb) = loop {
+            let sent = time::Instant::now();
+            let response = self.send_once(&method, &url, query, headers, body)?;
+            let ttfb = sent.elapsed();
+
+            if let Some(lookup) = self.resolver.take() {
+                dns = Some(dns.unwrap_or_default() + lookup);
+            }
+
+            let location = match response.header("Location") {
+                Some(location) if (300..400).contains(&response.status()) => location,
+                _ => break (response, ttfb),
+            };
+
+            if redirects.len() >= MAX_REDIRECTS {
+                let msg = format!("Stopped after following {} redirects", MAX_REDIRECTS);
+                return Err(msg.into());
+            }
+
+            let current =
+                Url::parse(response.get_url()).map_err(|e| format!("Invalid URL: {}", e))?;
+            let next = current
+                .join(location)
+                .map_err(|e| format!("Unable to follow the redirect to \"{}\": {}", location, e))?;
+
+            // The query parameters are already part of the new URL, and
+            // headers may contain credentials which shouldn't be sent to
+            // another server
+            query = &[];
+            if next.host_str() != current.host_str() {
+                headers = &[];
+            }
+
+            let status = response.status();
+            if status == 303 || (matches!(status, 301 | 302) && method != "HEAD") {
+                method = String::from("GET");
+                body = None;
+            }
+
+            redirects.push(Text::from(std::mem::replace(&mut url, next.to_string())));
+        };
+
+        let status = i32::from(response.status());
+        let status_text = Text::from(response.status_text());
+        let url = Text::from(response.get_url());
+        let body = response
+            .into_string()
+            .map_err(|e| format!("Unable to read the response: {}", e))?;
+
+        let metrics = Metrics {
+            timestamp,
+            total: milliseconds(start.elapsed()),
+            ttfb: Some(milliseconds(ttfb)),
+            dns: dns.map(milliseconds),
+            // ureq doesn't tell us how long it took to connect
+            connect: None,
+            size: body.len() as u64,
+            redirects: redirects.into(),
+        };
+
+        Ok(Response {
+            status,
+            status_text,
+            url,
+            body: parse_body(&body),
+            metrics: Some(metrics),
+        })
+    }
+
+    fn send_once(
+        &self,
+        method: &str,
+        url: &str,
+        query: &[(Text, Secret)],
+        headers: &[(Text, Secret)],
+        body: Option<&Secret>,
+    ) -> Result<ureq::Response, EvaluationError> {
+        let mut req = self.agent.request(method, url);
+
+        for (key, value) in query {
+            req = req.query(key, value.expose());
+        }
+        for (key, value) in headers {
+            req = req.set(key, value.expose());
+        }
+
+        let result = match body {
+            Some(body) => req.send_string(body.expose()),
+            None => req.call(),
+        };
+
+        match result {
+            Ok(response) | Err(ureq::Error::Status(_, response)) => Ok(response),
+            Err(ureq::Error::Transport(e)) => {
+                Err(format!("Unable to send the request: {}", e).into())
+            }
+        }
+    }
+}
+
+/// A DNS resolver which records how long its last lookup took.
+///
+/// Lookups are skipped when ureq reuses a pooled connection, so no time is
+/// recorded.
+#[derive(Debug, Default, Clone)]
+struct TimedResolver {
+    last_lookup: Arc<Mutex<Option<time::Duration>>>,
+}
+
+impl TimedResolver {
+    fn take(&self) -> Option<time::Duration> {
+        self.last_lookup.lock().unwrap().take()
+    }
+}
+
+impl ureq::Resolver for TimedResolver {
+    fn resolve(&self, netloc: &str) -> io::Result<Vec<SocketAddr>> {
+        let start = time::Instant::now();
+        let addresses = netloc.to_socket_addrs()?.collect();
+        *self.last_lookup.lock().unwrap() = Some(start.elapsed());
+
+        Ok(addresses)
+    }
+}
+
+fn milliseconds(duration: time::Duration) -> laskea_engine::Duration {
+    laskea_engine::Duration::from_millis(duration.as_millis().try_into().unwrap_or(i64::MAX))
+}
+
+/// Interpret a response body, treating it as JSON if possible.
+///
+/// JSON values which can't be represented as a [`Value`] (e.g. `null`) are
+/// kept as JSON text.
+fn parse_body(body: &str) -> Value {
+    match serde_json::from_str(body) {
+        Ok(json) => json_to_value(json),
+        Err(_) => Value::from(body),
+    }
+}
+
+fn json_to_value(json: serde_json::Value) -> Value {
+    match json {
+        serde_json::Value::Bool(b) => Value::from(b),
+        serde_json::Value::String(s) => Value::from(s),
+        serde_json::Value::Number(n) => match n.as_i64().map(i32::try_from) {
+            Some(Ok(n)) => Value::from(n),
+            _ => Value::from(n.to_string()),
+        },
+        serde_json::Value::Object(obj) => {
+            let fields: BTreeMap<Text, Value> = obj
+                .into_iter()
+                .map(|(key, value)| (Text::from(key), json_to_value(value)))
+                .collect();
+            Value::Object(fields.into())
+        }
+        serde_json::Value::Array(items) => {
+            Value::Array(items.into_iter().map(json_to_value).collect())
+        }
+        serde_json::Value::Null => Value::from("null"),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::workspace::Database;
+    use laskea_engine::{Auth, Inputs, Node, Secret};
+    use std::{
+        sync::atomic::{AtomicUsize, Ordering},
+        thread::JoinHandle,
+    };
+    use tiny_http::{Header, Server};
+
+    /// A local server with an OAuth2 token endpoint which hands out a new
+    /// token each time it is called, and an API which only accepts the
+    /// latest one.
+    struct MockServer {
+        url: String,
+        token_requests: Arc<AtomicUsize>,
+        handle: Option<JoinHandle<()>>,
+        server: Arc<Server>,
+    }
+
+    impl MockServer {
+        fn start() -> Self {
+            let server = Arc::new(Server::http("127.0.0.1:0").unwrap());
+            let url = format!("http://{}", server.server_addr().to_ip().unwrap());
+            let token_requests = Arc::new(AtomicUsize::new(0));
+
+            let handle = std::thread::spawn({
+                let server = Arc::clone(&server);
+                let token_requests = Arc::clone(&token_requests);
+                move || {
+                    for mut request in server.incoming_requests() {
+                        let mut body = String::new();
+                        let _ = request.as_reader().read_to_string(&mut body);
+
+                        if request.url() == "/redirect" {
+                            let location = Header::from_bytes("Location", "/health").unwrap();
+                            let response = tiny_http::Response::empty(302).with_header(location);
+                            let _ = request.respond(response);
+                            continue;
+                        }
+
+                        let (status, reply) = match request.url() {
+                            "/token" if body.contains("client_secret=hunter2") => {
+                                let n = token_requests.fetch_add(1, Ordering::SeqCst) + 1;
+                                (200, format!(r#"{{"access_token": "token-{}"}}"#, n))
+                            }
+                            "/token" => (401, String::from(r#"{"error": "invalid_client"}"#)),
+                            _ => {
+                                let authorized = request.headers().iter().any(|h| {
+                                    h.field.equiv("Authorization") && h.value == "Bearer token-2"
+                                });
+                                if authorized {
+                                    (200, String::from(r#"{"status": "up", "items": [1, 2]}"#))
+                                } else {
+                                    (401, String::from("Unauthorized"))
+                                }
+                            }
+                        };
+
+                        let header =
+                            Header::from_bytes("Content-Type", "application/json").unwrap();
+                        let response = tiny_http::Response::from_string(reply)
+                            .with_status_code(status)
+                            .with_header(header);
+                        let _ = request.respond(response);
+                    }
+                }
+            });
+
+            MockServer {
+                url,
+                token_requests,
+                handle: Some(handle),
+                server,
+            }
+        }
+    }
+
+    impl Drop for MockServer {
+        fn drop(&mut self) {
+            self.server.unblock();
+            if let Some(handle) = self.handle.take() {
+                let _ = handle.join();
+            }
+        }
+    }
+
+    fn database(server: &MockServer, client_secret: &str) -> Database {
+        let mut db = Database::with_nodes(vec![
+            Node::new("client-id", Expression::string("laskea")),
+            Node::new("client-secret", Expression::secret("client-secret")),
+            Node::new(
+                "token",
+                Expression::OAuth2Token {
+                    token_url: format!("{}/token", server.url).into(),
+                    client_id: "client-id".into(),
+                    client_secret: "client-secret".into(),
+                    scope: None,
+                    response: None,
+                    error: None,
+                },
+            ),
+            Node::new(
+                "health",
+                Expression::Request {
+                    url: format!("{}/health", server.url).into(),
+                    auth: Some(Auth::OAuth2 {
+                        token: "token".into(),
+                    }),
+                    refresh: None,
+                    response: None,
+                    error: None,
+                },
+            ),
+        ]);
+        db.set_secrets(
+            vec![(Text::from("client-secret"), Secret::new(client_secret))]
+                .into_iter()
+                .collect(),
+        );
+        db
+    }
+
+    #[test]
+    fn expired_tokens_are_refreshed() {
+        let server = MockServer::start();
+        let mut db = database(&server, "hunter2");
+
+        fetch_all(&mut db);
+
+        assert_eq!(server.token_requests.load(Ordering::SeqCst), 2);
+        assert_eq!(db.evaluate()[2], Ok(Value::Secret(Secret::new("token-2"))));
+        let health = match db.evaluate()[3].clone().unwrap() {
+            Value::Object(obj) => obj,
+            other => panic!("Expected an object, found {}", other),
+        };
+        assert_eq!(health.get("status"), Some(&Value::from(200)));
+        let body = match health.get("body") {
+            Some(Value::Object(body)) => body.clone(),
+            other => panic!("Expected an object, found {:?}", other),
+        };
+        assert_eq!(body.get("status"), Some(&Value::from("up")));
+        assert_eq!(
+            body.get("items"),
+            Some(&Value::from(vec![Value::from(1), Value::from(2)]))
+        );
+    }
+
+    #[test]
+    fn record_metrics_and_redirects() {
+        let server = MockServer::start();
+        let mut db = database(&server, "hunter2");
+        let mut nodes = db.nodes().to_vec();
+        nodes[3] = Node::new(
+            "health",
+            Expression::Request {
+                url: format!("{}/redirect", server.url).into(),
+                auth: Some(Auth::OAuth2 {
+                    token: "token".into(),
+                }),
+                refresh: None,
+                response: None,
+                error: None,
+            },
+        );
+        db.set_nodes(nodes.into());
+
+        fetch_all(&mut db);
+
+        let health = match db.evaluate()[3].clone().unwrap() {
+            Value::Object(obj) => obj,
+            other => panic!("Expected an object, found {}", other),
+        };
+        assert_eq!(health.get("status"), Some(&Value::from(200)));
+        assert_eq!(
+            health.get("url"),
+            Some(&Value::from(format!("{}/health", server.url)))
+        );
+        assert_eq!(
+            health.get("redirects"),
+            Some(&Value::from(vec![Value::from(format!(
+                "{}/redirect",
+                server.url
+            ))]))
+        );
+        assert_eq!(
+            health.get("size_bytes"),
+            Some(&Value::from(
+                r#"{"status": "up", "items": [1, 2]}"#.len() as i32
+            ))
+        );
+        assert!(matches!(health.get("latency_ms"), Some(Value::Number(n)) if *n >= 0));
+        assert!(matches!(health.get("ttfb_ms"), Some(Value::Number(_))));
+        assert!(matches!(health.get("timestamp"), Some(Value::Instant(_))));
+    }
+
+    #[test]
+    fn rejected_client_credentials() {
+        let server = MockServer::start();
+        let mut db = database(&server, "wrong");
+
+        fetch_all(&mut db);
+
+        assert_eq!(server.token_requests.load(Ordering::SeqCst), 0);
+        assert_eq!(
+            db.evaluate()[2],
+            Err(EvaluationError::from(
+                "The token request failed with 401 Unauthorized"
+            ))
+        );
+        assert_eq!(
+            db.evaluate()[3],
+            Err(EvaluationError::UpstreamFailed {
+                node: "token".into(),
+                message: "The token request failed with 401 Unauthorized".into(),
+            })
+        );
+    }
+
+    #[test]
+    fn non_json_bodies_are_strings() {
+        assert_eq!(parse_body("Hello, World!"), Value::from("Hello, World!"));
+        assert_eq!(parse_body("42"), Value::from(42));
+        assert_eq!(parse_body("null"), Value::from("null"));
+    }
+}
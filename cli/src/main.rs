@@ -0,0 +1,69 @@
+//! The `laskea` command-line tool.
+
+#[cfg(test)]
+#[macro_use]
+extern crate pretty_assertions;
+
+mod alerts;
+mod diff;
+mod graph;
+mod history;
+mod http;
+mod merge;
+mod monitor;
+mod test;
+mod workspace;
+
+use clap::{Parser, Subcommand};
+use std::process::ExitCode;
+
+type Error = Box<dyn std::error::Error>;
+
+#[derive(Debug, Parser)]
+#[command(version, about = "Work with laskea workspaces from the command-line")]
+struct Args {
+    #[command(subcommand)]
+    command: Command,
+}
+
+#[derive(Debug, Subcommand)]
+enum Command {
+    /// Show how the nodes in two versions of a workspace differ.
+    Diff(diff::Diff),
+    /// Render the dependency graph for a workspace.
+    Graph(graph::Graph),
+    /// Show the results recorded for a node while monitoring a workspace.
+    History(history::HistoryArgs),
+    /// Three-way merge two versions of a workspace.
+    ///
+    /// The arguments are in the order git passes them to a merge driver, so
+    /// `laskea merge %O %A %B --output %A` can be used to merge workspaces
+    /// automatically.
+    Merge(merge::Merge),
+    /// Keep re-sending requests with a refresh interval, printing each
+    /// change as a line of JSON and sending alerts when nodes start failing.
+    Monitor(monitor::Monitor),
+    /// Check a workspace's assertions, reporting the results.
+    Test(test::Test),
+}
+
+fn main() -> ExitCode {
+    let Args { command } = Args::parse();
+
+    let result = match command {
+        Command::Diff(d) => d.run(),
+        Command::Graph(g) => g.run(),
+        Command::History(h) => h.run(),
+        Command::Merge(m) => m.run(),
+        Command::Monitor(m) => m.run(),
+        Command::Test(t) => t.run(),
+    };
+
+    match result {
+        Ok(()) => ExitCode::SUCCESS,
+        Err(e) => {
+            eprintln!("Error: {}", e);
+            ExitCode::FAILURE
+        }
+    }
+}
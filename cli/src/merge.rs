@@ -0,0 +1,181 @@
+use crate::{workspace, Error};
+use clap::Parser;
+use laskea_engine::{Conflict, Node};
+use serde_json::{Map, Value};
+use std::{collections::BTreeSet, path::PathBuf};
+
+#[derive(Debug, Parser)]
+pub struct Merge {
+    /// The common ancestor of both versions.
+    base: PathBuf,
+    /// Our version of the workspace.
+    ours: PathBuf,
+    /// Their version of the workspace.
+    theirs: PathBuf,
+    /// Where to write the merged workspace, instead of stdout.
+    #[arg(short, long)]
+    output: Option<PathBuf>,
+}
+
+impl Merge {
+    pub fn run(self) -> Result<(), Error> {
+        let base = workspace::read_nodes(&self.base)?;
+        let ours = workspace::read_nodes(&self.ours)?;
+        let theirs = workspace::read_nodes(&self.theirs)?;
+
+        let Outcome { merged, conflicts } = merge(base, ours, theirs);
+        let json = serde_json::to_string_pretty(&merged)? + "\n";
+
+        match &self.output {
+            Some(path) => std::fs::write(path, json)
+                .map_err(|e| format!("Unable to write \"{}\": {}", path.display(), e))?,
+            None => print!("{}", json),
+        }
+
+        if conflicts.is_empty() {
+            Ok(())
+        } else {
+            for conflict in &conflicts {
+                eprintln!("Conflict: {}", conflict);
+            }
+            Err(format!("{} conflicts need to be resolved by hand", conflicts.len()).into())
+        }
+    }
+}
+
+struct Outcome {
+    merged: Value,
+    conflicts: Vec<String>,
+}
+
+/// Merge the nodes in each workspace, as well as any other sections (e.g.
+/// `environments`) when the workspace files are objects.
+///
+/// Conflicting changes are resolved in favour of ours.
+fn merge(
+    (base_json, base): (Value, Vec<Node>),
+    (ours_json, ours): (Value, Vec<Node>),
+    (theirs_json, theirs): (Value, Vec<Node>),
+) -> Outcome {
+    let merged_nodes = laskea_engine::merge(&base, &ours, &theirs);
+    let mut conflicts: Vec<String> = merged_nodes
+        .conflicts
+        .iter()
+        .map(Conflict::to_string)
+        .collect();
+
+    let base_fields = fields(base_json);
+    let ours_fields = fields(ours_json.clone());
+    let theirs_fields = fields(theirs_json);
+    let names: BTreeSet<&String> = base_fields
+        .keys()
+        .chain(ours_fields.keys())
+        .chain(theirs_fields.keys())
+        .filter(|name| *name != "nodes")
+        .collect();
+
+    let mut merged = Map::new();
+    merged.insert(
+        String::from("nodes"),
+        serde_json::to_value(&merged_nodes.nodes).unwrap_or_default(),
+    );
+
+    for name in names {
+        let base = base_fields.get(name);
+        let ours = ours_fields.get(name);
+        let theirs = theirs_fields.get(name);
+
+        let value = if ours == theirs || theirs == base {
+            ours
+        } else if ours == base {
+            theirs
+        } else {
+            conflicts.push(format!(
+                "The \"{}\" section was changed on both sides",
+                name
+            ));
+            ours
+        };
+
+        if let Some(value) = value {
+            merged.insert(name.clone(), value.clone());
+        }
+    }
+
+    // Keep using a plain array of nodes if that's what we had
+    let merged = if ours_json.is_array() && merged.len() == 1 {
+        merged.remove("nodes").unwrap_or_default()
+    } else {
+        Value::Object(merged)
+    };
+
+    Outcome { merged, conflicts }
+}
+
+fn fields(json: Value) -> Map<String, Value> {
+    match json {
+        Value::Object(fields) => fields,
+        _ => Map::new(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    fn workspace(json: Value) -> (Value, Vec<Node>) {
+        let nodes = match &json {
+            Value::Object(fields) => fields["nodes"].clone(),
+            other => other.clone(),
+        };
+        (json.clone(), serde_json::from_value(nodes).unwrap())
+    }
+
+    fn string(name: &str, value: &str) -> Value {
+        json!({ "name": name, "expression": { "type": "string", "value": value } })
+    }
+
+    #[test]
+    fn merge_nodes_and_environments() {
+        let base = workspace(json!({
+            "nodes": [string("url", "http://localhost/")],
+            "environments": { "dev": { "token": "abc" } },
+        }));
+        let ours = workspace(json!({
+            "nodes": [string("url", "http://localhost/"), string("user", "admin")],
+            "environments": { "dev": { "token": "abc" } },
+        }));
+        let theirs = workspace(json!({
+            "nodes": [string("url", "https://example.com/")],
+            "environments": { "dev": { "token": "xyz" } },
+            "functions": {},
+        }));
+
+        let Outcome { merged, conflicts } = merge(base, ours, theirs);
+
+        assert!(conflicts.is_empty(), "{:?}", conflicts);
+        assert_eq!(
+            merged,
+            json!({
+                "nodes": [string("url", "https://example.com/"), string("user", "admin")],
+                "environments": { "dev": { "token": "xyz" } },
+                "functions": {},
+            })
+        );
+    }
+
+    #[test]
+    fn report_conflicts() {
+        let base = workspace(json!([string("url", "http://localhost/")]));
+        let ours = workspace(json!([string("url", "http://localhost:8000/")]));
+        let theirs = workspace(json!({
+            "nodes": [string("url", "https://example.com/")],
+        }));
+
+        let Outcome { merged, conflicts } = merge(base, ours, theirs);
+
+        assert_eq!(conflicts, ["\"url\" was changed on both sides"]);
+        assert_eq!(merged, json!([string("url", "http://localhost:8000/")]));
+    }
+}
@@ -0,0 +1,236 @@
+use crate::{
+    alerts::{self, AlertConfig},
+    http::{self, Client},
+    workspace::WorkspaceArgs,
+    Error,
+};
+use clap::Parser;
+use laskea_engine::{
+    Alerter, Change, Clock, Evaluate, Expression, History, InputsExt, Redact, Scheduler,
+    SystemClock,
+};
+use std::{io::Write, ops::ControlFlow, path::PathBuf};
+
+#[derive(Debug, Parser)]
+pub struct Monitor {
+    #[command(flatten)]
+    workspace: WorkspaceArgs,
+    /// Stop after checking the workspace this many times.
+    #[arg(long)]
+    count: Option<usize>,
+    /// A SQLite database to record each node's results in, so they can be
+    /// compared with the previous check.
+    #[arg(long)]
+    history: Option<PathBuf>,
+}
+
+impl Monitor {
+    pub fn run(self) -> Result<(), Error> {
+        let (mut db, alerts) = self.workspace.load_with_alerts()?;
+        let mut history = self.history.as_ref().map(History::open).transpose()?;
+        let stdout = std::io::stdout();
+
+        monitor(
+            &mut db,
+            &SystemClock,
+            &alerts,
+            history.as_mut(),
+            self.count,
+            &mut stdout.lock(),
+        )
+    }
+}
+
+/// Keep re-sending the workspace's requests as their refresh intervals
+/// elapse, writing each [`Change`] to `out` as a line of JSON and sending
+/// any alerts to the configured sinks.
+///
+/// If a [`History`] is provided, the results from each check are recorded so
+/// they can be compared with the next one.
+fn monitor<DB>(
+    db: &mut DB,
+    clock: &dyn Clock,
+    alerts: &AlertConfig,
+    mut history: Option<&mut History>,
+    count: Option<usize>,
+    out: &mut dyn Write,
+) -> Result<(), Error>
+where
+    DB: Evaluate + InputsExt,
+{
+    let refreshed = db.nodes().iter().any(|node| {
+        matches!(
+            &*node.expr,
+            Expression::Request {
+                refresh: Some(_),
+                ..
+            }
+        )
+    });
+    if !refreshed {
+        return Err("None of the workspace's requests have a refresh interval".into());
+    }
+
+    let client = Client::new();
+    let mut scheduler = Scheduler::new();
+    let mut alerter = Alerter::new(alerts.policy.clone());
+    let mut checks = 0;
+    let mut result = Ok(());
+
+    if let Some(history) = &history {
+        db.set_previous_results(history.latest_results()?);
+    }
+    http::fetch_tokens(db, &client);
+
+    scheduler.run(
+        db,
+        clock,
+        |db, name| http::resend(db, &client, name),
+        |db, changes| {
+            checks += 1;
+            let now = clock.now();
+
+            for alert in alerter.check(db, now) {
+                alerts::notify(&alerts.sinks, &alert);
+            }
+
+            if let Some(history) = history.as_deref_mut() {
+                let recorded = history
+                    .record(db, now)
+                    .and_then(|_| history.latest_results());
+                match recorded {
+                    Ok(results) => db.set_previous_results(results),
+                    Err(e) => {
+                        result = Err(e.into());
+                        return ControlFlow::Break(());
+                    }
+                }
+            }
+
+            if let Err(e) = write_changes(out, changes) {
+                result = Err(e);
+                return ControlFlow::Break(());
+            }
+
+            match count {
+                Some(count) if checks >= count => ControlFlow::Break(()),
+                _ => ControlFlow::Continue(()),
+            }
+        },
+    );
+
+    result
+}
+
+fn write_changes(out: &mut dyn Write, changes: &[Change]) -> Result<(), Error> {
+    for change in changes {
+        serde_json::to_writer(&mut *out, &change.redacted())?;
+        writeln!(out)?;
+    }
+    out.flush()?;
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{alerts::Sink, workspace::Database};
+    use laskea_engine::{Alert, AlertKind, AlertPolicy, Duration, Instant, Node};
+    use std::cell::Cell;
+
+    struct FakeClock(Cell<Instant>);
+
+    impl Clock for FakeClock {
+        fn now(&self) -> Instant {
+            self.0.get()
+        }
+
+        fn sleep(&self, duration: Duration) {
+            self.0.set(self.0.get().checked_add(duration).unwrap());
+        }
+    }
+
+    fn request(refresh: Option<Duration>) -> Expression {
+        Expression::Request {
+            // Nothing should be listening on port 1
+            url: "http://127.0.0.1:1/".into(),
+            auth: None,
+            refresh,
+            response: None,
+            error: None,
+        }
+    }
+
+    #[test]
+    fn write_changes_send_alerts_and_record_history() {
+        let mut db = Database::with_nodes(vec![
+            Node::new("health", request(Some(Duration::from_millis(60_000)))),
+            Node::new("status", Expression::get("health", "status")),
+        ]);
+        let clock = FakeClock(Cell::new(Instant::UNIX_EPOCH));
+        let log = std::env::temp_dir().join(format!("laskea-monitor-{}.log", std::process::id()));
+        let _ = std::fs::remove_file(&log);
+        let alerts = AlertConfig {
+            sinks: vec![Sink::File { path: log.clone() }],
+            policy: AlertPolicy::default(),
+        };
+        let mut history = History::in_memory().unwrap();
+        let mut out = Vec::new();
+
+        monitor(
+            &mut db,
+            &clock,
+            &alerts,
+            Some(&mut history),
+            Some(2),
+            &mut out,
+        )
+        .unwrap();
+
+        let lines: Vec<serde_json::Value> = String::from_utf8(out)
+            .unwrap()
+            .lines()
+            .map(|line| serde_json::from_str(line).unwrap())
+            .collect();
+        // The first check reports both nodes and the second one doesn't
+        // report anything because the request failed the same way again
+        assert_eq!(lines.len(), 2);
+        assert_eq!(lines[0]["name"], "health");
+        assert_eq!(lines[1]["name"], "status");
+        assert_eq!(lines[1]["result"]["Err"]["type"], "upstream-failed");
+        assert_eq!(clock.now(), Instant::from_unix_millis(60_000).unwrap());
+        // Only the request raised an alert, and only the first time it failed
+        let alerts: Vec<Alert> = std::fs::read_to_string(&log)
+            .unwrap()
+            .lines()
+            .map(|line| serde_json::from_str(line).unwrap())
+            .collect();
+        assert_eq!(alerts.len(), 1);
+        assert_eq!(&*alerts[0].node, "health");
+        assert_eq!(alerts[0].kind, AlertKind::Errored);
+        // Both checks were recorded
+        assert_eq!(history.entries("health").unwrap().len(), 2);
+    }
+
+    #[test]
+    fn nothing_to_monitor() {
+        let mut db = Database::with_nodes(vec![Node::new("health", request(None))]);
+        let clock = FakeClock(Cell::new(Instant::UNIX_EPOCH));
+
+        let err = monitor(
+            &mut db,
+            &clock,
+            &AlertConfig::default(),
+            None,
+            None,
+            &mut Vec::new(),
+        )
+        .unwrap_err();
+
+        assert_eq!(
+            err.to_string(),
+            "None of the workspace's requests have a refresh interval"
+        );
+    }
+}
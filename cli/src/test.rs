@@ -0,0 +1,99 @@
+use crate::{workspace::WorkspaceArgs, Error};
+use clap::{Parser, ValueEnum};
+use laskea_engine::{run_tests, Outcome, TestReport};
+use std::fmt::Write;
+
+#[derive(Debug, Parser)]
+pub struct Test {
+    #[command(flatten)]
+    workspace: WorkspaceArgs,
+    #[arg(short, long, value_enum, default_value_t = Format::Summary)]
+    format: Format,
+}
+
+#[derive(Debug, Copy, Clone, PartialEq, Eq, ValueEnum)]
+enum Format {
+    /// A human-readable summary.
+    Summary,
+    /// JUnit XML.
+    Junit,
+    /// The Test Anything Protocol.
+    Tap,
+}
+
+impl Test {
+    pub fn run(self) -> Result<(), Error> {
+        let db = self.workspace.load()?;
+        let report = run_tests(&db);
+        let suite = self.workspace.name();
+
+        let output = match self.format {
+            Format::Summary => summary(&report),
+            Format::Junit => report.to_junit(&suite),
+            Format::Tap => report.to_tap(),
+        };
+        print!("{}", output);
+
+        if report.is_success() {
+            Ok(())
+        } else {
+            let failures = report.failed() + report.errored();
+            Err(format!("{} of {} assertions failed", failures, report.cases.len()).into())
+        }
+    }
+}
+
+fn summary(report: &TestReport) -> String {
+    let mut summary = String::new();
+
+    for case in &report.cases {
+        let _ = match &case.outcome {
+            Outcome::Passed => writeln!(summary, "PASS {}", case.name),
+            Outcome::Failed { message } => writeln!(summary, "FAIL {}: {}", case.name, message),
+            Outcome::Errored { message } => writeln!(summary, "ERROR {}: {}", case.name, message),
+            Outcome::Indeterminate => writeln!(summary, "SKIP {}", case.name),
+        };
+    }
+
+    let _ = writeln!(
+        summary,
+        "\n{} passed, {} failed, {} errored, {} indeterminate",
+        report.passed(),
+        report.failed(),
+        report.errored(),
+        report.indeterminate()
+    );
+
+    summary
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use laskea_engine::TestCase;
+
+    #[test]
+    fn human_readable_summary() {
+        let report = TestReport {
+            cases: vec![
+                TestCase {
+                    name: "is-ok".into(),
+                    outcome: Outcome::Passed,
+                },
+                TestCase {
+                    name: "has-body".into(),
+                    outcome: Outcome::Failed {
+                        message: "The body was empty".into(),
+                    },
+                },
+            ],
+        };
+
+        let got = summary(&report);
+
+        assert_eq!(
+            got,
+            "PASS is-ok\nFAIL has-body: The body was empty\n\n1 passed, 1 failed, 0 errored, 0 indeterminate\n"
+        );
+    }
+}
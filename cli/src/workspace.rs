@@ -0,0 +1,229 @@
+use crate::{alerts::AlertConfig, Error};
+use laskea_engine::{
+    Environment, EnvironmentVariables, EvaluateStorage, Function, Inputs, InputsExt, InputsStorage,
+    Instant, Node, SecretSource, SecretsFile, Text,
+};
+use std::{
+    collections::BTreeMap,
+    path::{Path, PathBuf},
+};
+
+#[salsa::database(InputsStorage, EvaluateStorage)]
+#[derive(Default)]
+pub struct Database {
+    storage: salsa::Storage<Self>,
+}
+
+impl salsa::Database for Database {}
+
+impl Database {
+    pub fn with_nodes(nodes: Vec<Node>) -> Self {
+        let mut db = Database::default();
+        laskea_engine::init_inputs(&mut db);
+        db.set_nodes(nodes.into());
+        db.set_now(Instant::now());
+        db
+    }
+}
+
+/// Command-line arguments for loading a workspace.
+#[derive(Debug, clap::Args)]
+pub struct WorkspaceArgs {
+    /// A JSON file containing the workspace's nodes.
+    workspace: PathBuf,
+    /// The environment to read variables from.
+    #[arg(short, long)]
+    env: Option<String>,
+    /// A file containing `name=value` lines to read secrets from.
+    ///
+    /// Secrets can also be provided using `LASKEA_SECRET_*` environment
+    /// variables, which take precedence.
+    #[arg(long)]
+    secrets_file: Option<PathBuf>,
+    /// Send the workspace's HTTP requests before using their results.
+    #[arg(long)]
+    fetch: bool,
+}
+
+impl WorkspaceArgs {
+    /// A name for the workspace, based on its filename.
+    pub fn name(&self) -> String {
+        self.workspace
+            .file_stem()
+            .map(|s| s.to_string_lossy().into_owned())
+            .unwrap_or_else(|| String::from("laskea"))
+    }
+
+    pub fn load(&self) -> Result<Database, Error> {
+        self.load_with_alerts().map(|(db, _)| db)
+    }
+
+    /// Load the workspace along with its alerting configuration.
+    pub fn load_with_alerts(&self) -> Result<(Database, AlertConfig), Error> {
+        let path = &self.workspace;
+        let json = std::fs::read(path)
+            .map_err(|e| format!("Unable to read \"{}\": {}", path.display(), e))?;
+        let file: WorkspaceFile = serde_json::from_slice(&json)
+            .map_err(|e| format!("Unable to parse \"{}\": {}", path.display(), e))?;
+
+        let alerts = file.alerts();
+        let mut db = file.into_database(self.env.as_deref())?;
+
+        let secrets_file = match &self.secrets_file {
+            Some(path) => SecretsFile::load(path)
+                .map_err(|e| format!("Unable to read \"{}\": {}", path.display(), e))?,
+            None => SecretsFile::default(),
+        };
+        let sources: &[&dyn SecretSource] = &[&EnvironmentVariables::new(), &secrets_file];
+        db.load_secrets(&sources);
+        laskea_engine::schemas::load_files(
+            &mut db,
+            path.parent().unwrap_or_else(|| Path::new(".")),
+        );
+
+        if self.fetch {
+            crate::http::fetch_all(&mut db);
+        }
+
+        Ok((db, alerts))
+    }
+}
+
+/// Read a workspace file without loading it into a [`Database`], returning
+/// its JSON along with the nodes it contains.
+pub fn read_nodes(path: &Path) -> Result<(serde_json::Value, Vec<Node>), Error> {
+    let json =
+        std::fs::read(path).map_err(|e| format!("Unable to read \"{}\": {}", path.display(), e))?;
+    let parse_error =
+        |e: serde_json::Error| format!("Unable to parse \"{}\": {}", path.display(), e);
+
+    let json: serde_json::Value = serde_json::from_slice(&json).map_err(parse_error)?;
+    let nodes = match &json {
+        serde_json::Value::Object(fields) => fields.get("nodes").cloned().unwrap_or_default(),
+        other => other.clone(),
+    };
+    let nodes = serde_json::from_value(nodes).map_err(parse_error)?;
+
+    Ok((json, nodes))
+}
+
+/// The contents of a workspace file.
+///
+/// This is either an array of nodes or an object containing the nodes, any
+/// environments or functions they use, and how to alert people when they
+/// start failing.
+#[derive(Debug, serde::Deserialize)]
+#[serde(untagged)]
+enum WorkspaceFile {
+    Nodes(Vec<Node>),
+    Workspace {
+        nodes: Vec<Node>,
+        #[serde(default)]
+        environments: BTreeMap<Text, Environment>,
+        #[serde(default)]
+        functions: BTreeMap<Text, Function>,
+        #[serde(default)]
+        alerts: AlertConfig,
+    },
+}
+
+impl WorkspaceFile {
+    fn alerts(&self) -> AlertConfig {
+        match self {
+            WorkspaceFile::Nodes(_) => AlertConfig::default(),
+            WorkspaceFile::Workspace { alerts, .. } => alerts.clone(),
+        }
+    }
+
+    fn into_database(self, env: Option<&str>) -> Result<Database, Error> {
+        let (nodes, environments, functions) = match self {
+            WorkspaceFile::Nodes(nodes) => (nodes, BTreeMap::new(), BTreeMap::new()),
+            WorkspaceFile::Workspace {
+                nodes,
+                environments,
+                functions,
+                ..
+            } => (nodes, environments, functions),
+        };
+
+        let mut db = Database::with_nodes(nodes);
+        db.set_environments(environments);
+        for (name, function) in functions {
+            db.set_function(name, function)?;
+        }
+        db.select_environment(env)?;
+
+        Ok(db)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use laskea_engine::{Evaluate, Value};
+    use serde_json::json;
+
+    #[test]
+    fn load_a_workspace_with_environments() {
+        let file: WorkspaceFile = serde_json::from_value(json!({
+            "nodes": [
+                { "name": "url", "expression": { "type": "variable", "name": "base_url" } },
+            ],
+            "environments": {
+                "dev": { "base_url": "http://localhost:8000/" },
+            },
+        }))
+        .unwrap();
+
+        let db = file.into_database(Some("dev")).unwrap();
+
+        assert_eq!(db.evaluate()[0], Ok(Value::from("http://localhost:8000/")));
+    }
+
+    #[test]
+    fn load_a_workspace_with_functions() {
+        let file: WorkspaceFile = serde_json::from_value(json!({
+            "nodes": [
+                {
+                    "name": "ok",
+                    "expression": {
+                        "type": "call",
+                        "function": "is_ok",
+                        "arguments": [
+                            { "type": "constant", "value": { "type": "number", "value": 204 } },
+                        ],
+                    },
+                },
+            ],
+            "functions": {
+                "is_ok": {
+                    "parameters": [{ "name": "status", "type": { "type": "number" } }],
+                    "body": [
+                        {
+                            "name": "created",
+                            "expression": {
+                                "type": "equals",
+                                "target": "status",
+                                "value": { "type": "number", "value": 204 },
+                            },
+                        },
+                    ],
+                },
+            },
+        }))
+        .unwrap();
+
+        let db = file.into_database(None).unwrap();
+
+        assert_eq!(db.evaluate()[0], Ok(Value::from(true)));
+    }
+
+    #[test]
+    fn unknown_environments_are_rejected() {
+        let file = WorkspaceFile::Nodes(Vec::new());
+
+        let err = file.into_database(Some("prod")).err().unwrap();
+
+        assert_eq!(err.to_string(), "No \"prod\" environment found");
+    }
+}
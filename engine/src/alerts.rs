@@ -0,0 +1,346 @@
+//! Noticing when an assertion starts failing or a node starts erroring, so
+//! the people monitoring a workspace can be told.
+
+use crate::{testing, Duration, Evaluate, EvaluationError, Expression, Instant, Text, Value};
+use std::{
+    collections::BTreeMap,
+    fmt::{self, Display, Formatter},
+};
+
+/// A notification that a node's health has changed.
+#[derive(Debug, Clone, PartialEq, Eq, serde::Serialize, serde::Deserialize)]
+pub struct Alert {
+    pub node: Text,
+    pub kind: AlertKind,
+    pub message: Text,
+    /// When the change was confirmed.
+    pub timestamp: Instant,
+}
+
+impl Display for Alert {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        let label = match self.kind {
+            AlertKind::AssertionFailed => "FAILED",
+            AlertKind::Errored => "ERROR",
+            AlertKind::Recovered => "RECOVERED",
+        };
+
+        write!(
+            f,
+            "[{}] {} {}: {}",
+            self.timestamp, label, self.node, self.message
+        )
+    }
+}
+
+#[derive(Debug, Copy, Clone, PartialEq, Eq, serde::Serialize, serde::Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum AlertKind {
+    /// An [`Expression::Assert`] which used to pass has failed.
+    AssertionFailed,
+    /// A node which used to evaluate successfully has started failing.
+    Errored,
+    /// A node which was failing is healthy again.
+    Recovered,
+}
+
+/// How eager an [`Alerter`] should be.
+#[derive(Debug, Clone, PartialEq, Eq, serde::Serialize, serde::Deserialize)]
+pub struct AlertPolicy {
+    /// How long a node needs to stay failing (or healthy) before an alert is
+    /// raised. Nodes which flap back within this window don't raise alerts.
+    #[serde(default)]
+    pub debounce: Duration,
+    /// Raise an [`AlertKind::Recovered`] alert when a failing node becomes
+    /// healthy again.
+    #[serde(default = "default_recovery")]
+    pub recovery: bool,
+}
+
+impl Default for AlertPolicy {
+    fn default() -> Self {
+        AlertPolicy {
+            debounce: Duration::ZERO,
+            recovery: default_recovery(),
+        }
+    }
+}
+
+fn default_recovery() -> bool {
+    true
+}
+
+/// Watches a workspace's results, raising an [`Alert`] when a node's health
+/// changes.
+///
+/// Nodes are assumed to be healthy until they've been checked, so a node
+/// which is already failing will raise an alert the first time it is seen.
+/// Errors which were propagated from another node are ignored, because the
+/// node they originated from will raise its own alert.
+#[derive(Debug, Default, Clone, PartialEq)]
+pub struct Alerter {
+    policy: AlertPolicy,
+    nodes: BTreeMap<Text, NodeState>,
+}
+
+#[derive(Debug, Default, Clone, PartialEq)]
+struct NodeState {
+    /// The health we last told people about.
+    reported: Health,
+    /// A different health which was first seen at this time, but hasn't
+    /// lasted long enough to be reported yet.
+    pending: Option<(Health, Instant)>,
+}
+
+#[derive(Debug, Default, Clone, PartialEq)]
+enum Health {
+    #[default]
+    Healthy,
+    Failing {
+        kind: AlertKind,
+        message: Text,
+    },
+}
+
+impl Health {
+    fn same_as(&self, other: &Health) -> bool {
+        match (self, other) {
+            (Health::Healthy, Health::Healthy) => true,
+            (Health::Failing { kind: a, .. }, Health::Failing { kind: b, .. }) => a == b,
+            _ => false,
+        }
+    }
+}
+
+impl Alerter {
+    pub fn new(policy: AlertPolicy) -> Self {
+        Alerter {
+            policy,
+            nodes: BTreeMap::new(),
+        }
+    }
+
+    /// Evaluate the workspace and return any alerts raised at `now`.
+    pub fn check(&mut self, db: &dyn Evaluate, now: Instant) -> Vec<Alert> {
+        let nodes = db.nodes();
+        let results = db.evaluate();
+        let mut alerts = Vec::new();
+
+        for (node, result) in nodes.iter().zip(results.iter()) {
+            let health = match health(db, &node.expr, result) {
+                Some(health) => health,
+                None => continue,
+            };
+            let state = self.nodes.entry(node.name.clone()).or_default();
+
+            if health.same_as(&state.reported) {
+                state.pending = None;
+                continue;
+            }
+
+            let since = match &state.pending {
+                Some((pending, since)) if pending.same_as(&health) => *since,
+                _ => now,
+            };
+
+            let settled = now
+                .duration_since(since)
+                .is_some_and(|elapsed| elapsed >= self.policy.debounce);
+            if !settled {
+                state.pending = Some((health, since));
+                continue;
+            }
+
+            let alert = match &health {
+                Health::Failing { kind, message } => Some((*kind, message.clone())),
+                Health::Healthy if self.policy.recovery => {
+                    Some((AlertKind::Recovered, Text::from("Recovered")))
+                }
+                Health::Healthy => None,
+            };
+            if let Some((kind, message)) = alert {
+                alerts.push(Alert {
+                    node: node.name.clone(),
+                    kind,
+                    message,
+                    timestamp: now,
+                });
+            }
+
+            state.reported = health;
+            state.pending = None;
+        }
+
+        self.nodes
+            .retain(|name, _| nodes.iter().any(|n| n.name == *name));
+
+        alerts
+    }
+}
+
+/// Work out whether a node is healthy, or `None` if it can't be told yet.
+fn health(
+    db: &dyn Evaluate,
+    expr: &Expression,
+    result: &Result<Value, EvaluationError>,
+) -> Option<Health> {
+    match result {
+        Ok(Value::Indeterminate) | Err(EvaluationError::UpstreamFailed { .. }) => None,
+        Ok(_) => Some(Health::Healthy),
+        Err(EvaluationError::Failed { message }) => {
+            let kind = match expr {
+                Expression::Assert { target, .. } if testing::is_false(db, target) => {
+                    AlertKind::AssertionFailed
+                }
+                _ => AlertKind::Errored,
+            };
+            Some(Health::Failing {
+                kind,
+                message: message.clone(),
+            })
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{test_utils::Database, InputsExt, Node};
+
+    fn at(seconds_after_epoch: i64) -> Instant {
+        Instant::from_unix_millis(seconds_after_epoch * 1000).unwrap()
+    }
+
+    /// A workspace where `status` is compared against "up" and the result is
+    /// asserted.
+    fn set_status(db: &mut Database, status: &str) {
+        db.set_nodes(
+            vec![
+                Node {
+                    name: "status".into(),
+                    expr: Expression::string(status).into(),
+                },
+                Node {
+                    name: "is-up".into(),
+                    expr: Expression::equals("status", "up").into(),
+                },
+                Node {
+                    name: "assertion".into(),
+                    expr: Expression::assert("is-up", "The service should be up").into(),
+                },
+            ]
+            .into(),
+        );
+    }
+
+    fn database(status: &str) -> Database {
+        let mut db = Database::default();
+        set_status(&mut db, status);
+        db
+    }
+
+    fn summary(alerts: &[Alert]) -> Vec<(&str, AlertKind)> {
+        alerts.iter().map(|a| (&*a.node, a.kind)).collect()
+    }
+
+    #[test]
+    fn alert_when_an_assertion_fails_and_recovers() {
+        let mut db = database("up");
+        let mut alerter = Alerter::new(AlertPolicy::default());
+
+        assert!(alerter.check(&db, at(0)).is_empty());
+
+        set_status(&mut db, "down");
+        let alerts = alerter.check(&db, at(10));
+        assert_eq!(
+            alerts,
+            vec![Alert {
+                node: "assertion".into(),
+                kind: AlertKind::AssertionFailed,
+                message: "The service should be up".into(),
+                timestamp: at(10),
+            }]
+        );
+
+        // Still failing, so there's nothing new to say
+        assert!(alerter.check(&db, at(20)).is_empty());
+
+        set_status(&mut db, "up");
+        let alerts = alerter.check(&db, at(30));
+        assert_eq!(summary(&alerts), [("assertion", AlertKind::Recovered)]);
+    }
+
+    #[test]
+    fn flapping_is_debounced() {
+        let mut db = database("up");
+        let mut alerter = Alerter::new(AlertPolicy {
+            debounce: Duration::from_millis(30_000),
+            recovery: false,
+        });
+        alerter.check(&db, at(0));
+
+        // A brief failure doesn't raise anything
+        set_status(&mut db, "down");
+        assert!(alerter.check(&db, at(10)).is_empty());
+        set_status(&mut db, "up");
+        assert!(alerter.check(&db, at(20)).is_empty());
+
+        // But one which lasts longer than the debounce period does
+        set_status(&mut db, "down");
+        assert!(alerter.check(&db, at(30)).is_empty());
+        assert!(alerter.check(&db, at(50)).is_empty());
+        let alerts = alerter.check(&db, at(60));
+        assert_eq!(
+            summary(&alerts),
+            [("assertion", AlertKind::AssertionFailed)]
+        );
+
+        // Recovery notifications were turned off
+        set_status(&mut db, "up");
+        assert!(alerter.check(&db, at(100)).is_empty());
+    }
+
+    #[test]
+    fn only_the_failing_node_raises_an_alert() {
+        let mut db = database("up");
+        db.set_nodes(
+            vec![
+                Node {
+                    name: "missing".into(),
+                    expr: Expression::get("nowhere", "status").into(),
+                },
+                Node {
+                    name: "assertion".into(),
+                    expr: Expression::assert("missing", "Unreachable").into(),
+                },
+            ]
+            .into(),
+        );
+        let mut alerter = Alerter::new(AlertPolicy::default());
+
+        let alerts = alerter.check(&db, at(0));
+
+        assert_eq!(summary(&alerts), [("missing", AlertKind::Errored)]);
+    }
+
+    #[test]
+    fn assertions_which_cant_be_checked_are_errors() {
+        let db = Database::with_nodes(vec![
+            Node::new("status", Expression::string("up")),
+            Node::new("assertion", Expression::assert("status", "Not a boolean")),
+        ]);
+        let mut alerter = Alerter::new(AlertPolicy::default());
+
+        let alerts = alerter.check(&db, at(0));
+
+        assert_eq!(summary(&alerts), [("assertion", AlertKind::Errored)]);
+    }
+
+    #[test]
+    fn policy_defaults() {
+        let policy: AlertPolicy = serde_json::from_str("{}").unwrap();
+
+        assert_eq!(policy, AlertPolicy::default());
+        assert!(policy.recovery);
+    }
+}
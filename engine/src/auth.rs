@@ -0,0 +1,546 @@
+use crate::{Evaluate, EvaluationError, Expression, Secret, Text, Value};
+use serde::{ser::SerializeStruct, Serialize, Serializer};
+
+/// How a [`Expression::Request`] authenticates itself.
+///
+/// Credentials are the names of the nodes which provide them (typically
+/// [`Expression::Secret`] nodes).
+#[derive(Debug, Clone, PartialEq, Eq, Hash, serde::Serialize, serde::Deserialize)]
+#[cfg_attr(feature = "typescript", derive(ts_rs::TS))]
+#[serde(tag = "type", rename_all = "kebab-case")]
+pub enum Auth {
+    Basic {
+        username: Text,
+        password: Text,
+    },
+    Bearer {
+        token: Text,
+    },
+    /// Send an API key using the header or query parameter called `name`.
+    ApiKey {
+        name: Text,
+        value: Text,
+        #[serde(default)]
+        location: ApiKeyLocation,
+    },
+    /// Use the access token from an [`Expression::OAuth2Token`] node.
+    #[serde(rename = "oauth2")]
+    OAuth2 {
+        token: Text,
+    },
+}
+
+impl Auth {
+    /// The names of the nodes which provide this method's credentials.
+    pub(crate) fn credentials(&self) -> Vec<&Text> {
+        match self {
+            Auth::Basic { username, password } => vec![username, password],
+            Auth::Bearer { token } | Auth::OAuth2 { token } => vec![token],
+            Auth::ApiKey { value, .. } => vec![value],
+        }
+    }
+}
+
+#[derive(
+    Debug, Default, Copy, Clone, PartialEq, Eq, Hash, serde::Serialize, serde::Deserialize,
+)]
+#[cfg_attr(feature = "typescript", derive(ts_rs::TS))]
+#[serde(rename_all = "kebab-case")]
+pub enum ApiKeyLocation {
+    #[default]
+    Header,
+    Query,
+}
+
+/// Everything needed to send the HTTP request for a [`Expression::Request`]
+/// or [`Expression::OAuth2Token`] node.
+///
+/// Credentials are kept as [`Secret`]s so they aren't accidentally logged.
+/// They are serialized with their real values, because whoever sends the
+/// request needs them.
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "typescript", derive(ts_rs::TS))]
+pub struct PreparedRequest {
+    pub method: Text,
+    pub url: Text,
+    pub query: Vec<(Text, Secret)>,
+    pub headers: Vec<(Text, Secret)>,
+    #[cfg_attr(feature = "typescript", ts(optional))]
+    pub body: Option<Secret>,
+    /// The [`Expression::OAuth2Token`] node to refresh if the server rejects
+    /// the request with a `401 Unauthorized`.
+    #[cfg_attr(feature = "typescript", ts(optional))]
+    pub refresh: Option<Text>,
+}
+
+impl Serialize for PreparedRequest {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        let exposed = |pairs: &[(Text, Secret)]| -> Vec<(Text, Text)> {
+            pairs
+                .iter()
+                .map(|(key, value)| (key.clone(), value.expose().into()))
+                .collect()
+        };
+
+        let mut request = serializer.serialize_struct("PreparedRequest", 6)?;
+        request.serialize_field("method", &self.method)?;
+        request.serialize_field("url", &self.url)?;
+        request.serialize_field("query", &exposed(&self.query))?;
+        request.serialize_field("headers", &exposed(&self.headers))?;
+        match &self.body {
+            Some(body) => request.serialize_field("body", body.expose())?,
+            None => request.skip_field("body")?,
+        }
+        match &self.refresh {
+            Some(refresh) => request.serialize_field("refresh", refresh)?,
+            None => request.skip_field("refresh")?,
+        }
+        request.end()
+    }
+}
+
+impl PreparedRequest {
+    fn get(url: Text) -> Self {
+        PreparedRequest {
+            method: "GET".into(),
+            url,
+            query: Vec::new(),
+            headers: Vec::new(),
+            body: None,
+            refresh: None,
+        }
+    }
+}
+
+pub(crate) fn prepare_request(
+    db: &dyn Evaluate,
+    name: Text,
+) -> Result<PreparedRequest, EvaluationError> {
+    let expression = db
+        .expression_of(name.clone())
+        .ok_or_else(|| format!("No \"{}\" input found", name))?;
+
+    match &*expression {
+        Expression::Request { url, auth, .. } => {
+            let mut request = PreparedRequest::get(url.clone());
+            if let Some(auth) = auth {
+                authenticate(db, &mut request, auth)?;
+            }
+            Ok(request)
+        }
+        Expression::OAuth2Token {
+            token_url,
+            client_id,
+            client_secret,
+            scope,
+            ..
+        } => {
+            let mut form = vec![
+                ("grant_type", Secret::new("client_credentials")),
+                ("client_id", credential(db, client_id)?),
+                ("client_secret", credential(db, client_secret)?),
+            ];
+            if let Some(scope) = scope {
+                form.push(("scope", Secret::new(scope.clone())));
+            }
+
+            let body = form
+                .iter()
+                .map(|(key, value)| format!("{}={}", key, form_encode(value.expose())))
+                .collect::<Vec<_>>()
+                .join("&");
+
+            Ok(PreparedRequest {
+                method: "POST".into(),
+                url: token_url.clone(),
+                query: Vec::new(),
+                headers: vec![(
+                    "Content-Type".into(),
+                    Secret::new("application/x-www-form-urlencoded"),
+                )],
+                body: Some(Secret::new(body)),
+                refresh: None,
+            })
+        }
+        _ => Err(format!("\"{}\" is not a request", name).into()),
+    }
+}
+
+fn authenticate(
+    db: &dyn Evaluate,
+    request: &mut PreparedRequest,
+    auth: &Auth,
+) -> Result<(), EvaluationError> {
+    match auth {
+        Auth::Basic { username, password } => {
+            let credentials = format!(
+                "{}:{}",
+                credential(db, username)?.expose(),
+                credential(db, password)?.expose()
+            );
+            let header = format!("Basic {}", base64(credentials.as_bytes()));
+            request
+                .headers
+                .push(("Authorization".into(), Secret::new(header)));
+        }
+        Auth::Bearer { token } => {
+            let header = format!("Bearer {}", credential(db, token)?.expose());
+            request
+                .headers
+                .push(("Authorization".into(), Secret::new(header)));
+        }
+        Auth::ApiKey {
+            name,
+            value,
+            location,
+        } => {
+            let value = credential(db, value)?;
+            match location {
+                ApiKeyLocation::Header => request.headers.push((name.clone(), value)),
+                ApiKeyLocation::Query => request.query.push((name.clone(), value)),
+            }
+        }
+        Auth::OAuth2 { token } => {
+            let header = format!("Bearer {}", credential(db, token)?.expose());
+            request
+                .headers
+                .push(("Authorization".into(), Secret::new(header)));
+            request.refresh = Some(token.clone());
+        }
+    }
+
+    Ok(())
+}
+
+/// Get the value of a node which provides a credential.
+fn credential(db: &dyn Evaluate, node: &Text) -> Result<Secret, EvaluationError> {
+    let expression = db
+        .expression_of(node.clone())
+        .ok_or_else(|| format!("No \"{}\" input found", node))?;
+
+    match db.eval(node.clone(), expression) {
+        Ok(Value::Secret(secret)) => Ok(secret),
+        Ok(Value::String(s)) => Ok(Secret::new(s)),
+        Ok(Value::Number(n)) => Ok(Secret::new(n.to_string())),
+        Ok(Value::Indeterminate) => {
+            Err(format!("The credentials in \"{}\" aren't available yet", node).into())
+        }
+        Ok(_) => Err(format!("\"{}\" can't be used as a credential", node).into()),
+        Err(e) => Err(EvaluationError::upstream(node.clone(), e)),
+    }
+}
+
+/// The access token from an OAuth2 token endpoint's response.
+pub(crate) fn access_token(name: &Text, body: &Value) -> Result<Value, EvaluationError> {
+    match body {
+        Value::Object(obj) => match obj.get("access_token") {
+            Some(Value::String(token)) => Ok(Value::Secret(Secret::new(token.clone()))),
+            Some(Value::Secret(token)) => Ok(Value::Secret(token.clone())),
+            _ => Err(format!("The response for \"{}\" has no access token", name).into()),
+        },
+        _ => Err(format!("The response for \"{}\" is not an object", name).into()),
+    }
+}
+
+/// Encode a value for use in an `application/x-www-form-urlencoded` body.
+fn form_encode(s: &str) -> String {
+    let mut encoded = String::with_capacity(s.len());
+
+    for byte in s.bytes() {
+        match byte {
+            b'A'..=b'Z' | b'a'..=b'z' | b'0'..=b'9' | b'-' | b'_' | b'.' | b'~' => {
+                encoded.push(byte as char)
+            }
+            b' ' => encoded.push('+'),
+            _ => encoded.push_str(&format!("%{:02X}", byte)),
+        }
+    }
+
+    encoded
+}
+
+fn base64(bytes: &[u8]) -> String {
+    const ALPHABET: &[u8; 64] = b"ABCDEFGHIJKLMNOPQRSTUVWXYZabcdefghijklmnopqrstuvwxyz0123456789+/";
+    let mut encoded = String::with_capacity(bytes.len().div_ceil(3) * 4);
+
+    for chunk in bytes.chunks(3) {
+        let b = [
+            chunk[0],
+            chunk.get(1).copied().unwrap_or(0),
+            chunk.get(2).copied().unwrap_or(0),
+        ];
+        let n = (u32::from(b[0]) << 16) | (u32::from(b[1]) << 8) | u32::from(b[2]);
+
+        for i in 0..4 {
+            if i <= chunk.len() {
+                encoded.push(ALPHABET[(n >> (18 - 6 * i) & 0x3f) as usize] as char);
+            } else {
+                encoded.push('=');
+            }
+        }
+    }
+
+    encoded
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{test_utils::Database, Inputs, InputsExt, Node, Response};
+    use std::collections::BTreeMap;
+
+    fn request(auth: Auth) -> Expression {
+        Expression::Request {
+            url: "http://example.com/".into(),
+            auth: Some(auth),
+            refresh: None,
+            response: None,
+            error: None,
+        }
+    }
+
+    fn database(nodes: Vec<Node>) -> Database {
+        let mut db = Database::default();
+        db.set_nodes(nodes.into());
+        db.set_secrets(
+            vec![(Text::from("password"), Secret::new("hunter2"))]
+                .into_iter()
+                .collect(),
+        );
+        db
+    }
+
+    fn header(request: &PreparedRequest, name: &str) -> Option<String> {
+        request
+            .headers
+            .iter()
+            .find(|(key, _)| &**key == name)
+            .map(|(_, value)| value.expose().to_string())
+    }
+
+    #[test]
+    fn basic_auth() {
+        let db = database(vec![
+            Node::new("username", Expression::string("admin")),
+            Node::new("password", Expression::secret("password")),
+            Node::new(
+                "request",
+                request(Auth::Basic {
+                    username: "username".into(),
+                    password: "password".into(),
+                }),
+            ),
+        ]);
+
+        let got = db.prepare_request("request".into()).unwrap();
+
+        assert_eq!(
+            header(&got, "Authorization").unwrap(),
+            "Basic YWRtaW46aHVudGVyMg=="
+        );
+        assert!(!format!("{:?}", got).contains("YWRtaW46aHVudGVyMg"));
+    }
+
+    #[test]
+    fn prepared_requests_serialize_their_credentials() {
+        let db = database(vec![
+            Node::new("password", Expression::secret("password")),
+            Node::new(
+                "request",
+                request(Auth::Bearer {
+                    token: "password".into(),
+                }),
+            ),
+        ]);
+        let prepared = db.prepare_request("request".into()).unwrap();
+
+        let got = serde_json::to_value(&prepared).unwrap();
+
+        assert_eq!(
+            got,
+            serde_json::json!({
+                "method": "GET",
+                "url": "http://example.com/",
+                "query": [],
+                "headers": [["Authorization", "Bearer hunter2"]],
+            })
+        );
+    }
+
+    #[test]
+    fn bearer_tokens_and_api_keys() {
+        let db = database(vec![
+            Node::new("token", Expression::secret("password")),
+            Node::new(
+                "bearer",
+                request(Auth::Bearer {
+                    token: "token".into(),
+                }),
+            ),
+            Node::new(
+                "header",
+                request(Auth::ApiKey {
+                    name: "X-API-Key".into(),
+                    value: "token".into(),
+                    location: ApiKeyLocation::Header,
+                }),
+            ),
+            Node::new(
+                "query",
+                request(Auth::ApiKey {
+                    name: "api_key".into(),
+                    value: "token".into(),
+                    location: ApiKeyLocation::Query,
+                }),
+            ),
+        ]);
+
+        let bearer = db.prepare_request("bearer".into()).unwrap();
+        assert_eq!(header(&bearer, "Authorization").unwrap(), "Bearer hunter2");
+
+        let api_key = db.prepare_request("header".into()).unwrap();
+        assert_eq!(header(&api_key, "X-API-Key").unwrap(), "hunter2");
+
+        let query = db.prepare_request("query".into()).unwrap();
+        assert_eq!(
+            query.query,
+            vec![(Text::from("api_key"), Secret::new("hunter2"))]
+        );
+        assert!(query.headers.is_empty());
+    }
+
+    #[test]
+    fn oauth2_client_credentials() {
+        let mut body = BTreeMap::new();
+        body.insert(Text::from("access_token"), Value::from("abc123"));
+        let db = database(vec![
+            Node::new("client-id", Expression::string("my app")),
+            Node::new("client-secret", Expression::secret("password")),
+            Node::new(
+                "token",
+                Expression::OAuth2Token {
+                    token_url: "http://example.com/token".into(),
+                    client_id: "client-id".into(),
+                    client_secret: "client-secret".into(),
+                    scope: Some("read write".into()),
+                    response: Some(Response {
+                        status: 200,
+                        status_text: "OK".into(),
+                        url: "http://example.com/token".into(),
+                        body: Value::Object(body.into()),
+                        metrics: None,
+                    }),
+                    error: None,
+                },
+            ),
+            Node::new(
+                "request",
+                request(Auth::OAuth2 {
+                    token: "token".into(),
+                }),
+            ),
+        ]);
+
+        let token_request = db.prepare_request("token".into()).unwrap();
+        assert_eq!(token_request.method, Text::from("POST"));
+        assert_eq!(
+            token_request.body.unwrap().expose(),
+            "grant_type=client_credentials&client_id=my+app&client_secret=hunter2&scope=read+write"
+        );
+
+        assert_eq!(db.evaluate()[2], Ok(Value::Secret(Secret::new("abc123"))));
+
+        let got = db.prepare_request("request".into()).unwrap();
+        assert_eq!(header(&got, "Authorization").unwrap(), "Bearer abc123");
+        assert_eq!(got.refresh, Some(Text::from("token")));
+    }
+
+    #[test]
+    fn pending_and_failed_credentials() {
+        let db = database(vec![
+            Node::new("missing", Expression::secret("missing")),
+            Node::new(
+                "token",
+                Expression::OAuth2Token {
+                    token_url: "http://example.com/token".into(),
+                    client_id: "missing".into(),
+                    client_secret: "missing".into(),
+                    scope: None,
+                    response: None,
+                    error: None,
+                },
+            ),
+            Node::new(
+                "pending",
+                request(Auth::OAuth2 {
+                    token: "token".into(),
+                }),
+            ),
+        ]);
+
+        let err = db.prepare_request("pending".into()).unwrap_err();
+        assert_eq!(
+            err,
+            EvaluationError::from("The credentials in \"token\" aren't available yet")
+        );
+
+        let err = db.prepare_request("token".into()).unwrap_err();
+        assert_eq!(
+            err,
+            EvaluationError::UpstreamFailed {
+                node: "missing".into(),
+                message: "No \"missing\" secret found".into(),
+            }
+        );
+    }
+
+    #[test]
+    fn credentials_are_dependencies() {
+        let basic = request(Auth::Basic {
+            username: "username".into(),
+            password: "password".into(),
+        });
+        let token = Expression::OAuth2Token {
+            token_url: "http://example.com/token".into(),
+            client_id: "client-id".into(),
+            client_secret: "request".into(),
+            scope: None,
+            response: None,
+            error: None,
+        };
+
+        assert_eq!(
+            crate::evaluate::dependencies(&basic),
+            [&Text::from("username"), &Text::from("password")]
+        );
+        assert_eq!(
+            crate::evaluate::dependencies(&token),
+            [&Text::from("client-id"), &Text::from("request")]
+        );
+
+        let db = database(vec![
+            Node::new("client-id", Expression::string("my app")),
+            Node::new("token", token),
+            Node::new(
+                "request",
+                request(Auth::OAuth2 {
+                    token: "token".into(),
+                }),
+            ),
+        ]);
+
+        assert_eq!(
+            db.reference_cycle("request".into()),
+            Some(vec![Text::from("request"), "token".into(), "request".into()].into())
+        );
+    }
+
+    #[test]
+    fn base64_encoding() {
+        let inputs = [("", ""), ("f", "Zg=="), ("fo", "Zm8="), ("foo", "Zm9v")];
+
+        for (input, should_be) in inputs {
+            assert_eq!(base64(input.as_bytes()), should_be);
+        }
+    }
+}
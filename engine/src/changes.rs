@@ -0,0 +1,221 @@
+use crate::{Evaluate, EvaluationError, Redact, Text, Value};
+
+/// Something that changed between two evaluations.
+#[derive(Debug, Clone, PartialEq, Eq, Hash, serde::Serialize, serde::Deserialize)]
+#[cfg_attr(feature = "typescript", derive(ts_rs::TS))]
+#[serde(tag = "type", rename_all = "kebab-case")]
+pub enum Change {
+    /// The node with this name is new or its result changed. The `index` is
+    /// where it is now.
+    Updated {
+        index: usize,
+        name: Text,
+        result: Result<Value, EvaluationError>,
+    },
+    /// There is no longer a node with this name. The `index` is where it
+    /// used to be.
+    Removed { index: usize, name: Text },
+}
+
+impl Redact for Change {
+    fn redacted(&self) -> Self {
+        match self {
+            Change::Updated {
+                index,
+                name,
+                result,
+            } => Change::Updated {
+                index: *index,
+                name: name.clone(),
+                result: result.redacted(),
+            },
+            Change::Removed { .. } => self.clone(),
+        }
+    }
+}
+
+/// Remembers the results from the previous evaluation so callers only need to
+/// be told about nodes that have changed.
+#[derive(Debug, Default, Clone, PartialEq)]
+pub struct ChangeTracker {
+    previous: Vec<(Text, Result<Value, EvaluationError>)>,
+}
+
+impl ChangeTracker {
+    pub fn new() -> Self {
+        ChangeTracker::default()
+    }
+
+    /// Evaluate the database and return every [`Change`] since the last time
+    /// this method was called.
+    pub fn update(&mut self, db: &dyn Evaluate) -> Vec<Change> {
+        let nodes = db.nodes();
+        let results = db.evaluate();
+
+        let current: Vec<_> = nodes
+            .iter()
+            .map(|n| n.name.clone())
+            .zip(results.iter().cloned())
+            .collect();
+
+        let mut changes = Vec::new();
+        // Nodes are matched by name, so adding, removing, or moving a node
+        // doesn't make the nodes after it look like they changed
+        let mut unmatched: Vec<_> = self.previous.iter().map(Some).collect();
+
+        for (index, (name, result)) in current.iter().enumerate() {
+            let previous = unmatched
+                .iter_mut()
+                .find(|p| matches!(p, Some((n, _)) if n == name))
+                .and_then(Option::take);
+
+            if previous.map(|(_, r)| r) != Some(result) {
+                changes.push(Change::Updated {
+                    index,
+                    name: name.clone(),
+                    result: result.clone(),
+                });
+            }
+        }
+
+        for (index, previous) in unmatched.into_iter().enumerate() {
+            if let Some((name, _)) = previous {
+                changes.push(Change::Removed {
+                    index,
+                    name: name.clone(),
+                });
+            }
+        }
+
+        self.previous = current;
+        changes
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{test_utils::Database, Expression, InputsExt, Node, Sequence};
+
+    #[test]
+    fn everything_is_new_the_first_time() {
+        let mut db = Database::default();
+        db.set_nodes(vec![Node::new("greeting", Expression::string("Hello"))].into());
+        let mut tracker = ChangeTracker::new();
+
+        let got = tracker.update(&db);
+
+        assert_eq!(
+            got,
+            vec![Change::Updated {
+                index: 0,
+                name: "greeting".into(),
+                result: Ok(Value::from("Hello")),
+            }]
+        );
+    }
+
+    #[test]
+    fn only_dependents_of_an_edit_are_reported() {
+        let mut db = Database::default();
+        let nodes: Sequence<_> = vec![
+            Node::new("greeting", Expression::string("Hello")),
+            Node::new("other", Expression::string("World")),
+            Node::new("is-hello", Expression::equals("greeting", "Hello")),
+        ]
+        .into();
+        db.set_nodes(nodes.clone());
+        let mut tracker = ChangeTracker::new();
+        tracker.update(&db);
+
+        let mut nodes = nodes.to_vec();
+        nodes[0] = Node::new("greeting", Expression::string("Goodbye"));
+        db.set_nodes(nodes.into());
+        let got = tracker.update(&db);
+
+        assert_eq!(
+            got,
+            vec![
+                Change::Updated {
+                    index: 0,
+                    name: "greeting".into(),
+                    result: Ok(Value::from("Goodbye")),
+                },
+                Change::Updated {
+                    index: 2,
+                    name: "is-hello".into(),
+                    result: Ok(Value::from(false)),
+                },
+            ]
+        );
+    }
+
+    #[test]
+    fn removed_nodes() {
+        let mut db = Database::default();
+        db.set_nodes(
+            vec![
+                Node::new("first", Expression::string("1")),
+                Node::new("second", Expression::string("2")),
+            ]
+            .into(),
+        );
+        let mut tracker = ChangeTracker::new();
+        tracker.update(&db);
+
+        db.set_nodes(vec![Node::new("first", Expression::string("1"))].into());
+        let got = tracker.update(&db);
+
+        assert_eq!(
+            got,
+            vec![Change::Removed {
+                index: 1,
+                name: "second".into(),
+            }]
+        );
+    }
+
+    #[test]
+    fn nodes_are_matched_by_name() {
+        let mut db = Database::with_nodes(vec![
+            Node::new("first", Expression::string("1")),
+            Node::new("second", Expression::string("2")),
+            Node::new("third", Expression::string("3")),
+        ]);
+        let mut tracker = ChangeTracker::new();
+        tracker.update(&db);
+
+        db.remove_node("first").unwrap();
+        db.move_node("third", 0).unwrap();
+        db.add_node(Node::new("fourth", Expression::string("4")))
+            .unwrap();
+        let got = tracker.update(&db);
+
+        assert_eq!(
+            got,
+            vec![
+                Change::Updated {
+                    index: 2,
+                    name: "fourth".into(),
+                    result: Ok(Value::from("4")),
+                },
+                Change::Removed {
+                    index: 0,
+                    name: "first".into(),
+                },
+            ]
+        );
+    }
+
+    #[test]
+    fn no_changes() {
+        let mut db = Database::default();
+        db.set_nodes(vec![Node::new("first", Expression::string("1"))].into());
+        let mut tracker = ChangeTracker::new();
+        tracker.update(&db);
+
+        let got = tracker.update(&db);
+
+        assert!(got.is_empty());
+    }
+}
@@ -0,0 +1,598 @@
+use crate::{
+    evaluate::{dependencies, Locals, Scope},
+    Evaluate, EvaluationError, Lambda, Operation, Sequence, Text, Value,
+};
+use std::{cmp::Ordering, collections::BTreeMap};
+
+impl Operation {
+    pub fn name(self) -> &'static str {
+        match self {
+            Operation::Map => "map",
+            Operation::Filter => "filter",
+            Operation::Any => "any",
+            Operation::All => "all",
+            Operation::Count => "count",
+            Operation::Sum => "sum",
+            Operation::Min => "min",
+            Operation::Max => "max",
+            Operation::SortBy => "sort_by",
+            Operation::GroupBy => "group_by",
+            Operation::Unique => "unique",
+            Operation::First => "first",
+            Operation::Last => "last",
+        }
+    }
+
+    /// Does this operation only make sense with a lambda?
+    fn requires_lambda(self) -> bool {
+        matches!(
+            self,
+            Operation::Map | Operation::Filter | Operation::GroupBy
+        )
+    }
+}
+
+impl Lambda {
+    /// The names used by the lambda's body which don't refer to its
+    /// parameter or another node in the body.
+    pub(crate) fn free_variables(&self) -> Vec<&Text> {
+        let mut bound = vec![&self.parameter];
+        let mut free = Vec::new();
+
+        for node in self.body.iter() {
+            for dep in dependencies(&node.expr) {
+                if !bound.contains(&dep) && !free.contains(&dep) {
+                    free.push(dep);
+                }
+            }
+            bound.push(&node.name);
+        }
+
+        free
+    }
+}
+
+fn call_lambda(
+    db: &dyn Evaluate,
+    scope: &dyn Scope,
+    lambda: &Lambda,
+    item: &Value,
+) -> Result<Value, EvaluationError> {
+    let mut locals = Locals::new(scope);
+    locals.bind(lambda.parameter.clone(), Ok(item.clone()));
+
+    locals
+        .evaluate_body(db, &lambda.body)
+        .unwrap_or_else(|| Err("The lambda has no body".into()))
+}
+
+/// Evaluate an [`Expression::Collection`][crate::Expression::Collection].
+pub(crate) fn apply(
+    db: &dyn Evaluate,
+    scope: &dyn Scope,
+    operation: Operation,
+    target: Text,
+    lambda: Option<&Lambda>,
+) -> Result<Value, EvaluationError> {
+    let name = operation.name();
+
+    if lambda.is_none() && operation.requires_lambda() {
+        return Err(format!("{}() needs a lambda", name).into());
+    }
+
+    let items = match scope.lookup(&target)? {
+        Value::Array(items) => items,
+        Value::Indeterminate => return Ok(Value::Indeterminate),
+        _ => return Err(format!("\"{}\" is not an array", target).into()),
+    };
+
+    // The values the operation works with, which are the items themselves
+    // if there is no lambda
+    let mut keys = Vec::with_capacity(items.len());
+    for (i, item) in items.iter().enumerate() {
+        let key = match lambda {
+            Some(lambda) => call_lambda(db, scope, lambda, item).map_err(|e| match e {
+                EvaluationError::Failed { message } => {
+                    format!("{}() failed on item {}: {}", name, i, message).into()
+                }
+                upstream => upstream,
+            })?,
+            None => item.clone(),
+        };
+        keys.push(key);
+    }
+
+    if keys.contains(&Value::Indeterminate) {
+        return Ok(Value::Indeterminate);
+    }
+
+    let op = Apply {
+        name,
+        target: &target,
+        items: &items,
+        keys: &keys,
+    };
+
+    match operation {
+        Operation::Map => Ok(Value::Array(keys.into())),
+        Operation::Filter => op.filter(),
+        Operation::Any => Ok(Value::from(op.booleans()?.contains(&true))),
+        Operation::All => Ok(Value::from(!op.booleans()?.contains(&false))),
+        Operation::Count => match lambda {
+            Some(_) => op.count(),
+            None => length(items.len()),
+        },
+        Operation::Sum => op.sum(),
+        Operation::Min => op.extreme(Ordering::Less),
+        Operation::Max => op.extreme(Ordering::Greater),
+        Operation::SortBy => op.sort_by(),
+        Operation::GroupBy => op.group_by(),
+        Operation::Unique => Ok(op.unique()),
+        Operation::First => op.find(0..items.len(), lambda.is_some()),
+        Operation::Last => op.find((0..items.len()).rev(), lambda.is_some()),
+    }
+}
+
+/// An operation being applied to the items in a collection, where `keys`
+/// are the values computed from each item.
+struct Apply<'a> {
+    name: &'a str,
+    target: &'a Text,
+    items: &'a Sequence<Value>,
+    keys: &'a [Value],
+}
+
+impl Apply<'_> {
+    fn type_error(&self, index: usize, expected: &str) -> EvaluationError {
+        format!(
+            "{}() expects {}, but item {} was {}",
+            self.name, expected, index, self.keys[index]
+        )
+        .into()
+    }
+
+    fn booleans(&self) -> Result<Vec<bool>, EvaluationError> {
+        self.keys
+            .iter()
+            .enumerate()
+            .map(|(i, key)| match key {
+                Value::Boolean(b) => Ok(*b),
+                _ => Err(self.type_error(i, "a boolean")),
+            })
+            .collect()
+    }
+
+    fn filter(&self) -> Result<Value, EvaluationError> {
+        let keep = self.booleans()?;
+        let items = self
+            .items
+            .iter()
+            .zip(keep)
+            .filter(|(_, keep)| *keep)
+            .map(|(item, _)| item.clone())
+            .collect();
+
+        Ok(Value::Array(items))
+    }
+
+    fn count(&self) -> Result<Value, EvaluationError> {
+        let count = self.booleans()?.into_iter().filter(|b| *b).count();
+        length(count)
+    }
+
+    fn sum(&self) -> Result<Value, EvaluationError> {
+        let mut total: i32 = 0;
+
+        for (i, key) in self.keys.iter().enumerate() {
+            let n = match key {
+                Value::Number(n) => *n,
+                _ => return Err(self.type_error(i, "a number")),
+            };
+            total = total
+                .checked_add(n)
+                .ok_or_else(|| format!("The sum of \"{}\" overflowed", self.target))?;
+        }
+
+        Ok(Value::from(total))
+    }
+
+    /// Make sure every key can be compared with every other key.
+    fn check_comparable(&self) -> Result<(), EvaluationError> {
+        let first = match self.keys.first() {
+            Some(first) => first,
+            None => return Ok(()),
+        };
+
+        for (i, key) in self.keys.iter().enumerate() {
+            if compare(first, key).is_none() {
+                return Err(self.type_error(i, "numbers, strings, or booleans of the same type"));
+            }
+        }
+
+        Ok(())
+    }
+
+    /// Find the item whose key is the furthest in the `direction` ordering.
+    fn extreme(&self, direction: Ordering) -> Result<Value, EvaluationError> {
+        self.check_comparable()?;
+
+        let mut best: Option<usize> = None;
+
+        for i in 0..self.keys.len() {
+            best = match best {
+                Some(b) if compare(&self.keys[i], &self.keys[b]) != Some(direction) => Some(b),
+                _ => Some(i),
+            };
+        }
+
+        best.map(|i| self.items[i].clone())
+            .ok_or_else(|| format!("{}() of an empty array", self.name).into())
+    }
+
+    fn sort_by(&self) -> Result<Value, EvaluationError> {
+        self.check_comparable()?;
+
+        let mut indices: Vec<usize> = (0..self.items.len()).collect();
+        indices.sort_by(|&a, &b| compare(&self.keys[a], &self.keys[b]).unwrap_or(Ordering::Equal));
+
+        Ok(Value::Array(
+            indices.into_iter().map(|i| self.items[i].clone()).collect(),
+        ))
+    }
+
+    fn group_by(&self) -> Result<Value, EvaluationError> {
+        let mut groups: BTreeMap<Text, Vec<Value>> = BTreeMap::new();
+
+        for (i, (item, key)) in self.items.iter().zip(self.keys).enumerate() {
+            let key = match key {
+                Value::String(s) => s.clone(),
+                Value::Number(n) => n.to_string().into(),
+                Value::Boolean(b) => b.to_string().into(),
+                _ => return Err(self.type_error(i, "a string, number, or boolean key")),
+            };
+            groups.entry(key).or_default().push(item.clone());
+        }
+
+        let groups: BTreeMap<Text, Value> = groups
+            .into_iter()
+            .map(|(key, items)| (key, Value::from(items)))
+            .collect();
+
+        Ok(Value::Object(groups.into()))
+    }
+
+    fn unique(&self) -> Value {
+        let mut seen = Vec::new();
+        let mut unique = Vec::new();
+
+        for (item, key) in self.items.iter().zip(self.keys) {
+            if !seen.contains(&key) {
+                seen.push(key);
+                unique.push(item.clone());
+            }
+        }
+
+        Value::from(unique)
+    }
+
+    /// Get the first item from `candidates` (a list of indices), using the
+    /// keys as a predicate when `use_predicate` is set.
+    fn find(
+        &self,
+        mut candidates: impl Iterator<Item = usize>,
+        use_predicate: bool,
+    ) -> Result<Value, EvaluationError> {
+        if use_predicate {
+            self.booleans()?;
+        }
+
+        candidates
+            .find(|&i| !use_predicate || self.keys[i] == Value::from(true))
+            .map(|i| self.items[i].clone())
+            .ok_or_else(|| {
+                if use_predicate {
+                    format!("No items in \"{}\" matched", self.target).into()
+                } else {
+                    format!("\"{}\" is empty", self.target).into()
+                }
+            })
+    }
+}
+
+pub(crate) fn compare(a: &Value, b: &Value) -> Option<Ordering> {
+    match (a, b) {
+        (Value::Number(a), Value::Number(b)) => Some(a.cmp(b)),
+        (Value::String(a), Value::String(b)) => Some(a.cmp(b)),
+        (Value::Boolean(a), Value::Boolean(b)) => Some(a.cmp(b)),
+        (Value::Instant(a), Value::Instant(b)) => Some(a.cmp(b)),
+        (Value::Duration(a), Value::Duration(b)) => Some(a.cmp(b)),
+        _ => None,
+    }
+}
+
+fn length(len: usize) -> Result<Value, EvaluationError> {
+    i32::try_from(len)
+        .map(Value::from)
+        .map_err(|_| "The length overflowed".into())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{test_utils::Database, Argument, Expression, InputsExt, Node, Object, Response};
+
+    fn user(id: i32, status: &str, team: &str) -> Value {
+        let mut fields = BTreeMap::new();
+        fields.insert(Text::from("id"), Value::from(id));
+        fields.insert(Text::from("status"), Value::from(status));
+        fields.insert(Text::from("team"), Value::from(team));
+        Value::Object(Object::from(fields))
+    }
+
+    /// Evaluate `extra` in a workspace where `items` is an array of users
+    /// (taken from a response body) and `status` is `"active"`.
+    fn evaluate(extra: Vec<Node>) -> Vec<Result<Value, EvaluationError>> {
+        let users = Value::from(vec![
+            user(3, "active", "red"),
+            user(1, "failed", "blue"),
+            user(2, "active", "red"),
+        ]);
+        let response = Response {
+            url: "http://example.com/".into(),
+            status: 200,
+            status_text: Text::from("OK"),
+            body: users,
+            metrics: None,
+        };
+        let mut nodes = vec![
+            Node::new(
+                "users",
+                Expression::Request {
+                    url: "http://example.com/".into(),
+                    auth: None,
+                    refresh: None,
+                    response: Some(response),
+                    error: None,
+                },
+            ),
+            Node::new("items", Expression::get("users", "body")),
+            Node::new("status", Expression::string("active")),
+        ];
+        let skip = nodes.len();
+        nodes.extend(extra);
+
+        let mut db = Database::default();
+        db.set_nodes(nodes.into());
+
+        db.evaluate().iter().skip(skip).cloned().collect()
+    }
+
+    /// A lambda which gets a field from each user.
+    fn field(name: &str) -> Lambda {
+        Lambda::new(
+            "user",
+            vec![Node::new("value", Expression::get("user", name))],
+        )
+    }
+
+    /// A lambda which checks if a user has the workspace's `status`.
+    fn has_status() -> Lambda {
+        Lambda::new(
+            "user",
+            vec![
+                Node::new("value", Expression::get("user", "status")),
+                Node::new(
+                    "is-match",
+                    Expression::call(
+                        "contains",
+                        [Argument::node("value"), Argument::node("status")],
+                    ),
+                ),
+            ],
+        )
+    }
+
+    fn ids(value: &Value) -> Vec<i32> {
+        match value {
+            Value::Array(items) => items
+                .iter()
+                .map(|item| match item {
+                    Value::Object(obj) => match obj.get("id") {
+                        Some(Value::Number(n)) => *n,
+                        _ => panic!(),
+                    },
+                    _ => panic!(),
+                })
+                .collect(),
+            _ => panic!("Expected an array, found {}", value),
+        }
+    }
+
+    #[test]
+    fn operations_with_lambdas() {
+        let got = evaluate(vec![
+            Node::new(
+                "ids",
+                Expression::collection(Operation::Map, "items", field("id")),
+            ),
+            Node::new(
+                "active",
+                Expression::collection(Operation::Filter, "items", has_status()),
+            ),
+            Node::new(
+                "any-active",
+                Expression::collection(Operation::Any, "items", has_status()),
+            ),
+            Node::new(
+                "all-active",
+                Expression::collection(Operation::All, "items", has_status()),
+            ),
+            Node::new(
+                "active-count",
+                Expression::collection(Operation::Count, "items", has_status()),
+            ),
+            Node::new(
+                "id-total",
+                Expression::collection(Operation::Sum, "items", field("id")),
+            ),
+            Node::new(
+                "sorted",
+                Expression::collection(Operation::SortBy, "items", field("id")),
+            ),
+            Node::new(
+                "newest",
+                Expression::collection(Operation::Max, "items", field("id")),
+            ),
+            Node::new(
+                "first-failed",
+                Expression::collection(
+                    Operation::First,
+                    "items",
+                    Lambda::new(
+                        "user",
+                        vec![
+                            Node::new("status", Expression::get("user", "status")),
+                            Node::new("failed", Expression::equals("status", "failed")),
+                        ],
+                    ),
+                ),
+            ),
+        ]);
+
+        assert_eq!(
+            got[0],
+            Ok(Value::from(vec![
+                Value::from(3),
+                Value::from(1),
+                Value::from(2)
+            ]))
+        );
+        assert_eq!(ids(got[1].as_ref().unwrap()), [3, 2]);
+        assert_eq!(got[2], Ok(Value::from(true)));
+        assert_eq!(got[3], Ok(Value::from(false)));
+        assert_eq!(got[4], Ok(Value::from(2)));
+        assert_eq!(got[5], Ok(Value::from(6)));
+        assert_eq!(ids(got[6].as_ref().unwrap()), [1, 2, 3]);
+        assert_eq!(got[7], Ok(user(3, "active", "red")));
+        assert_eq!(got[8], Ok(user(1, "failed", "blue")));
+    }
+
+    #[test]
+    fn grouping_and_uniqueness() {
+        let got = evaluate(vec![
+            Node::new(
+                "by-team",
+                Expression::collection(Operation::GroupBy, "items", field("team")),
+            ),
+            Node::new(
+                "one-per-status",
+                Expression::collection(Operation::Unique, "items", field("status")),
+            ),
+            Node::new(
+                "count",
+                Expression::Collection {
+                    operation: Operation::Count,
+                    target: "items".into(),
+                    lambda: None,
+                },
+            ),
+        ]);
+
+        let teams = match &got[0] {
+            Ok(Value::Object(teams)) => teams.clone(),
+            other => panic!("{:?}", other),
+        };
+        assert_eq!(
+            teams.keys().map(|k| &**k).collect::<Vec<_>>(),
+            ["blue", "red"]
+        );
+        assert_eq!(ids(&teams["red"]), [3, 2]);
+        assert_eq!(ids(got[1].as_ref().unwrap()), [3, 1]);
+        assert_eq!(got[2], Ok(Value::from(3)));
+    }
+
+    #[test]
+    fn errors() {
+        let got = evaluate(vec![
+            Node::new(
+                "not-numbers",
+                Expression::collection(Operation::Sum, "items", field("status")),
+            ),
+            Node::new(
+                "missing-field",
+                Expression::collection(Operation::Map, "items", field("name")),
+            ),
+            Node::new(
+                "no-lambda",
+                Expression::Collection {
+                    operation: Operation::Map,
+                    target: "items".into(),
+                    lambda: None,
+                },
+            ),
+            Node::new(
+                "not-an-array",
+                Expression::collection(Operation::Map, "status", field("id")),
+            ),
+        ]);
+
+        assert_eq!(
+            got,
+            vec![
+                Err(EvaluationError::from(
+                    "sum() expects a number, but item 0 was \"active\""
+                )),
+                Err(EvaluationError::from(
+                    "map() failed on item 0: \"user\" has no \"name\" field"
+                )),
+                Err(EvaluationError::from("map() needs a lambda")),
+                Err(EvaluationError::from("\"status\" is not an array")),
+            ]
+        );
+    }
+
+    #[test]
+    fn lambdas_can_refer_to_workspace_nodes() {
+        assert!(field("id").free_variables().is_empty());
+        assert_eq!(has_status().free_variables(), [&Text::from("status")]);
+
+        let mut db = Database::default();
+        db.set_nodes(
+            vec![
+                Node::new("status", Expression::string("active")),
+                Node::new(
+                    "statuses",
+                    Expression::call(
+                        "split",
+                        [Argument::constant("active,failed"), Argument::constant(",")],
+                    ),
+                ),
+                Node::new(
+                    "matching",
+                    Expression::collection(
+                        Operation::Count,
+                        "statuses",
+                        Lambda::new(
+                            "s",
+                            vec![Node::new(
+                                "same",
+                                Expression::call(
+                                    "contains",
+                                    [Argument::node("s"), Argument::node("status")],
+                                ),
+                            )],
+                        ),
+                    ),
+                ),
+            ]
+            .into(),
+        );
+
+        assert_eq!(db.evaluate()[2], Ok(Value::from(1)));
+        let graph = crate::DependencyGraph::new(&db);
+        assert!(graph
+            .edges
+            .iter()
+            .any(|edge| edge.from == Text::from("status") && edge.to == Text::from("matching")));
+    }
+}
@@ -0,0 +1,252 @@
+use serde::{Deserialize, Deserializer, Serialize, Serializer};
+use std::{
+    fmt::{self, Debug, Display, Formatter},
+    str::FromStr,
+    time::SystemTime,
+};
+use time::{format_description::well_known::Rfc3339, OffsetDateTime};
+
+/// A point in time, with millisecond precision.
+///
+/// Instants are always stored in UTC and are written as RFC 3339 timestamps
+/// (e.g. `2024-01-31T09:30:00Z`).
+#[derive(Copy, Clone, PartialEq, Eq, PartialOrd, Ord, Hash)]
+#[cfg_attr(feature = "typescript", derive(ts_rs::TS), ts(type = "string"))]
+pub struct Instant(i64);
+
+impl Instant {
+    pub const UNIX_EPOCH: Instant = Instant(0);
+
+    /// The current time, according to the system clock.
+    ///
+    /// This is normally only used by hosts when setting
+    /// [`Inputs::now()`][crate::Inputs::now].
+    pub fn now() -> Self {
+        let ms = match SystemTime::now().duration_since(SystemTime::UNIX_EPOCH) {
+            Ok(elapsed) => elapsed.as_millis() as i64,
+            Err(e) => -(e.duration().as_millis() as i64),
+        };
+
+        Instant(ms)
+    }
+
+    /// Create an instant from the number of milliseconds since the Unix
+    /// epoch, returning `None` if it can't be represented as an RFC 3339
+    /// timestamp.
+    pub fn from_unix_millis(ms: i64) -> Option<Self> {
+        let instant = Instant(ms);
+        instant.to_offset_date_time().map(|_| instant)
+    }
+
+    pub fn unix_millis(self) -> i64 {
+        self.0
+    }
+
+    pub fn checked_add(self, duration: Duration) -> Option<Instant> {
+        self.0
+            .checked_add(duration.0)
+            .and_then(Instant::from_unix_millis)
+    }
+
+    pub fn checked_sub(self, duration: Duration) -> Option<Instant> {
+        self.0
+            .checked_sub(duration.0)
+            .and_then(Instant::from_unix_millis)
+    }
+
+    /// The time elapsed between `earlier` and this instant.
+    pub fn duration_since(self, earlier: Instant) -> Option<Duration> {
+        self.0.checked_sub(earlier.0).map(Duration)
+    }
+
+    fn to_offset_date_time(self) -> Option<OffsetDateTime> {
+        OffsetDateTime::from_unix_timestamp_nanos(i128::from(self.0) * 1_000_000).ok()
+    }
+}
+
+impl FromStr for Instant {
+    type Err = InvalidTimestamp;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let parsed = OffsetDateTime::parse(s, &Rfc3339).map_err(|_| InvalidTimestamp)?;
+        let ms = parsed.unix_timestamp_nanos() / 1_000_000;
+
+        i64::try_from(ms)
+            .ok()
+            .and_then(Instant::from_unix_millis)
+            .ok_or(InvalidTimestamp)
+    }
+}
+
+impl Display for Instant {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        let formatted = self
+            .to_offset_date_time()
+            .and_then(|dt| dt.format(&Rfc3339).ok())
+            .ok_or(fmt::Error)?;
+
+        f.write_str(&formatted)
+    }
+}
+
+impl Debug for Instant {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        write!(f, "Instant({})", self)
+    }
+}
+
+impl Serialize for Instant {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.collect_str(self)
+    }
+}
+
+impl<'de> Deserialize<'de> for Instant {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        let s = String::deserialize(deserializer)?;
+        s.parse().map_err(serde::de::Error::custom)
+    }
+}
+
+/// The error returned when a string isn't a valid RFC 3339 timestamp.
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub struct InvalidTimestamp;
+
+impl Display for InvalidTimestamp {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        f.write_str("Expected an RFC 3339 timestamp (e.g. \"2024-01-31T09:30:00Z\")")
+    }
+}
+
+impl std::error::Error for InvalidTimestamp {}
+
+/// A signed length of time, with millisecond precision.
+///
+/// Durations are serialized as a number of milliseconds.
+#[derive(
+    Debug, Default, Copy, Clone, PartialEq, Eq, PartialOrd, Ord, Hash, Serialize, Deserialize,
+)]
+#[cfg_attr(feature = "typescript", derive(ts_rs::TS), ts(type = "number"))]
+pub struct Duration(i64);
+
+impl Duration {
+    pub const ZERO: Duration = Duration(0);
+
+    pub const fn from_millis(ms: i64) -> Self {
+        Duration(ms)
+    }
+
+    pub fn as_millis(self) -> i64 {
+        self.0
+    }
+
+    pub fn checked_add(self, other: Duration) -> Option<Duration> {
+        self.0.checked_add(other.0).map(Duration)
+    }
+
+    pub fn checked_sub(self, other: Duration) -> Option<Duration> {
+        self.0.checked_sub(other.0).map(Duration)
+    }
+}
+
+/// Durations are displayed using their largest units (e.g. `1h 30m` or
+/// `250ms`).
+impl Display for Duration {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        const UNITS: [(&str, u64); 5] = [
+            ("d", 24 * 60 * 60 * 1000),
+            ("h", 60 * 60 * 1000),
+            ("m", 60 * 1000),
+            ("s", 1000),
+            ("ms", 1),
+        ];
+
+        if self.0 == 0 {
+            return f.write_str("0s");
+        }
+        if self.0 < 0 {
+            f.write_str("-")?;
+        }
+
+        let mut remaining = self.0.unsigned_abs();
+        let mut first = true;
+
+        for (suffix, size) in UNITS {
+            let count = remaining / size;
+            remaining %= size;
+
+            if count > 0 {
+                if !first {
+                    f.write_str(" ")?;
+                }
+                write!(f, "{}{}", count, suffix)?;
+                first = false;
+            }
+        }
+
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parse_and_format_timestamps() {
+        let inputs = vec![
+            ("1970-01-01T00:00:00Z", 0, "1970-01-01T00:00:00Z"),
+            (
+                "2024-01-31T09:30:00.250Z",
+                1_706_693_400_250,
+                "2024-01-31T09:30:00.25Z",
+            ),
+            // Offsets are normalized to UTC
+            (
+                "2024-01-31T19:30:00+10:00",
+                1_706_693_400_000,
+                "2024-01-31T09:30:00Z",
+            ),
+        ];
+
+        for (src, ms, formatted) in inputs {
+            let got: Instant = src.parse().unwrap();
+
+            assert_eq!(got.unix_millis(), ms);
+            assert_eq!(got.to_string(), formatted);
+        }
+    }
+
+    #[test]
+    fn invalid_timestamps() {
+        for src in ["", "yesterday", "2024-01-31", "2024-13-01T00:00:00Z"] {
+            assert_eq!(src.parse::<Instant>(), Err(InvalidTimestamp), "{:?}", src);
+        }
+    }
+
+    #[test]
+    fn display_durations() {
+        let inputs = vec![
+            (0, "0s"),
+            (250, "250ms"),
+            (90 * 60 * 1000, "1h 30m"),
+            (-5000, "-5s"),
+            (((24 + 1) * 60 * 60 + 60 + 1) * 1000 + 1, "1d 1h 1m 1s 1ms"),
+        ];
+
+        for (ms, should_be) in inputs {
+            assert_eq!(Duration::from_millis(ms).to_string(), should_be);
+        }
+    }
+
+    #[test]
+    fn instants_are_serialized_as_timestamps() {
+        let instant: Instant = "2024-01-31T09:30:00Z".parse().unwrap();
+
+        let got = serde_json::to_value(instant).unwrap();
+
+        assert_eq!(got, serde_json::json!("2024-01-31T09:30:00Z"));
+        let round_tripped: Instant = serde_json::from_value(got).unwrap();
+        assert_eq!(round_tripped, instant);
+    }
+}
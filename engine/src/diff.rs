@@ -0,0 +1,655 @@
+//! Comparing and merging versions of a workspace at the [`Node`] level, so
+//! workspaces stored in version control can be reviewed and merged.
+
+use crate::{ApiKeyLocation, Argument, Auth, Expression, Json, Node, SchemaSource, Text, Value};
+use std::{
+    collections::BTreeMap,
+    fmt::{self, Display, Formatter},
+    sync::Arc,
+};
+
+/// Something that happened to a node between two versions of a workspace.
+#[derive(Debug, Clone, PartialEq, Eq, serde::Serialize, serde::Deserialize)]
+#[serde(tag = "type", rename_all = "kebab-case")]
+pub enum NodeChange {
+    Added {
+        node: Node,
+    },
+    Removed {
+        node: Node,
+    },
+    /// The node's name changed but its expression didn't.
+    Renamed {
+        from: Text,
+        to: Text,
+    },
+    /// The node now does something different (e.g. it refers to another
+    /// node or uses a different kind of expression).
+    ExpressionChanged {
+        name: Text,
+        before: Arc<Expression>,
+        after: Arc<Expression>,
+    },
+    /// Only the node's constants changed (e.g. a string's value, the value
+    /// being compared against, or a request's URL or response).
+    LiteralChanged {
+        name: Text,
+        before: Arc<Expression>,
+        after: Arc<Expression>,
+    },
+}
+
+impl Display for NodeChange {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        match self {
+            NodeChange::Added { node } => write!(f, "Added \"{}\"", node.name),
+            NodeChange::Removed { node } => write!(f, "Removed \"{}\"", node.name),
+            NodeChange::Renamed { from, to } => write!(f, "Renamed \"{}\" to \"{}\"", from, to),
+            NodeChange::ExpressionChanged { name, .. } => {
+                write!(f, "Changed the expression for \"{}\"", name)
+            }
+            NodeChange::LiteralChanged { name, .. } => {
+                write!(f, "Changed a literal in \"{}\"", name)
+            }
+        }
+    }
+}
+
+/// Work out how the nodes in `before` became the nodes in `after`.
+///
+/// Nodes are matched by name. A node which was removed while another with
+/// the same expression was added is treated as being renamed.
+pub fn diff(before: &[Node], after: &[Node]) -> Vec<NodeChange> {
+    let (before_to_after, after_to_before) = pair(before, after);
+    let mut changes = Vec::new();
+
+    for (node, original) in after.iter().zip(&after_to_before) {
+        let original = match original {
+            Some(i) => &before[*i],
+            None => {
+                changes.push(NodeChange::Added { node: node.clone() });
+                continue;
+            }
+        };
+
+        if original.name != node.name {
+            changes.push(NodeChange::Renamed {
+                from: original.name.clone(),
+                to: node.name.clone(),
+            });
+        } else if original.expr != node.expr {
+            let name = node.name.clone();
+            let before = Arc::clone(&original.expr);
+            let after = Arc::clone(&node.expr);
+
+            if without_literals(&before) == without_literals(&after) {
+                changes.push(NodeChange::LiteralChanged {
+                    name,
+                    before,
+                    after,
+                });
+            } else {
+                changes.push(NodeChange::ExpressionChanged {
+                    name,
+                    before,
+                    after,
+                });
+            }
+        }
+    }
+
+    for (node, _) in before
+        .iter()
+        .zip(&before_to_after)
+        .filter(|(_, matched)| matched.is_none())
+    {
+        changes.push(NodeChange::Removed { node: node.clone() });
+    }
+
+    changes
+}
+
+/// Match up the nodes in two versions of a workspace, returning the index
+/// each node was matched with in the other version.
+///
+/// Nodes are matched by name first, then any leftovers with identical
+/// expressions are matched as renames.
+fn pair(before: &[Node], after: &[Node]) -> (Vec<Option<usize>>, Vec<Option<usize>>) {
+    let mut before_to_after = vec![None; before.len()];
+    let mut after_to_before = vec![None; after.len()];
+
+    let mut by_name = BTreeMap::new();
+    for (i, node) in before.iter().enumerate() {
+        by_name.entry(&node.name).or_insert(i);
+    }
+
+    for (j, node) in after.iter().enumerate() {
+        if let Some(&i) = by_name.get(&node.name) {
+            if before_to_after[i].is_none() {
+                before_to_after[i] = Some(j);
+                after_to_before[j] = Some(i);
+            }
+        }
+    }
+
+    for (j, node) in after.iter().enumerate() {
+        if after_to_before[j].is_some() {
+            continue;
+        }
+
+        let renamed = before.iter().enumerate().position(|(i, original)| {
+            before_to_after[i].is_none()
+                && same_definition(&original.expr, &node.expr)
+                && !after.iter().any(|n| n.name == original.name)
+        });
+        if let Some(i) = renamed {
+            before_to_after[i] = Some(j);
+            after_to_before[j] = Some(i);
+        }
+    }
+
+    (before_to_after, after_to_before)
+}
+
+/// A copy of the expression with every constant blanked out, so expressions
+/// which only differ by their constants compare as equal.
+///
+/// The names of the nodes an expression refers to (and a property's name)
+/// are part of its structure, so they are kept.
+fn without_literals(expr: &Expression) -> Expression {
+    let mut expr = without_runtime(expr);
+
+    match &mut expr {
+        Expression::StringConstant { value } => *value = Text::default(),
+        Expression::Request {
+            url,
+            auth,
+            refresh,
+            response: _,
+            error: _,
+        } => {
+            *url = Text::default();
+            *refresh = None;
+            if let Some(Auth::ApiKey { name, location, .. }) = auth {
+                *name = Text::default();
+                *location = ApiKeyLocation::default();
+            }
+        }
+        Expression::Equals { target: _, value } => *value = Value::Indeterminate,
+        Expression::GetProperty {
+            target: _,
+            field: _,
+        } => {}
+        Expression::Assert { target: _, message } => *message = Text::default(),
+        Expression::Variable { name } | Expression::Secret { name } => *name = Text::default(),
+        Expression::OAuth2Token {
+            token_url,
+            client_id: _,
+            client_secret: _,
+            scope,
+            response: _,
+            error: _,
+        } => {
+            *token_url = Text::default();
+            *scope = None;
+        }
+        Expression::Matches { target: _, pattern } => *pattern = Text::default(),
+        Expression::Capture {
+            target: _,
+            pattern,
+            group,
+        } => {
+            *pattern = Text::default();
+            *group = None;
+        }
+        Expression::Call {
+            function: _,
+            arguments,
+        } => {
+            *arguments = arguments
+                .iter()
+                .map(|argument| match argument {
+                    Argument::Constant { .. } => Argument::constant(Value::Indeterminate),
+                    Argument::Node { .. } => argument.clone(),
+                })
+                .collect();
+        }
+        Expression::Collection {
+            operation: _,
+            target: _,
+            lambda,
+        } => {
+            if let Some(lambda) = lambda {
+                lambda.body = lambda
+                    .body
+                    .iter()
+                    .map(|node| Node::new(node.name.clone(), without_literals(&node.expr)))
+                    .collect();
+            }
+        }
+        Expression::ValidateSchema { target: _, schema } => match schema {
+            SchemaSource::Inline { schema } => *schema = Json::from(serde_json::Value::Null),
+            SchemaSource::File { path } => *path = Text::default(),
+        },
+        Expression::Previous { target: _ } | Expression::Changed { target: _ } => {}
+    }
+
+    expr
+}
+
+/// A copy of the expression without the response or error recorded the
+/// last time it was evaluated, leaving just what the user wrote.
+fn without_runtime(expr: &Expression) -> Expression {
+    let mut expr = expr.clone();
+
+    if let Expression::Request {
+        response, error, ..
+    }
+    | Expression::OAuth2Token {
+        response, error, ..
+    } = &mut expr
+    {
+        *response = None;
+        *error = None;
+    }
+
+    expr
+}
+
+/// Whether two expressions are the same once their runtime state is ignored.
+fn same_definition(a: &Expression, b: &Expression) -> bool {
+    a == b || without_runtime(a) == without_runtime(b)
+}
+
+/// The outcome of a three-way [`merge()`].
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Merged {
+    /// The merged nodes. Nodes which are in conflict are kept the way they
+    /// are in "ours".
+    pub nodes: Vec<Node>,
+    pub conflicts: Vec<Conflict>,
+}
+
+impl Merged {
+    pub fn is_clean(&self) -> bool {
+        self.conflicts.is_empty()
+    }
+}
+
+/// A node which was changed in incompatible ways on both sides of a merge.
+#[derive(Debug, Clone, PartialEq, Eq, serde::Serialize, serde::Deserialize)]
+pub struct Conflict {
+    pub name: Text,
+    pub base: Option<Node>,
+    pub ours: Option<Node>,
+    pub theirs: Option<Node>,
+}
+
+impl Display for Conflict {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        match (&self.base, &self.ours, &self.theirs) {
+            (Some(_), Some(_), Some(_)) => {
+                write!(f, "\"{}\" was changed on both sides", self.name)
+            }
+            (Some(_), _, _) => write!(
+                f,
+                "\"{}\" was removed on one side and changed on the other",
+                self.name
+            ),
+            (None, _, _) => write!(
+                f,
+                "\"{}\" was added on both sides with different expressions",
+                self.name
+            ),
+        }
+    }
+}
+
+/// Merge the changes made in `ours` and `theirs` since they diverged from
+/// `base`.
+///
+/// A node's name and expression are merged separately, so one side can
+/// rename a node while the other changes its expression. Nodes keep the
+/// order they have in `ours`, with nodes added by `theirs` placed after the
+/// node they follow in `theirs`.
+pub fn merge(base: &[Node], ours: &[Node], theirs: &[Node]) -> Merged {
+    let (base_to_ours, ours_to_base) = pair(base, ours);
+    let (base_to_theirs, theirs_to_base) = pair(base, theirs);
+    let mut conflicts = Vec::new();
+
+    let merged_base: Vec<Option<Node>> = base
+        .iter()
+        .enumerate()
+        .map(|(i, original)| {
+            let ours = base_to_ours[i].map(|j| &ours[j]);
+            let theirs = base_to_theirs[i].map(|k| &theirs[k]);
+
+            let merged = match (ours, theirs) {
+                (None, None) => return None,
+                (Some(node), None) | (None, Some(node))
+                    if node.name == original.name
+                        && same_definition(&node.expr, &original.expr) =>
+                {
+                    return None
+                }
+                (Some(ours), Some(theirs)) => {
+                    merge3(&original.name, &ours.name, &theirs.name, PartialEq::eq)
+                        .zip(merge3(&original.expr, &ours.expr, &theirs.expr, |a, b| {
+                            same_definition(a, b)
+                        }))
+                        .map(|(name, expr)| Node { name, expr })
+                }
+                _ => None,
+            };
+
+            if merged.is_none() {
+                conflicts.push(Conflict {
+                    name: original.name.clone(),
+                    base: Some(original.clone()),
+                    ours: ours.cloned(),
+                    theirs: theirs.cloned(),
+                });
+            }
+
+            merged.or_else(|| ours.cloned())
+        })
+        .collect();
+
+    let mut nodes = Vec::new();
+
+    for (node, original) in ours.iter().zip(&ours_to_base) {
+        let node = match original {
+            Some(i) => match &merged_base[*i] {
+                Some(merged) => merged,
+                None => continue,
+            },
+            None => node,
+        };
+        let end = nodes.len();
+        insert(&mut nodes, &mut conflicts, end, node.clone());
+    }
+
+    for (k, node) in theirs.iter().enumerate() {
+        if theirs_to_base[k].is_some() {
+            continue;
+        }
+
+        // Keep the node next to the one it followed in theirs
+        let position = theirs[..k]
+            .iter()
+            .zip(&theirs_to_base)
+            .rev()
+            .filter_map(|(previous, original)| match original {
+                Some(i) => merged_base[*i].as_ref().map(|n| &n.name),
+                None => Some(&previous.name),
+            })
+            .find_map(|name| nodes.iter().position(|n| n.name == *name))
+            .map_or(0, |index| index + 1);
+
+        insert(&mut nodes, &mut conflicts, position, node.clone());
+    }
+
+    Merged { nodes, conflicts }
+}
+
+/// Add a node to the merged workspace, recording a conflict if another node
+/// with the same name but a different expression is already there.
+fn insert(nodes: &mut Vec<Node>, conflicts: &mut Vec<Conflict>, index: usize, node: Node) {
+    match nodes.iter().find(|n| n.name == node.name) {
+        Some(existing) if same_definition(&existing.expr, &node.expr) => {}
+        Some(existing) => conflicts.push(Conflict {
+            name: node.name.clone(),
+            base: None,
+            ours: Some(existing.clone()),
+            theirs: Some(node),
+        }),
+        None => nodes.insert(index, node),
+    }
+}
+
+/// Three-way merge a single value, returning `None` if both sides changed
+/// it in different ways.
+fn merge3<T: Clone>(base: &T, ours: &T, theirs: &T, same: impl Fn(&T, &T) -> bool) -> Option<T> {
+    if same(ours, theirs) || same(theirs, base) {
+        Some(ours.clone())
+    } else if same(ours, base) {
+        Some(theirs.clone())
+    } else {
+        None
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{Lambda, Operation, Response};
+    use serde_json::json;
+
+    fn names(nodes: &[Node]) -> Vec<&str> {
+        nodes.iter().map(|n| &*n.name).collect()
+    }
+
+    fn base() -> Vec<Node> {
+        vec![
+            Node::new("url", Expression::string("http://localhost/")),
+            Node::new("status", Expression::get("response", "status")),
+            Node::new("is-ok", Expression::equals("status", "200")),
+            Node::new("check", Expression::assert("is-ok", "Expected a 200")),
+        ]
+    }
+
+    #[test]
+    fn classify_changes() {
+        let before = base();
+        let after = vec![
+            Node::new("base-url", Expression::string("http://localhost/")),
+            Node::new("status", Expression::get("response", "code")),
+            Node::new("is-ok", Expression::equals("status", "204")),
+            Node::new("body", Expression::get("response", "body")),
+        ];
+
+        let got = diff(&before, &after);
+
+        let summary: Vec<_> = got.iter().map(ToString::to_string).collect();
+        assert_eq!(
+            summary,
+            [
+                "Renamed \"url\" to \"base-url\"",
+                "Changed the expression for \"status\"",
+                "Changed a literal in \"is-ok\"",
+                "Added \"body\"",
+                "Removed \"check\"",
+            ]
+        );
+    }
+
+    #[test]
+    fn identical_workspaces_have_no_changes() {
+        assert!(diff(&base(), &base()).is_empty());
+    }
+
+    #[test]
+    fn merge_changes_which_dont_overlap() {
+        let base = base();
+        let mut ours = base.clone();
+        ours[0] = Node::new("base-url", Expression::string("http://localhost/"));
+        ours.push(Node::new("body", Expression::get("response", "body")));
+        let mut theirs = base.clone();
+        theirs[0] = Node::new("url", Expression::string("https://example.com/"));
+        theirs[2] = Node::new("is-ok", Expression::equals("status", "204"));
+        theirs.insert(
+            2,
+            Node::new("headers", Expression::get("response", "headers")),
+        );
+        theirs.remove(4);
+
+        let got = merge(&base, &ours, &theirs);
+
+        assert!(got.is_clean(), "{:?}", got.conflicts);
+        assert_eq!(
+            got.nodes,
+            vec![
+                Node::new("base-url", Expression::string("https://example.com/")),
+                Node::new("status", Expression::get("response", "status")),
+                Node::new("headers", Expression::get("response", "headers")),
+                Node::new("is-ok", Expression::equals("status", "204")),
+                Node::new("body", Expression::get("response", "body")),
+            ]
+        );
+    }
+
+    #[test]
+    fn conflicting_changes() {
+        let base = base();
+        let mut ours = base.clone();
+        ours[2] = Node::new("is-ok", Expression::equals("status", "201"));
+        ours.remove(3);
+        ours.push(Node::new("body", Expression::get("response", "body")));
+        let mut theirs = base.clone();
+        theirs[2] = Node::new("is-ok", Expression::equals("status", "204"));
+        theirs[3] = Node::new("check", Expression::assert("is-ok", "Expected a 204"));
+        theirs.push(Node::new("body", Expression::get("response", "text")));
+
+        let got = merge(&base, &ours, &theirs);
+
+        let summary: Vec<_> = got.conflicts.iter().map(ToString::to_string).collect();
+        assert_eq!(
+            summary,
+            [
+                "\"is-ok\" was changed on both sides",
+                "\"check\" was removed on one side and changed on the other",
+                "\"body\" was added on both sides with different expressions",
+            ]
+        );
+        // Our side wins when there is a conflict
+        assert_eq!(names(&got.nodes), ["url", "status", "is-ok", "body"]);
+        assert_eq!(got.nodes[2], ours[2]);
+    }
+
+    #[test]
+    fn removed_on_one_side() {
+        let base = base();
+        let ours = base.clone();
+        let theirs = base[..3].to_vec();
+
+        let got = merge(&base, &ours, &theirs);
+
+        assert!(got.is_clean());
+        assert_eq!(names(&got.nodes), ["url", "status", "is-ok"]);
+    }
+
+    fn request(status: i32) -> Expression {
+        Expression::Request {
+            url: "http://localhost/".into(),
+            auth: None,
+            refresh: None,
+            response: Some(Response {
+                url: "http://localhost/".into(),
+                status,
+                status_text: "OK".into(),
+                body: Value::Indeterminate,
+                metrics: None,
+            }),
+            error: None,
+        }
+    }
+
+    #[test]
+    fn every_kind_of_constant_is_a_literal() {
+        let before = vec![
+            Node::new("pattern", Expression::matches("body", "^ok")),
+            Node::new("group", Expression::capture("body", "id=(\\d+)", "1")),
+            Node::new("host", Expression::variable("HOST")),
+            Node::new("token", Expression::secret("TOKEN")),
+            Node::new(
+                "schema",
+                Expression::validate_schema(
+                    "body",
+                    SchemaSource::inline(json!({ "type": "object" })),
+                ),
+            ),
+            Node::new(
+                "admins",
+                Expression::collection(
+                    Operation::Filter,
+                    "users",
+                    Lambda::new(
+                        "user",
+                        vec![Node::new("name", Expression::equals("user", "admin"))],
+                    ),
+                ),
+            ),
+        ];
+        let after = vec![
+            Node::new("pattern", Expression::matches("body", "^OK")),
+            Node::new("group", Expression::capture("body", "id=(\\w+)", "1")),
+            Node::new("host", Expression::variable("BASE_URL")),
+            Node::new("token", Expression::secret("API_TOKEN")),
+            Node::new(
+                "schema",
+                Expression::validate_schema(
+                    "body",
+                    SchemaSource::inline(json!({ "type": "array" })),
+                ),
+            ),
+            Node::new(
+                "admins",
+                Expression::collection(
+                    Operation::Filter,
+                    "users",
+                    Lambda::new(
+                        "user",
+                        vec![Node::new("name", Expression::equals("user", "owner"))],
+                    ),
+                ),
+            ),
+        ];
+
+        let got = diff(&before, &after);
+
+        let summary: Vec<_> = got.iter().map(ToString::to_string).collect();
+        assert_eq!(
+            summary,
+            [
+                "Changed a literal in \"pattern\"",
+                "Changed a literal in \"group\"",
+                "Changed a literal in \"host\"",
+                "Changed a literal in \"token\"",
+                "Changed a literal in \"schema\"",
+                "Changed a literal in \"admins\"",
+            ]
+        );
+    }
+
+    #[test]
+    fn responses_are_ignored_when_merging() {
+        let base = vec![Node::new("response", request(200))];
+        let ours = vec![Node::new("response", request(500))];
+        let theirs = vec![
+            Node::new("response", request(404)),
+            Node::new("status", Expression::get("response", "status")),
+        ];
+
+        let got = merge(&base, &ours, &theirs);
+
+        assert!(got.is_clean(), "{:?}", got.conflicts);
+        assert_eq!(
+            got.nodes,
+            vec![
+                Node::new("response", request(500)),
+                Node::new("status", Expression::get("response", "status")),
+            ]
+        );
+    }
+
+    #[test]
+    fn removing_a_node_whose_response_changed() {
+        let base = vec![Node::new("response", request(200))];
+        let ours = vec![Node::new("response", request(500))];
+
+        let got = merge(&base, &ours, &[]);
+
+        assert!(got.is_clean(), "{:?}", got.conflicts);
+        assert!(got.nodes.is_empty());
+    }
+}
@@ -0,0 +1,416 @@
+use crate::{
+    Environment, Expression, Function, Inputs, Node, NodeId, SecretSource, Sequence, Text,
+};
+use std::{
+    error::Error,
+    fmt::{self, Display, Formatter},
+    sync::Arc,
+};
+
+/// Targeted updates to the nodes, environment, secret, and function inputs.
+///
+/// Each node is stored as its own [`Inputs::node()`], so editing a node only
+/// invalidates the results which depend on it.
+pub trait InputsExt: Inputs {
+    /// Replace every node.
+    ///
+    /// Nodes are matched with the existing ones by name and only the nodes
+    /// which were added or changed are updated, so salsa can reuse any
+    /// cached results which don't depend on them.
+    fn set_nodes(&mut self, nodes: Sequence<Node>) {
+        let old_ids = self.node_ids();
+        let mut unmatched: Vec<(NodeId, Node)> =
+            old_ids.iter().map(|&id| (id, self.node(id))).collect();
+        let mut ids = Vec::new();
+
+        for node in nodes.iter() {
+            let id = match unmatched.iter().position(|(_, n)| n.name == node.name) {
+                Some(index) => {
+                    let (id, existing) = unmatched.remove(index);
+                    if existing != *node {
+                        self.set_node(id, node.clone());
+                    }
+                    id
+                }
+                None => {
+                    let id = NodeId::unused(&[&old_ids[..], &ids[..]].concat());
+                    self.set_node(id, node.clone());
+                    id
+                }
+            };
+            ids.push(id);
+        }
+
+        if old_ids != ids {
+            self.set_node_ids(ids.into());
+        }
+    }
+
+    /// Add a new node to the end of the list.
+    fn add_node(&mut self, node: Node) -> Result<(), EditError> {
+        let ids = self.node_ids();
+
+        if ids.iter().any(|&id| self.node(id).name == node.name) {
+            return Err(EditError::DuplicateName(node.name));
+        }
+
+        let id = NodeId::unused(&ids);
+        self.set_node(id, node);
+        self.set_node_ids(ids.iter().copied().chain([id]).collect());
+        Ok(())
+    }
+
+    /// Replace the expression for an existing node.
+    fn update_expression(&mut self, name: &str, expr: Arc<Expression>) -> Result<(), EditError> {
+        let id = id_of(self, name)?;
+
+        let mut node = self.node(id);
+        node.expr = expr;
+        self.set_node(id, node);
+        Ok(())
+    }
+
+    fn rename_node(&mut self, name: &str, new_name: Text) -> Result<(), EditError> {
+        let id = id_of(self, name)?;
+
+        if *name == *new_name {
+            return Ok(());
+        }
+        if id_of(self, &new_name).is_ok() {
+            return Err(EditError::DuplicateName(new_name));
+        }
+
+        let mut node = self.node(id);
+        node.name = new_name;
+        self.set_node(id, node);
+        Ok(())
+    }
+
+    /// Remove a node, returning it.
+    fn remove_node(&mut self, name: &str) -> Result<Node, EditError> {
+        let id = id_of(self, name)?;
+
+        let ids = self.node_ids();
+        self.set_node_ids(ids.iter().copied().filter(|&i| i != id).collect());
+        Ok(self.node(id))
+    }
+
+    /// Move a node so it is at the specified position in the list.
+    fn move_node(&mut self, name: &str, index: usize) -> Result<(), EditError> {
+        let id = id_of(self, name)?;
+        let mut ids = self.node_ids().to_vec();
+
+        if index >= ids.len() {
+            return Err(EditError::OutOfBounds {
+                index,
+                len: ids.len(),
+            });
+        }
+
+        ids.retain(|&i| i != id);
+        ids.insert(index, id);
+        self.set_node_ids(ids.into());
+        Ok(())
+    }
+
+    /// Create or replace an environment.
+    fn set_environment(&mut self, name: Text, environment: Environment) {
+        let mut environments = self.environments();
+        environments.insert(name, environment);
+        self.set_environments(environments);
+    }
+
+    /// Remove an environment, deactivating it if it was active.
+    fn remove_environment(&mut self, name: &str) -> Result<Environment, EditError> {
+        let mut environments = self.environments();
+        let removed = environments
+            .remove(name)
+            .ok_or_else(|| EditError::UnknownEnvironment(name.into()))?;
+
+        if self.active_environment().as_deref() == Some(name) {
+            self.set_active_environment(None);
+        }
+        self.set_environments(environments);
+        Ok(removed)
+    }
+
+    /// Switch to another environment, or pass `None` to deactivate the
+    /// current one.
+    fn select_environment(&mut self, name: Option<&str>) -> Result<(), EditError> {
+        if let Some(name) = name {
+            if !self.environments().contains_key(name) {
+                return Err(EditError::UnknownEnvironment(name.into()));
+            }
+        }
+
+        self.set_active_environment(name.map(Text::from));
+        Ok(())
+    }
+
+    /// Create or replace a user-defined function.
+    ///
+    /// Functions can't have the same name as a built-in function, because
+    /// calls would always go to the built-in one.
+    fn set_function(&mut self, name: Text, function: Function) -> Result<(), EditError> {
+        if crate::functions::is_builtin(&name) {
+            return Err(EditError::BuiltinFunction(name));
+        }
+
+        let mut functions = self.functions();
+        functions.insert(name, function);
+        self.set_functions(functions);
+        Ok(())
+    }
+
+    fn remove_function(&mut self, name: &str) -> Result<Function, EditError> {
+        let mut functions = self.functions();
+        let removed = functions
+            .remove(name)
+            .ok_or_else(|| EditError::UnknownFunction(name.into()))?;
+        self.set_functions(functions);
+        Ok(removed)
+    }
+
+    /// Load every secret referenced by an [`Expression::Secret`] node from
+    /// `source`.
+    ///
+    /// Secrets the source doesn't know about are left out, so the nodes using
+    /// them will fail to evaluate.
+    fn load_secrets(&mut self, source: &dyn SecretSource) {
+        let secrets = self
+            .nodes()
+            .iter()
+            .filter_map(|node| match &*node.expr {
+                Expression::Secret { name } => Some(name.clone()),
+                _ => None,
+            })
+            .filter_map(|name| source.secret(&name).map(|secret| (name, secret)))
+            .collect();
+
+        self.set_secrets(secrets);
+    }
+}
+
+impl<DB: Inputs + ?Sized> InputsExt for DB {}
+
+/// Find the first node called `name`.
+fn id_of<DB: Inputs + ?Sized>(db: &DB, name: &str) -> Result<NodeId, EditError> {
+    db.node_ids()
+        .iter()
+        .copied()
+        .find(|&id| &*db.node(id).name == name)
+        .ok_or_else(|| EditError::UnknownNode(name.into()))
+}
+
+/// The reasons an edit can fail.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum EditError {
+    UnknownNode(Text),
+    DuplicateName(Text),
+    OutOfBounds { index: usize, len: usize },
+    UnknownEnvironment(Text),
+    UnknownFunction(Text),
+    BuiltinFunction(Text),
+}
+
+impl Display for EditError {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        match self {
+            EditError::UnknownNode(name) => write!(f, "No \"{}\" node found", name),
+            EditError::DuplicateName(name) => {
+                write!(f, "There is already a node called \"{}\"", name)
+            }
+            EditError::OutOfBounds { index, len } => write!(
+                f,
+                "Index {} is out of bounds for a list of {} nodes",
+                index, len
+            ),
+            EditError::UnknownEnvironment(name) => {
+                write!(f, "No \"{}\" environment found", name)
+            }
+            EditError::UnknownFunction(name) => write!(f, "No \"{}\" function found", name),
+            EditError::BuiltinFunction(name) => {
+                write!(
+                    f,
+                    "There is already a built-in function called \"{}\"",
+                    name
+                )
+            }
+        }
+    }
+}
+
+impl Error for EditError {}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{test_utils::Database, Evaluate, Keyring, Secret, Sequence, Value};
+    use std::collections::BTreeMap;
+
+    fn database() -> Database {
+        Database::with_nodes(vec![
+            Node::new("first", Expression::string("1")),
+            Node::new("second", Expression::string("2")),
+            Node::new("third", Expression::equals("first", "1")),
+        ])
+    }
+
+    fn names(db: &Database) -> Vec<String> {
+        db.nodes().iter().map(|n| n.name.to_string()).collect()
+    }
+
+    #[test]
+    fn add_a_node() {
+        let mut db = database();
+
+        db.add_node(Node::new("fourth", Expression::string("4")))
+            .unwrap();
+
+        assert_eq!(names(&db), ["first", "second", "third", "fourth"]);
+    }
+
+    #[test]
+    fn names_must_be_unique() {
+        let mut db = database();
+
+        let err = db
+            .add_node(Node::new("first", Expression::string("4")))
+            .unwrap_err();
+        assert_eq!(err, EditError::DuplicateName("first".into()));
+
+        let err = db.rename_node("second", "first".into()).unwrap_err();
+        assert_eq!(err, EditError::DuplicateName("first".into()));
+    }
+
+    #[test]
+    fn updating_an_expression_updates_dependents() {
+        let mut db = database();
+
+        db.update_expression("first", Expression::string("one").into())
+            .unwrap();
+
+        assert_eq!(
+            db.evaluate(),
+            Sequence::from(vec![
+                Ok(Value::from("one")),
+                Ok(Value::from("2")),
+                Ok(Value::from(false)),
+            ])
+        );
+    }
+
+    /// The names of the nodes which were re-evaluated.
+    fn reevaluated(db: &Database) -> Vec<String> {
+        db.executed("eval")
+            .iter()
+            .filter_map(|key| key.split('"').nth(1).map(String::from))
+            .collect()
+    }
+
+    #[test]
+    fn edits_only_reevaluate_affected_nodes() {
+        let mut db = database();
+        db.evaluate();
+        reevaluated(&db);
+
+        db.update_expression("second", Expression::string("two").into())
+            .unwrap();
+        db.evaluate();
+        assert_eq!(reevaluated(&db), ["second"]);
+
+        db.add_node(Node::new("fourth", Expression::string("4")))
+            .unwrap();
+        db.evaluate();
+        assert_eq!(reevaluated(&db), ["fourth"]);
+
+        let mut nodes = db.nodes().to_vec();
+        nodes[0].expr = Expression::string("one").into();
+        db.set_nodes(nodes.into());
+        db.evaluate();
+        assert_eq!(reevaluated(&db), ["first", "third"]);
+    }
+
+    #[test]
+    fn renaming_a_node_to_its_own_name_does_nothing() {
+        let mut db = database();
+
+        db.rename_node("first", "first".into()).unwrap();
+
+        assert_eq!(names(&db), ["first", "second", "third"]);
+        let err = db.rename_node("missing", "missing".into()).unwrap_err();
+        assert_eq!(err, EditError::UnknownNode("missing".into()));
+    }
+
+    #[test]
+    fn rename_remove_and_move() {
+        let mut db = database();
+
+        db.rename_node("second", "2nd".into()).unwrap();
+        assert_eq!(names(&db), ["first", "2nd", "third"]);
+
+        db.move_node("third", 0).unwrap();
+        assert_eq!(names(&db), ["third", "first", "2nd"]);
+
+        let removed = db.remove_node("first").unwrap();
+        assert_eq!(removed.name, Text::from("first"));
+        assert_eq!(names(&db), ["third", "2nd"]);
+    }
+
+    #[test]
+    fn unknown_nodes_and_indices() {
+        let mut db = database();
+
+        let err = db.remove_node("missing").unwrap_err();
+        assert_eq!(err, EditError::UnknownNode("missing".into()));
+
+        let err = db.move_node("first", 3).unwrap_err();
+        assert_eq!(err, EditError::OutOfBounds { index: 3, len: 3 });
+    }
+
+    #[test]
+    fn switch_environments() {
+        let mut db = database();
+        let dev: BTreeMap<Text, Text> = [("base_url".into(), "http://localhost/".into())]
+            .into_iter()
+            .collect();
+        db.set_environment("dev".into(), dev.into());
+
+        db.select_environment(Some("dev")).unwrap();
+        assert_eq!(db.active_environment(), Some(Text::from("dev")));
+
+        let err = db.select_environment(Some("prod")).unwrap_err();
+        assert_eq!(err, EditError::UnknownEnvironment("prod".into()));
+
+        db.remove_environment("dev").unwrap();
+        assert_eq!(db.active_environment(), None);
+        assert!(db.environments().is_empty());
+    }
+
+    #[test]
+    fn load_secrets_used_by_nodes() {
+        let mut db = database();
+        db.add_node(Node::new("token", Expression::secret("api_token")))
+            .unwrap();
+        db.add_node(Node::new("password", Expression::secret("password")))
+            .unwrap();
+        let mut keyring = Keyring::new();
+        keyring.insert("api_token", Secret::new("abc123"));
+        keyring.insert("unused", Secret::new("hunter2"));
+
+        db.load_secrets(&keyring);
+
+        let secrets = db.secrets();
+        assert_eq!(
+            secrets.keys().collect::<Vec<_>>(),
+            [&Text::from("api_token")]
+        );
+        assert_eq!(
+            &db.evaluate()[3..],
+            &[
+                Ok(Value::Secret(Secret::new("abc123"))),
+                Err("No \"password\" secret found".into()),
+            ]
+        );
+    }
+}
@@ -1,6 +1,10 @@
-use crate::{EvaluationError, Expression, Inputs, Node, Sequence, Text, Value};
+use crate::{
+    collections, patterns, schemas, Argument, Auth, Diagnostic, EvaluationError, Explanation,
+    Expression, Function, Inputs, Node, NodeId, Pattern, PreparedRequest, Response, Schema,
+    SchemaSource, Secret, Sequence, Text, Type, Value,
+};
 use std::{
-    collections::{btree_map::Entry, BTreeMap},
+    collections::{BTreeMap, BTreeSet},
     sync::Arc,
 };
 
@@ -8,14 +12,55 @@ use std::{
 pub trait Evaluate: Inputs {
     fn evaluate(&self) -> Sequence<Result<Value, EvaluationError>>;
     fn eval(&self, name: Text, expr: Arc<Expression>) -> Result<Value, EvaluationError>;
-    fn named_expressions(&self) -> BTreeMap<Text, NamedExpression>;
+    /// The node each name refers to. If several nodes have the same name,
+    /// the first one wins.
+    fn node_index(&self) -> BTreeMap<Text, NodeId>;
+    /// The expression for the node called `name`.
+    fn expression_of(&self, name: Text) -> Option<Arc<Expression>>;
     fn reference_cycle(&self, name: Text) -> Option<Sequence<Text>>;
-}
-
-#[derive(Debug, Clone, PartialEq, Eq, Hash)]
-pub struct NamedExpression {
-    pub index: usize,
-    pub expression: Arc<Expression>,
+    /// Look up a variable in the active environment.
+    fn variable(&self, name: Text) -> Result<Text, EvaluationError>;
+    /// Problems found while evaluating the current set of nodes.
+    fn diagnostics(&self) -> Sequence<Diagnostic>;
+    /// Explain how a node's value was derived, returning `None` if there is
+    /// no node with this name.
+    #[salsa::invoke(crate::explain::explain)]
+    fn explain(&self, name: Text) -> Option<Explanation>;
+    /// Work out the HTTP request a [`Expression::Request`] or
+    /// [`Expression::OAuth2Token`] node should send, including any
+    /// credentials.
+    #[salsa::invoke(crate::auth::prepare_request)]
+    fn prepare_request(&self, name: Text) -> Result<PreparedRequest, EvaluationError>;
+    /// Look up a user-defined function.
+    #[salsa::invoke(crate::user_functions::function)]
+    fn function(&self, name: Text) -> Option<Function>;
+    /// Call a user-defined function.
+    ///
+    /// Calls are memoized by their arguments, so a function which is called
+    /// with the same values from several places is only evaluated once.
+    #[salsa::invoke(crate::user_functions::call_function)]
+    fn call_function(
+        &self,
+        function: Text,
+        arguments: Sequence<Value>,
+    ) -> Result<Value, EvaluationError>;
+    /// Find a chain of calls leading from a user-defined function back to
+    /// itself.
+    #[salsa::invoke(crate::user_functions::function_cycle)]
+    fn function_cycle(&self, function: Text) -> Option<Sequence<Text>>;
+    /// Infer the type of a node's value from its expression and the types of
+    /// the nodes it depends on.
+    #[salsa::invoke(crate::typecheck::type_of)]
+    fn type_of(&self, name: Text) -> Result<Type, EvaluationError>;
+    /// The type returned by a user-defined function.
+    #[salsa::invoke(crate::typecheck::return_type)]
+    fn return_type(&self, function: Text) -> Result<Type, EvaluationError>;
+    /// Compile a JSON Schema.
+    #[salsa::invoke(crate::schemas::schema)]
+    fn schema(&self, source: SchemaSource) -> Result<Schema, EvaluationError>;
+    /// Compile a regular expression.
+    #[salsa::invoke(crate::patterns::pattern)]
+    fn pattern(&self, pattern: Text) -> Result<Pattern, EvaluationError>;
 }
 
 fn evaluate(db: &dyn Evaluate) -> Sequence<Result<Value, EvaluationError>> {
@@ -31,13 +76,97 @@ fn evaluate(db: &dyn Evaluate) -> Sequence<Result<Value, EvaluationError>> {
 }
 
 fn eval(db: &dyn Evaluate, name: Text, expr: Arc<Expression>) -> Result<Value, EvaluationError> {
-    if let Some(cycle) = db.reference_cycle(name) {
+    if let Some(cycle) = db.reference_cycle(name.clone()) {
         let msg = format!("Cycle detected: {}", cycle.join(" → "));
         return Err(EvaluationError::from(msg));
     }
 
-    match Expression::clone(&expr) {
-        Expression::StringConstant(s) => Ok(Value::String(s)),
+    evaluate_expression(db, &Nodes(db), name, &expr)
+}
+
+/// Somewhere the names referred to by an expression can be looked up.
+pub(crate) trait Scope {
+    /// Get the value called `name`, marking any error as having come from
+    /// upstream.
+    fn lookup(&self, name: &Text) -> Result<Value, EvaluationError>;
+}
+
+/// The nodes in the workspace.
+struct Nodes<'db>(&'db dyn Evaluate);
+
+impl Scope for Nodes<'_> {
+    fn lookup(&self, name: &Text) -> Result<Value, EvaluationError> {
+        let expression = self
+            .0
+            .expression_of(name.clone())
+            .ok_or_else(|| format!("No \"{}\" input found", name))?;
+
+        self.0
+            .eval(name.clone(), expression)
+            .map_err(|e| EvaluationError::upstream(name.clone(), e))
+    }
+}
+
+/// Names bound while evaluating the body of a lambda or function, falling back
+/// to another scope for anything else.
+pub(crate) struct Locals<'a> {
+    parent: &'a dyn Scope,
+    values: BTreeMap<Text, Result<Value, EvaluationError>>,
+}
+
+impl<'a> Locals<'a> {
+    pub(crate) fn new(parent: &'a dyn Scope) -> Self {
+        Locals {
+            parent,
+            values: BTreeMap::new(),
+        }
+    }
+
+    pub(crate) fn bind(&mut self, name: Text, value: Result<Value, EvaluationError>) {
+        self.values.insert(name, value);
+    }
+
+    /// Evaluate each node in `body`, binding its result so later nodes can
+    /// use it, and return the last node's value (or `None` if the body is
+    /// empty).
+    pub(crate) fn evaluate_body(
+        &mut self,
+        db: &dyn Evaluate,
+        body: &Sequence<Node>,
+    ) -> Option<Result<Value, EvaluationError>> {
+        let mut result = None;
+
+        for node in body.iter() {
+            let value = evaluate_expression(db, self, node.name.clone(), &node.expr);
+            self.bind(node.name.clone(), value.clone());
+            result = Some(value);
+        }
+
+        result
+    }
+}
+
+impl Scope for Locals<'_> {
+    fn lookup(&self, name: &Text) -> Result<Value, EvaluationError> {
+        // Errors from locals are passed through as-is because they aren't
+        // nodes the user could go and look at
+        match self.values.get(name) {
+            Some(value) => value.clone(),
+            None => self.parent.lookup(name),
+        }
+    }
+}
+
+/// Evaluate the expression for the node called `name`, looking up any names
+/// it refers to in `scope`.
+pub(crate) fn evaluate_expression(
+    db: &dyn Evaluate,
+    scope: &dyn Scope,
+    name: Text,
+    expr: &Expression,
+) -> Result<Value, EvaluationError> {
+    match expr.clone() {
+        Expression::StringConstant { value } => Ok(Value::String(value)),
         Expression::Request { error: Some(e), .. } => Err(e),
         Expression::Request {
             response: Some(response),
@@ -49,108 +178,327 @@ fn eval(db: &dyn Evaluate, name: Text, expr: Arc<Expression>) -> Result<Value, E
             error: None,
             ..
         } => Ok(Value::Indeterminate),
-        Expression::Equals { target, value } => equals(db, target, value),
-        Expression::GetProperty { target, field } => get_property(db, target, field),
+        Expression::Equals { target, value } => equals(scope, target, value),
+        Expression::GetProperty { target, field } => get_property(scope, target, field),
+        Expression::Assert { target, message } => assert(scope, target, message),
+        Expression::Variable { name } => db.variable(name).map(Value::String),
+        Expression::Secret { name } => match db.secrets().get(&name) {
+            Some(secret) => Ok(Value::Secret(secret.clone())),
+            None => Err(format!("No \"{}\" secret found", name).into()),
+        },
+        Expression::OAuth2Token { error: Some(e), .. } => Err(e),
+        Expression::OAuth2Token {
+            response: Some(response),
+            error: None,
+            ..
+        } => oauth2_token(name, response),
+        Expression::OAuth2Token {
+            response: None,
+            error: None,
+            ..
+        } => Ok(Value::Indeterminate),
+        Expression::Matches { target, pattern } => {
+            let pattern = db.pattern(pattern)?;
+            with_string(scope, target, |s| {
+                Ok(Value::from(patterns::matches(&pattern, s)))
+            })
+        }
+        Expression::Capture {
+            target,
+            pattern,
+            group,
+        } => {
+            let pattern = db.pattern(pattern)?;
+            with_string(scope, target, |s| {
+                patterns::capture(&pattern, s, group.as_ref())
+            })
+        }
+        Expression::Call {
+            function,
+            arguments,
+        } => call(db, scope, function, arguments),
+        Expression::Collection {
+            operation,
+            target,
+            lambda,
+        } => collections::apply(db, scope, operation, target, lambda.as_ref()),
+        Expression::ValidateSchema { target, schema } => {
+            let schema = db.schema(schema)?;
+            match scope.lookup(&target)? {
+                Value::Indeterminate => Ok(Value::Indeterminate),
+                value => Ok(schemas::validate(&schema, &value)),
+            }
+        }
+        Expression::Previous { target } => match db.previous_results().get(&target) {
+            Some(Ok(value)) => Ok(value.clone()),
+            Some(Err(e)) => Err(EvaluationError::upstream(target, e.clone())),
+            None => Ok(Value::Indeterminate),
+        },
+        Expression::Changed { target } => {
+            // Failing (or recovering) counts as a change, so compare the
+            // results rather than bailing out when the target fails
+            let current = scope.lookup(&target).map(|value| fingerprinted(&value));
+
+            match (current, db.previous_results().get(&target)) {
+                (Ok(Value::Indeterminate), _) | (_, None) => Ok(Value::Indeterminate),
+                (current, Some(previous)) => {
+                    let previous = previous
+                        .clone()
+                        .map_err(|e| EvaluationError::upstream(target.clone(), e));
+                    Ok(Value::from(previous != current))
+                }
+            }
+        }
+    }
+}
+
+/// Replace any secrets in a value with their fingerprints, so it can be
+/// compared with the [`Inputs::previous_results()`].
+pub(crate) fn fingerprinted(value: &Value) -> Value {
+    value.map_secrets(&Secret::fingerprint)
+}
+
+/// Evaluate a node which operates on the string in `target`.
+fn with_string(
+    scope: &dyn Scope,
+    target: Text,
+    func: impl FnOnce(&str) -> Result<Value, EvaluationError>,
+) -> Result<Value, EvaluationError> {
+    match scope.lookup(&target)? {
+        Value::String(s) => func(&s),
+        Value::Indeterminate => Ok(Value::Indeterminate),
+        _ => Err(format!("\"{}\" is not a string", target).into()),
     }
 }
 
-fn equals(db: &dyn Evaluate, target: Text, value: Value) -> Result<Value, EvaluationError> {
-    let expressions = db.named_expressions();
+fn call(
+    db: &dyn Evaluate,
+    scope: &dyn Scope,
+    function: Text,
+    arguments: Sequence<Argument>,
+) -> Result<Value, EvaluationError> {
+    let mut values = Vec::new();
+
+    for argument in arguments.iter() {
+        let value = match argument {
+            Argument::Constant { value } => value.clone(),
+            Argument::Node { name } => scope.lookup(name)?,
+        };
+        values.push(value);
+    }
+
+    if values.contains(&Value::Indeterminate) {
+        return Ok(Value::Indeterminate);
+    }
 
-    let NamedExpression { expression, .. } = expressions
-        .get(&target)
-        .ok_or_else(|| format!("No \"{}\" input found", target))?;
+    if crate::functions::is_builtin(&function) {
+        crate::functions::call_builtin(db, &function, &values)
+    } else {
+        db.call_function(function, values.into())
+    }
+}
 
-    match db.eval(target, Arc::clone(expression)) {
-        Ok(target_value) => Ok(Value::from(target_value == value)),
-        Err(_) => Ok(Value::Indeterminate),
+fn oauth2_token(name: Text, response: Response) -> Result<Value, EvaluationError> {
+    if !(200..300).contains(&response.status) {
+        let msg = format!(
+            "The token request failed with {} {}",
+            response.status, response.status_text
+        );
+        return Err(msg.into());
     }
+
+    crate::auth::access_token(&name, &response.body)
 }
 
-fn get_property(db: &dyn Evaluate, target: Text, field: Text) -> Result<Value, EvaluationError> {
-    let expressions = db.named_expressions();
-    let NamedExpression { expression, .. } = expressions
-        .get(&target)
-        .ok_or_else(|| format!("No \"{}\" input found", target))?;
+fn equals(scope: &dyn Scope, target: Text, value: Value) -> Result<Value, EvaluationError> {
+    match scope.lookup(&target)? {
+        Value::Indeterminate => Ok(Value::Indeterminate),
+        target_value => Ok(Value::from(target_value == value)),
+    }
+}
 
-    match db.eval(target, Arc::clone(expression)) {
-        Ok(Value::Object(obj)) => match obj.get(&*field) {
+fn get_property(scope: &dyn Scope, target: Text, field: Text) -> Result<Value, EvaluationError> {
+    match scope.lookup(&target)? {
+        Value::Object(obj) => match obj.get(&*field) {
             Some(field_value) => Ok(field_value.clone()),
-            None => todo!(),
+            None => Err(format!("\"{}\" has no \"{}\" field", target, field).into()),
         },
-        Ok(_) => todo!(),
-        Err(_) => Ok(Value::Indeterminate),
+        Value::Indeterminate => Ok(Value::Indeterminate),
+        _ => Err(format!("\"{}\" is not an object", target).into()),
     }
 }
 
-fn named_expressions(db: &dyn Evaluate) -> BTreeMap<Text, NamedExpression> {
-    let mut expressions = BTreeMap::new();
+fn assert(scope: &dyn Scope, target: Text, message: Text) -> Result<Value, EvaluationError> {
+    match scope.lookup(&target)? {
+        Value::Boolean(true) => Ok(Value::Boolean(true)),
+        Value::Boolean(false) => Err(EvaluationError::Failed { message }),
+        Value::Indeterminate => Ok(Value::Indeterminate),
+        _ => Err(format!("\"{}\" is not a boolean", target).into()),
+    }
+}
 
-    for (
-        index,
-        Node {
-            name,
-            expr: expression,
-        },
-    ) in db.nodes().iter().cloned().enumerate()
-    {
-        let named = NamedExpression { index, expression };
+fn variable(db: &dyn Evaluate, name: Text) -> Result<Text, EvaluationError> {
+    let active = db.active_environment().ok_or_else(|| {
+        format!(
+            "Unable to look up \"{}\" because no environment is active",
+            name
+        )
+    })?;
+    let environments = db.environments();
+    let environment = environments
+        .get(&active)
+        .ok_or_else(|| format!("No \"{}\" environment found", active))?;
 
-        if let Entry::Vacant(entry) = expressions.entry(name) {
-            entry.insert(named);
-        }
+    environment.get(&name).cloned().ok_or_else(|| {
+        format!(
+            "\"{}\" isn't defined in the \"{}\" environment",
+            name, active
+        )
+        .into()
+    })
+}
+
+fn node_index(db: &dyn Evaluate) -> BTreeMap<Text, NodeId> {
+    let mut index = BTreeMap::new();
+
+    for &id in db.node_ids().iter() {
+        index.entry(db.node(id).name).or_insert(id);
     }
 
-    expressions
+    index
+}
+
+fn expression_of(db: &dyn Evaluate, name: Text) -> Option<Arc<Expression>> {
+    let id = *db.node_index().get(&name)?;
+    Some(db.node(id).expr)
 }
 
 fn reference_cycle(db: &dyn Evaluate, name: Text) -> Option<Sequence<Text>> {
-    let expressions = db.named_expressions();
+    let mut path = vec![name.clone()];
+    let mut visited = BTreeSet::new();
+
+    if find_cycle(db, &name, &mut path, &mut visited) {
+        Some(path.into())
+    } else {
+        None
+    }
+}
+
+/// Do a depth-first search for a path from `item` back to the first node in
+/// `path`.
+fn find_cycle(
+    db: &dyn Evaluate,
+    item: &Text,
+    path: &mut Vec<Text>,
+    visited: &mut BTreeSet<Text>,
+) -> bool {
+    let expression = match db.expression_of(item.clone()) {
+        Some(e) => e,
+        None => return false,
+    };
+    let deps = dependencies(&expression);
 
-    let mut dependencies = vec![name.clone()];
-    let mut item = &name;
+    for dep in deps {
+        if *dep == path[0] {
+            path.push(dep.clone());
+            return true;
+        }
+
+        if !visited.insert(dep.clone()) {
+            // We depend on a cycle (or have already checked this node), but
+            // aren't part of it
+            continue;
+        }
+
+        path.push(dep.clone());
+        if find_cycle(db, dep, path, visited) {
+            return true;
+        }
+        path.pop();
+    }
+
+    false
+}
 
-    while let Some(dep) = expressions
-        .get(item)
-        .and_then(|e| dependency(&e.expression))
-    {
-        dependencies.push(dep.clone());
+fn diagnostics(db: &dyn Evaluate) -> Sequence<Diagnostic> {
+    let mut diags = Vec::new();
+    let mut seen = BTreeSet::new();
 
-        if *dep == name {
-            return Some(dependencies.into());
+    for (node, result) in db.nodes().iter().zip(db.evaluate().iter()) {
+        let first = seen.insert(&node.name);
+        if !first {
+            let msg = format!("There is already a node called \"{}\"", node.name);
+            diags.push(Diagnostic::new(node.name.clone(), msg));
         }
 
-        item = dep;
+        match result {
+            Err(e) => diags.push(Diagnostic::new(node.name.clone(), e.to_string())),
+            // Type errors let us report problems with nodes that are still
+            // waiting on a request. Errors from upstream nodes are skipped
+            // because they'll be reported against the node they came from.
+            Ok(_) if first => {
+                if let Err(EvaluationError::Failed { message }) = db.type_of(node.name.clone()) {
+                    diags.push(Diagnostic::new(node.name.clone(), message));
+                }
+            }
+            Ok(_) => {}
+        }
     }
 
-    None
+    diags.into()
 }
 
-fn dependency(expr: &Expression) -> Option<&Text> {
+/// The names of the nodes an expression refers to.
+pub(crate) fn dependencies(expr: &Expression) -> Vec<&Text> {
     match expr {
-        Expression::StringConstant(_) | Expression::Request { .. } => None,
-        Expression::Equals { target, .. } | Expression::GetProperty { target, .. } => Some(target),
+        Expression::StringConstant { .. }
+        | Expression::Variable { .. }
+        | Expression::Secret { .. }
+        | Expression::Previous { .. } => Vec::new(),
+        Expression::Request { auth, .. } => auth.iter().flat_map(Auth::credentials).collect(),
+        Expression::OAuth2Token {
+            client_id,
+            client_secret,
+            ..
+        } => vec![client_id, client_secret],
+        Expression::Equals { target, .. }
+        | Expression::GetProperty { target, .. }
+        | Expression::Assert { target, .. }
+        | Expression::Matches { target, .. }
+        | Expression::Capture { target, .. }
+        | Expression::ValidateSchema { target, .. }
+        | Expression::Changed { target } => vec![target],
+        Expression::Call { arguments, .. } => arguments
+            .iter()
+            .filter_map(|arg| match arg {
+                Argument::Node { name } => Some(name),
+                Argument::Constant { .. } => None,
+            })
+            .collect(),
+        Expression::Collection { target, lambda, .. } => {
+            let mut deps = vec![target];
+            if let Some(lambda) = lambda {
+                for dep in lambda.free_variables() {
+                    if !deps.contains(&dep) {
+                        deps.push(dep);
+                    }
+                }
+            }
+            deps
+        }
     }
 }
 
 #[cfg(test)]
 mod tests {
     use super::*;
-    use crate::{inputs::InputsStorage, Response, Text};
-
-    #[salsa::database(InputsStorage, EvaluateStorage)]
-    #[derive(Default)]
-    struct Database {
-        storage: salsa::Storage<Self>,
-    }
-
-    impl salsa::Database for Database {}
+    use crate::{test_utils::Database, InputsExt, Response, Text};
 
     #[test]
     fn constant_expression() {
         let mut db = Database::default();
         db.set_nodes(Sequence::empty());
-        let expr = Arc::new(Expression::StringConstant("asdf".into()));
+        let expr = Arc::new(Expression::string("asdf"));
 
         let got = db.eval("".into(), expr).unwrap();
 
@@ -163,6 +511,8 @@ mod tests {
         db.set_nodes(Sequence::empty());
         let expr = Arc::new(Expression::Request {
             url: "".into(),
+            auth: None,
+            refresh: None,
             response: None,
             error: None,
         });
@@ -181,9 +531,12 @@ mod tests {
             status: 200,
             status_text: Text::from("OK"),
             body: Value::Number(42),
+            metrics: None,
         };
         let expr = Arc::new(Expression::Request {
             url: "".into(),
+            auth: None,
+            refresh: None,
             response: Some(response.clone()),
             error: None,
         });
@@ -201,6 +554,8 @@ mod tests {
         let error = EvaluationError::from("an error occurred");
         let expr = Arc::new(Expression::Request {
             url: "".into(),
+            auth: None,
+            refresh: None,
             response: None,
             error: Some(error.clone()),
         });
@@ -210,6 +565,33 @@ mod tests {
         assert_eq!(got, error);
     }
 
+    #[test]
+    fn failing_is_a_change() {
+        let status = Expression::Request {
+            url: "http://example.com/".into(),
+            auth: None,
+            refresh: None,
+            response: None,
+            error: Some(EvaluationError::from("Timed out")),
+        };
+        let mut db = Database::with_nodes(vec![
+            Node::new("status", status),
+            Node::new("changed", Expression::changed("status")),
+        ]);
+        let set_previous = |db: &mut Database, result| {
+            db.set_previous_results([(Text::from("status"), result)].into_iter().collect())
+        };
+
+        set_previous(&mut db, Ok(Value::from(200)));
+        assert_eq!(db.evaluate()[1], Ok(Value::from(true)));
+
+        set_previous(&mut db, Err(EvaluationError::from("Timed out")));
+        assert_eq!(db.evaluate()[1], Ok(Value::from(false)));
+
+        set_previous(&mut db, Err(EvaluationError::from("Connection refused")));
+        assert_eq!(db.evaluate()[1], Ok(Value::from(true)));
+    }
+
     #[test]
     fn string_equals() {
         let mut db = Database::default();
@@ -220,7 +602,7 @@ mod tests {
         let nodes = vec![
             Node {
                 name: "input".into(),
-                expr: Arc::new(Expression::StringConstant(Text::from("Hello, World!"))),
+                expr: Arc::new(Expression::string("Hello, World!")),
             },
             Node {
                 name: "equals".into(),
@@ -260,11 +642,14 @@ mod tests {
                 name: "input".into(),
                 expr: Arc::new(Expression::Request {
                     url: "http://example.com/".into(),
+                    auth: None,
+                    refresh: None,
                     response: Some(Response {
                         url: "http://example.com/".into(),
                         status: 200,
                         status_text: Text::from("OK"),
                         body: Value::Number(42),
+                        metrics: None,
                     }),
                     error: None,
                 }),
@@ -281,6 +666,212 @@ mod tests {
         assert_eq!(got, Value::from(200));
     }
 
+    #[test]
+    fn upstream_errors_remember_where_they_came_from() {
+        let mut db = Database::default();
+        let nodes: Sequence<_> = vec![
+            Node {
+                name: "login".into(),
+                expr: Expression::Request {
+                    url: "http://example.com/".into(),
+                    auth: None,
+                    refresh: None,
+                    response: None,
+                    error: Some("401 Unauthorized".into()),
+                }
+                .into(),
+            },
+            Node {
+                name: "status".into(),
+                expr: Expression::get("login", "status").into(),
+            },
+            Node {
+                name: "is-ok".into(),
+                expr: Expression::equals("status", 200).into(),
+            },
+        ]
+        .into();
+        db.set_nodes(nodes);
+
+        let got = db.evaluate();
+
+        let blocked = EvaluationError::UpstreamFailed {
+            node: "login".into(),
+            message: "401 Unauthorized".into(),
+        };
+        assert_eq!(got[1], Err(blocked.clone()));
+        assert_eq!(got[2], Err(blocked.clone()));
+        assert_eq!(
+            blocked.to_string(),
+            "blocked by failing node `login`: 401 Unauthorized"
+        );
+    }
+
+    #[test]
+    fn pending_requests_are_still_indeterminate() {
+        let mut db = Database::default();
+        let nodes: Sequence<_> = vec![
+            Node {
+                name: "login".into(),
+                expr: Expression::Request {
+                    url: "http://example.com/".into(),
+                    auth: None,
+                    refresh: None,
+                    response: None,
+                    error: None,
+                }
+                .into(),
+            },
+            Node {
+                name: "status".into(),
+                expr: Expression::get("login", "status").into(),
+            },
+        ]
+        .into();
+        db.set_nodes(nodes);
+
+        let got = db.evaluate();
+
+        assert_eq!(got[1], Ok(Value::Indeterminate));
+    }
+
+    #[test]
+    fn assertions() {
+        let mut db = Database::default();
+        let nodes: Sequence<_> = vec![
+            Node {
+                name: "greeting".into(),
+                expr: Expression::string("Hello").into(),
+            },
+            Node {
+                name: "is-hello".into(),
+                expr: Expression::equals("greeting", "Hello").into(),
+            },
+            Node {
+                name: "is-goodbye".into(),
+                expr: Expression::equals("greeting", "Goodbye").into(),
+            },
+            Node {
+                name: "hello".into(),
+                expr: Expression::assert("is-hello", "Should be hello").into(),
+            },
+            Node {
+                name: "goodbye".into(),
+                expr: Expression::assert("is-goodbye", "Should be goodbye").into(),
+            },
+            Node {
+                name: "not-boolean".into(),
+                expr: Expression::assert("greeting", "Should be a boolean").into(),
+            },
+        ]
+        .into();
+        db.set_nodes(nodes);
+
+        let got = db.evaluate();
+
+        assert_eq!(
+            &got[3..],
+            &[
+                Ok(Value::from(true)),
+                Err(EvaluationError::from("Should be goodbye")),
+                Err(EvaluationError::from("\"greeting\" is not a boolean")),
+            ]
+        );
+    }
+
+    fn environment(variables: &[(&str, &str)]) -> crate::Environment {
+        variables
+            .iter()
+            .map(|&(k, v)| (Text::from(k), Text::from(v)))
+            .collect::<BTreeMap<_, _>>()
+            .into()
+    }
+
+    #[test]
+    fn variables_come_from_the_active_environment() {
+        let mut db = Database::default();
+        db.set_nodes(
+            vec![Node {
+                name: "url".into(),
+                expr: Expression::variable("base_url").into(),
+            }]
+            .into(),
+        );
+        let mut environments = BTreeMap::new();
+        environments.insert(
+            Text::from("dev"),
+            environment(&[("base_url", "http://localhost/")]),
+        );
+        environments.insert(Text::from("prod"), environment(&[]));
+        db.set_environments(environments);
+
+        db.set_active_environment(None);
+        assert_eq!(
+            db.evaluate()[0],
+            Err(EvaluationError::from(
+                "Unable to look up \"base_url\" because no environment is active"
+            ))
+        );
+
+        db.set_active_environment(Some("dev".into()));
+        assert_eq!(db.evaluate()[0], Ok(Value::from("http://localhost/")));
+
+        db.set_active_environment(Some("prod".into()));
+        assert_eq!(
+            db.evaluate()[0],
+            Err(EvaluationError::from(
+                "\"base_url\" isn't defined in the \"prod\" environment"
+            ))
+        );
+    }
+
+    #[test]
+    fn switching_environments_only_reevaluates_affected_nodes() {
+        let mut db = Database::default();
+        db.set_nodes(
+            vec![
+                Node {
+                    name: "url".into(),
+                    expr: Expression::variable("base_url").into(),
+                },
+                Node {
+                    name: "is-local".into(),
+                    expr: Expression::equals("url", "http://localhost/").into(),
+                },
+                Node {
+                    name: "user".into(),
+                    expr: Expression::variable("user").into(),
+                },
+                Node {
+                    name: "is-admin".into(),
+                    expr: Expression::equals("user", "admin").into(),
+                },
+            ]
+            .into(),
+        );
+        let mut environments = BTreeMap::new();
+        environments.insert(
+            Text::from("dev"),
+            environment(&[("base_url", "http://localhost/"), ("user", "admin")]),
+        );
+        environments.insert(
+            Text::from("staging"),
+            environment(&[("base_url", "https://staging/"), ("user", "admin")]),
+        );
+        db.set_environments(environments);
+        db.set_active_environment(Some("dev".into()));
+        db.evaluate();
+        db.executed("eval");
+
+        db.set_active_environment(Some("staging".into()));
+        let got = db.evaluate();
+
+        assert_eq!(got[1], Ok(Value::from(false)));
+        let executed = db.executed("eval");
+        assert_eq!(executed.len(), 2, "{:?}", executed);
+        assert!(executed.iter().all(|key| !key.contains("user")));
+    }
+
     #[test]
     fn cycles_are_errors() {
         let mut db = Database::default();
@@ -310,4 +901,338 @@ mod tests {
 
         assert_eq!(got, should_be);
     }
+
+    #[test]
+    fn depending_on_a_cycle() {
+        let mut db = Database::default();
+        let nodes: Sequence<_> = vec![
+            Node {
+                name: "first".into(),
+                expr: Expression::equals("second", 42).into(),
+            },
+            Node {
+                name: "second".into(),
+                expr: Expression::equals("first", 42).into(),
+            },
+            Node {
+                name: "third".into(),
+                expr: Expression::equals("first", 42).into(),
+            },
+        ]
+        .into();
+        db.set_nodes(nodes);
+
+        assert!(db.reference_cycle("third".into()).is_none());
+        assert_eq!(
+            db.evaluate()[2],
+            Err(EvaluationError::UpstreamFailed {
+                node: "first".into(),
+                message: "Cycle detected: first → second → first".into(),
+            })
+        );
+    }
+
+    #[test]
+    fn calling_string_functions() {
+        let mut db = Database::default();
+        let nodes: Sequence<_> = vec![
+            Node {
+                name: "name".into(),
+                expr: Expression::string("  World ").into(),
+            },
+            Node {
+                name: "trimmed".into(),
+                expr: Expression::call("trim", [Argument::node("name")]).into(),
+            },
+            Node {
+                name: "greeting".into(),
+                expr: Expression::call(
+                    "format",
+                    [Argument::constant("Hello, {}!"), Argument::node("trimmed")],
+                )
+                .into(),
+            },
+            Node {
+                name: "is-greeting".into(),
+                expr: Expression::equals("greeting", "Hello, World!").into(),
+            },
+            Node {
+                name: "shouting".into(),
+                expr: Expression::call("upper", [Argument::node("is-greeting")]).into(),
+            },
+        ]
+        .into();
+        db.set_nodes(nodes);
+
+        let got = db.evaluate();
+
+        assert_eq!(
+            &got[1..],
+            &[
+                Ok(Value::from("World")),
+                Ok(Value::from("Hello, World!")),
+                Ok(Value::from(true)),
+                Err(EvaluationError::from(
+                    "upper() expects a string for argument 1, found true"
+                )),
+            ]
+        );
+        assert_eq!(
+            db.diagnostics()[0],
+            Diagnostic::new(
+                "shouting",
+                "upper() expects a string for argument 1, found true"
+            )
+        );
+    }
+
+    #[test]
+    fn cycles_through_any_argument() {
+        let mut db = Database::default();
+        let nodes: Sequence<_> = vec![
+            Node {
+                name: "first".into(),
+                expr: Expression::string("1").into(),
+            },
+            Node {
+                name: "second".into(),
+                expr: Expression::call(
+                    "concat",
+                    [Argument::node("first"), Argument::node("third")],
+                )
+                .into(),
+            },
+            Node {
+                name: "third".into(),
+                expr: Expression::call("trim", [Argument::node("second")]).into(),
+            },
+        ]
+        .into();
+        db.set_nodes(nodes);
+
+        assert_eq!(db.reference_cycle("first".into()), None);
+        assert_eq!(
+            db.reference_cycle("second".into()),
+            Some(Sequence::from(vec![
+                Text::from("second"),
+                Text::from("third"),
+                Text::from("second"),
+            ]))
+        );
+    }
+
+    #[test]
+    fn regular_expressions() {
+        let mut db = Database::default();
+        let nodes: Sequence<_> = vec![
+            Node {
+                name: "version".into(),
+                expr: Expression::string("v1.2.3").into(),
+            },
+            Node {
+                name: "is-semver".into(),
+                expr: Expression::matches("version", r"^v\d+\.\d+\.\d+$").into(),
+            },
+            Node {
+                name: "major".into(),
+                expr: Expression::capture("version", r"v(?P<major>\d+)", "major").into(),
+            },
+            Node {
+                name: "pending".into(),
+                expr: Expression::Request {
+                    url: "http://example.com/".into(),
+                    auth: None,
+                    refresh: None,
+                    response: None,
+                    error: None,
+                }
+                .into(),
+            },
+            Node {
+                name: "invalid".into(),
+                expr: Expression::matches("pending", "(unclosed").into(),
+            },
+        ]
+        .into();
+        db.set_nodes(nodes);
+
+        let got = db.evaluate();
+
+        assert_eq!(got[1], Ok(Value::from(true)));
+        assert_eq!(got[2], Ok(Value::from("1")));
+        let diags = db.diagnostics();
+        assert_eq!(diags.len(), 1);
+        assert_eq!(diags[0].node, Text::from("invalid"));
+        assert!(diags[0]
+            .message
+            .starts_with("Invalid regular expression, /(unclosed/"));
+    }
+
+    #[test]
+    fn patterns_are_only_compiled_once() {
+        let mut db = Database::default();
+        let nodes = vec![
+            Node {
+                name: "greeting".into(),
+                expr: Expression::string("Hello").into(),
+            },
+            Node {
+                name: "first".into(),
+                expr: Expression::matches("greeting", "^H").into(),
+            },
+            Node {
+                name: "second".into(),
+                expr: Expression::matches("greeting", "^H").into(),
+            },
+        ];
+        db.set_nodes(nodes.clone().into());
+        assert_eq!(db.evaluate()[1], Ok(Value::from(true)));
+
+        let mut nodes = nodes;
+        nodes[0].expr = Expression::string("Goodbye").into();
+        db.set_nodes(nodes.into());
+        assert_eq!(db.evaluate()[2], Ok(Value::from(false)));
+
+        assert_eq!(
+            db.executed("pattern"),
+            vec![String::from("pattern(Text(\"^H\"))")]
+        );
+    }
+
+    #[test]
+    fn validating_against_a_json_schema() {
+        let mut db = Database::default();
+        let schema = serde_json::json!({
+            "type": "object",
+            "required": ["status"],
+        });
+        let nodes: Sequence<_> = vec![
+            Node {
+                name: "body".into(),
+                expr: Expression::string("not an object").into(),
+            },
+            Node {
+                name: "inline".into(),
+                expr: Expression::validate_schema("body", SchemaSource::inline(schema)).into(),
+            },
+            Node {
+                name: "from-file".into(),
+                expr: Expression::validate_schema("body", SchemaSource::file("schema.json")).into(),
+            },
+            Node {
+                name: "invalid".into(),
+                expr: Expression::validate_schema(
+                    "body",
+                    SchemaSource::inline(serde_json::json!({ "type": 42 })),
+                )
+                .into(),
+            },
+        ]
+        .into();
+        db.set_nodes(nodes);
+
+        let got = db.evaluate();
+
+        let result = match &got[1] {
+            Ok(Value::Object(obj)) => obj.clone(),
+            other => panic!("Expected an object, found {:?}", other),
+        };
+        assert_eq!(result["valid"], Value::from(false));
+        assert_eq!(
+            got[2],
+            Err(EvaluationError::from(
+                "The \"schema.json\" schema hasn't been loaded"
+            ))
+        );
+        assert!(got[3]
+            .as_ref()
+            .unwrap_err()
+            .message()
+            .starts_with("Invalid JSON Schema"));
+    }
+
+    #[test]
+    fn updated_within_the_last_hour() {
+        let call = |function: &str, arguments: Vec<Argument>| -> Arc<Expression> {
+            Expression::call(function, arguments).into()
+        };
+        let mut db = Database::default();
+        let nodes: Sequence<_> = vec![
+            Node {
+                name: "updated_at".into(),
+                expr: call(
+                    "parse_instant",
+                    vec![Argument::constant("2024-01-31T09:30:00Z")],
+                ),
+            },
+            Node {
+                name: "now".into(),
+                expr: call("now", vec![]),
+            },
+            Node {
+                name: "age".into(),
+                expr: call(
+                    "subtract",
+                    vec![Argument::node("now"), Argument::node("updated_at")],
+                ),
+            },
+            Node {
+                name: "one_hour".into(),
+                expr: call("hours", vec![Argument::constant(1)]),
+            },
+            Node {
+                name: "recent".into(),
+                expr: call(
+                    "less_than",
+                    vec![Argument::node("age"), Argument::node("one_hour")],
+                ),
+            },
+        ]
+        .into();
+        db.set_nodes(nodes);
+        db.set_now("2024-01-31T10:00:00Z".parse().unwrap());
+
+        assert_eq!(db.evaluate()[4], Ok(Value::from(true)));
+        assert_eq!(db.type_of("age".into()), Ok(crate::Type::Duration));
+
+        db.set_now("2024-01-31T11:00:00Z".parse().unwrap());
+
+        let got = db.evaluate();
+        assert_eq!(
+            got[2],
+            Ok(Value::from(crate::Duration::from_millis(90 * 60 * 1000)))
+        );
+        assert_eq!(got[4], Ok(Value::from(false)));
+    }
+
+    #[test]
+    fn diagnostics_for_errors_and_duplicate_names() {
+        let mut db = Database::default();
+        let nodes: Sequence<_> = vec![
+            Node {
+                name: "first".into(),
+                expr: Expression::string("asdf").into(),
+            },
+            Node {
+                name: "first".into(),
+                expr: Expression::string("asdf").into(),
+            },
+            Node {
+                name: "second".into(),
+                expr: Expression::equals("missing", 42).into(),
+            },
+        ]
+        .into();
+        db.set_nodes(nodes);
+
+        let got = db.diagnostics();
+
+        assert_eq!(
+            got,
+            vec![
+                Diagnostic::new("first", "There is already a node called \"first\""),
+                Diagnostic::new("second", "No \"missing\" input found"),
+            ]
+        );
+    }
 }
@@ -0,0 +1,648 @@
+use crate::{
+    patterns, schemas, Argument, Evaluate, EvaluationError, Expression, Operation, Redact,
+    Sequence, Text, Value,
+};
+use std::sync::Arc;
+
+/// A tree explaining how a node's value was derived.
+#[derive(Debug, Clone, PartialEq, Eq, Hash, serde::Serialize, serde::Deserialize)]
+#[cfg_attr(feature = "typescript", derive(ts_rs::TS))]
+pub struct Explanation {
+    pub node: Text,
+    pub expression: Arc<Expression>,
+    pub result: Result<Value, EvaluationError>,
+    pub derivation: Derivation,
+    /// Explanations for the nodes that were consulted.
+    pub inputs: Sequence<Explanation>,
+}
+
+impl Redact for Explanation {
+    fn redacted(&self) -> Self {
+        Explanation {
+            result: self.result.redacted(),
+            inputs: self.inputs.iter().map(Explanation::redacted).collect(),
+            ..self.clone()
+        }
+    }
+}
+
+impl Explanation {
+    /// Follow the chain of upstream failures back to the node where this
+    /// node's error or indeterminate value originated.
+    pub fn origin(&self) -> &Explanation {
+        match &self.derivation {
+            Derivation::UpstreamFailed { node } | Derivation::UpstreamIndeterminate { node } => {
+                match self.inputs.iter().find(|input| input.node == *node) {
+                    Some(input) => input.origin(),
+                    None => self,
+                }
+            }
+            _ => self,
+        }
+    }
+}
+
+/// The branch that was taken when evaluating a node.
+#[derive(Debug, Clone, PartialEq, Eq, Hash, serde::Serialize, serde::Deserialize)]
+#[cfg_attr(feature = "typescript", derive(ts_rs::TS))]
+#[serde(tag = "type", rename_all = "kebab-case")]
+pub enum Derivation {
+    Constant,
+    /// The request hasn't been sent yet.
+    RequestPending,
+    RequestSucceeded,
+    RequestFailed,
+    /// The target's value was compared with a constant.
+    Compared {
+        equal: bool,
+    },
+    PropertyFound,
+    MissingProperty {
+        field: Text,
+    },
+    NotAnObject,
+    AssertionPassed,
+    AssertionFailed,
+    NotABoolean,
+    /// The target was checked against a regular expression.
+    Matched {
+        matched: bool,
+    },
+    /// Text was extracted using a regular expression.
+    Captured,
+    /// The regular expression didn't capture the requested text.
+    NothingCaptured,
+    InvalidPattern,
+    NotAString,
+    /// A function was called with the values of its arguments.
+    Called {
+        function: Text,
+    },
+    /// An operation was applied to each item in an array.
+    Iterated {
+        operation: Operation,
+    },
+    NotAnArray,
+    /// The target was checked against a JSON Schema.
+    Validated {
+        valid: bool,
+    },
+    InvalidSchema,
+    /// The value was recalled from the previous evaluation.
+    Recalled,
+    /// Nothing was recorded for the target the previous time the workspace
+    /// was evaluated.
+    NoHistory,
+    /// The target's value was compared with its previous value.
+    ComparedWithPrevious {
+        changed: bool,
+    },
+    /// The value was loaded from a secret.
+    Secret,
+    /// The value was looked up in the active environment.
+    Variable {
+        environment: Option<Text>,
+    },
+    /// The target failed, so this node's value couldn't be determined.
+    UpstreamFailed {
+        node: Text,
+    },
+    /// The target's value was indeterminate, so this node's value is too.
+    UpstreamIndeterminate {
+        node: Text,
+    },
+    UnknownNode {
+        name: Text,
+    },
+    Cycle {
+        path: Sequence<Text>,
+    },
+}
+
+pub(crate) fn explain(db: &dyn Evaluate, name: Text) -> Option<Explanation> {
+    let expression = db.expression_of(name.clone())?;
+    let result = db.eval(name.clone(), Arc::clone(&expression));
+
+    let (derivation, inputs) = match db.reference_cycle(name.clone()) {
+        Some(path) => (Derivation::Cycle { path }, Vec::new()),
+        None => derive(db, &expression),
+    };
+
+    Some(Explanation {
+        node: name,
+        expression,
+        result,
+        derivation,
+        inputs: inputs.into(),
+    })
+}
+
+fn derive(db: &dyn Evaluate, expression: &Expression) -> (Derivation, Vec<Explanation>) {
+    match expression {
+        Expression::StringConstant { .. } => (Derivation::Constant, Vec::new()),
+        Expression::Request { error: Some(_), .. }
+        | Expression::OAuth2Token { error: Some(_), .. } => (Derivation::RequestFailed, Vec::new()),
+        Expression::Request {
+            response: Some(_), ..
+        }
+        | Expression::OAuth2Token {
+            response: Some(_), ..
+        } => (Derivation::RequestSucceeded, Vec::new()),
+        Expression::Request { .. } | Expression::OAuth2Token { .. } => {
+            (Derivation::RequestPending, Vec::new())
+        }
+        Expression::Secret { .. } => (Derivation::Secret, Vec::new()),
+        Expression::Variable { .. } => {
+            let environment = db.active_environment();
+            (Derivation::Variable { environment }, Vec::new())
+        }
+        Expression::Equals { target, value } => {
+            with_target(db, target, |target_value| match target_value {
+                Value::Indeterminate => Derivation::UpstreamIndeterminate {
+                    node: target.clone(),
+                },
+                _ => Derivation::Compared {
+                    equal: target_value == value,
+                },
+            })
+        }
+        Expression::GetProperty { target, field } => {
+            with_target(db, target, |target_value| match target_value {
+                Value::Object(obj) if obj.contains_key(&**field) => Derivation::PropertyFound,
+                Value::Object(_) => Derivation::MissingProperty {
+                    field: field.clone(),
+                },
+                Value::Indeterminate => Derivation::UpstreamIndeterminate {
+                    node: target.clone(),
+                },
+                _ => Derivation::NotAnObject,
+            })
+        }
+        Expression::Assert { target, .. } => {
+            with_target(db, target, |target_value| match target_value {
+                Value::Boolean(true) => Derivation::AssertionPassed,
+                Value::Boolean(false) => Derivation::AssertionFailed,
+                Value::Indeterminate => Derivation::UpstreamIndeterminate {
+                    node: target.clone(),
+                },
+                _ => Derivation::NotABoolean,
+            })
+        }
+        Expression::Matches { target, pattern } => {
+            let pattern = match db.pattern(pattern.clone()) {
+                Ok(p) => p,
+                Err(_) => return (Derivation::InvalidPattern, Vec::new()),
+            };
+            with_target(db, target, |target_value| match target_value {
+                Value::String(s) => Derivation::Matched {
+                    matched: patterns::matches(&pattern, s),
+                },
+                Value::Indeterminate => Derivation::UpstreamIndeterminate {
+                    node: target.clone(),
+                },
+                _ => Derivation::NotAString,
+            })
+        }
+        Expression::Capture {
+            target,
+            pattern,
+            group,
+        } => {
+            let pattern = match db.pattern(pattern.clone()) {
+                Ok(p) => p,
+                Err(_) => return (Derivation::InvalidPattern, Vec::new()),
+            };
+            with_target(db, target, |target_value| match target_value {
+                Value::String(s) => match patterns::capture(&pattern, s, group.as_ref()) {
+                    Ok(_) => Derivation::Captured,
+                    Err(_) => Derivation::NothingCaptured,
+                },
+                Value::Indeterminate => Derivation::UpstreamIndeterminate {
+                    node: target.clone(),
+                },
+                _ => Derivation::NotAString,
+            })
+        }
+        Expression::Call {
+            function,
+            arguments,
+        } => called(db, function, arguments),
+        Expression::Collection {
+            operation,
+            target,
+            lambda,
+        } => {
+            let (mut derivation, mut inputs) =
+                with_target(db, target, |target_value| match target_value {
+                    Value::Array(_) => Derivation::Iterated {
+                        operation: *operation,
+                    },
+                    Value::Indeterminate => Derivation::UpstreamIndeterminate {
+                        node: target.clone(),
+                    },
+                    _ => Derivation::NotAnArray,
+                });
+
+            // The lambda's body can also use other nodes
+            let free_variables = lambda.iter().flat_map(|l| l.free_variables());
+            for name in free_variables.filter(|&name| name != target) {
+                let upstream = match db.explain(name.clone()) {
+                    Some(e) => e,
+                    None => {
+                        let unknown = Derivation::UnknownNode { name: name.clone() };
+                        return (unknown, inputs);
+                    }
+                };
+
+                if let (Derivation::Iterated { .. }, Err(_)) = (&derivation, &upstream.result) {
+                    derivation = Derivation::UpstreamFailed { node: name.clone() };
+                }
+                inputs.push(upstream);
+            }
+
+            (derivation, inputs)
+        }
+        Expression::ValidateSchema { target, schema } => {
+            let schema = match db.schema(schema.clone()) {
+                Ok(s) => s,
+                Err(_) => return (Derivation::InvalidSchema, Vec::new()),
+            };
+            with_target(db, target, |target_value| match target_value {
+                Value::Indeterminate => Derivation::UpstreamIndeterminate {
+                    node: target.clone(),
+                },
+                value => Derivation::Validated {
+                    valid: schemas::is_valid(&schema, value),
+                },
+            })
+        }
+        Expression::Previous { target } => match db.previous_results().get(target) {
+            Some(_) => (Derivation::Recalled, Vec::new()),
+            None => (Derivation::NoHistory, Vec::new()),
+        },
+        Expression::Changed { target } => {
+            let previous = db.previous_results().get(target).cloned();
+            with_target(db, target, |target_value| match (target_value, previous) {
+                (Value::Indeterminate, _) => Derivation::UpstreamIndeterminate {
+                    node: target.clone(),
+                },
+                (_, None) => Derivation::NoHistory,
+                (value, Some(previous)) => Derivation::ComparedWithPrevious {
+                    changed: previous != Ok(crate::evaluate::fingerprinted(value)),
+                },
+            })
+        }
+    }
+}
+
+/// Explain a function call, blaming the first argument which failed or (if
+/// none failed) the first one which was indeterminate.
+fn called(
+    db: &dyn Evaluate,
+    function: &Text,
+    arguments: &[Argument],
+) -> (Derivation, Vec<Explanation>) {
+    let mut inputs = Vec::new();
+    let mut failed = None;
+    let mut indeterminate = None;
+
+    for argument in arguments {
+        let name = match argument {
+            Argument::Node { name } => name,
+            Argument::Constant { .. } => continue,
+        };
+
+        let upstream = match db.explain(name.clone()) {
+            Some(e) => e,
+            None => {
+                let unknown = Derivation::UnknownNode { name: name.clone() };
+                return (unknown, inputs);
+            }
+        };
+
+        match &upstream.result {
+            Err(_) if failed.is_none() => failed = Some(name.clone()),
+            Ok(Value::Indeterminate) if indeterminate.is_none() => {
+                indeterminate = Some(name.clone())
+            }
+            _ => {}
+        }
+
+        inputs.push(upstream);
+    }
+
+    let derivation = match (failed, indeterminate) {
+        (Some(node), _) => Derivation::UpstreamFailed { node },
+        (None, Some(node)) => Derivation::UpstreamIndeterminate { node },
+        (None, None) => Derivation::Called {
+            function: function.clone(),
+        },
+    };
+
+    (derivation, inputs)
+}
+
+/// Explain a node which depends on `target`, using `derivation` to determine
+/// which branch was taken when the target evaluated successfully.
+fn with_target(
+    db: &dyn Evaluate,
+    target: &Text,
+    derivation: impl FnOnce(&Value) -> Derivation,
+) -> (Derivation, Vec<Explanation>) {
+    let upstream = match db.explain(target.clone()) {
+        Some(e) => e,
+        None => {
+            let unknown = Derivation::UnknownNode {
+                name: target.clone(),
+            };
+            return (unknown, Vec::new());
+        }
+    };
+
+    let derivation = match &upstream.result {
+        Ok(value) => derivation(value),
+        Err(_) => Derivation::UpstreamFailed {
+            node: target.clone(),
+        },
+    };
+
+    (derivation, vec![upstream])
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{test_utils::Database, Inputs, InputsExt, Lambda, Node, Response};
+
+    fn request(response: Option<Response>, error: Option<&str>) -> Expression {
+        Expression::Request {
+            url: "http://example.com/".into(),
+            auth: None,
+            refresh: None,
+            response,
+            error: error.map(EvaluationError::from),
+        }
+    }
+
+    #[test]
+    fn unknown_nodes_have_no_explanation() {
+        let mut db = Database::default();
+        db.set_nodes(Sequence::empty());
+
+        assert!(db.explain("missing".into()).is_none());
+    }
+
+    #[test]
+    fn comparison_with_a_constant() {
+        let mut db = Database::default();
+        db.set_nodes(
+            vec![
+                Node::new("greeting", Expression::string("Hello")),
+                Node::new("is-hello", Expression::equals("greeting", "Hello")),
+            ]
+            .into(),
+        );
+
+        let got = db.explain("is-hello".into()).unwrap();
+
+        assert_eq!(got.result, Ok(Value::from(true)));
+        assert_eq!(got.derivation, Derivation::Compared { equal: true });
+        assert_eq!(got.inputs.len(), 1);
+        assert_eq!(got.inputs[0].node, Text::from("greeting"));
+        assert_eq!(got.inputs[0].derivation, Derivation::Constant);
+        assert_eq!(got.origin(), &got);
+    }
+
+    #[test]
+    fn comparison_with_the_previous_run() {
+        let mut db = Database::default();
+        db.set_nodes(
+            vec![
+                Node::new("greeting", Expression::string("Hello")),
+                Node::new("was", Expression::previous("greeting")),
+                Node::new("changed", Expression::changed("greeting")),
+            ]
+            .into(),
+        );
+
+        let got = db.explain("changed".into()).unwrap();
+        assert_eq!(got.result, Ok(Value::Indeterminate));
+        assert_eq!(got.derivation, Derivation::NoHistory);
+
+        let previous = [(Text::from("greeting"), Ok(Value::from("Hi")))];
+        db.set_previous_results(previous.into_iter().collect());
+
+        let got = db.explain("changed".into()).unwrap();
+        assert_eq!(got.result, Ok(Value::from(true)));
+        assert_eq!(
+            got.derivation,
+            Derivation::ComparedWithPrevious { changed: true }
+        );
+        let got = db.explain("was".into()).unwrap();
+        assert_eq!(got.result, Ok(Value::from("Hi")));
+        assert_eq!(got.derivation, Derivation::Recalled);
+        assert!(got.inputs.is_empty());
+    }
+
+    #[test]
+    fn blame_the_argument_which_failed() {
+        let mut db = Database::default();
+        db.set_nodes(
+            vec![
+                Node::new("greeting", Expression::string("Hello")),
+                Node::new("pending", request(None, None)),
+                Node::new("login", request(None, Some("401 Unauthorized"))),
+                Node::new(
+                    "message",
+                    Expression::call(
+                        "concat",
+                        [
+                            Argument::node("greeting"),
+                            Argument::constant(", "),
+                            Argument::node("pending"),
+                            Argument::node("login"),
+                        ],
+                    ),
+                ),
+            ]
+            .into(),
+        );
+
+        let got = db.explain("message".into()).unwrap();
+
+        assert_eq!(
+            got.derivation,
+            Derivation::UpstreamFailed {
+                node: "login".into()
+            }
+        );
+        assert_eq!(got.inputs.len(), 3);
+        assert_eq!(got.origin().node, Text::from("login"));
+        assert_eq!(got.origin().derivation, Derivation::RequestFailed);
+    }
+
+    #[test]
+    fn blame_nodes_used_by_a_lambda() {
+        let response = Response {
+            status: 200,
+            status_text: "OK".into(),
+            url: "http://example.com/".into(),
+            body: Value::Array(vec![Value::from("a"), Value::from("b")].into()),
+            metrics: None,
+        };
+        let lambda = Lambda::new(
+            "item",
+            vec![Node::new("is-match", Expression::equals("status", "a"))],
+        );
+        let db = Database::with_nodes(vec![
+            Node::new("response", request(Some(response), None)),
+            Node::new("items", Expression::get("response", "body")),
+            Node::new("status", request(None, Some("500 Internal Server Error"))),
+            Node::new(
+                "matching",
+                Expression::collection(Operation::Filter, "items", lambda),
+            ),
+        ]);
+
+        let got = db.explain("matching".into()).unwrap();
+
+        assert_eq!(
+            got.derivation,
+            Derivation::UpstreamFailed {
+                node: "status".into()
+            }
+        );
+        let inputs: Vec<_> = got.inputs.iter().map(|e| e.node.clone()).collect();
+        assert_eq!(inputs, vec![Text::from("items"), Text::from("status")]);
+        assert_eq!(got.origin().derivation, Derivation::RequestFailed);
+    }
+
+    #[test]
+    fn trace_an_error_back_to_its_origin() {
+        let mut db = Database::default();
+        db.set_nodes(
+            vec![
+                Node::new("login", request(None, Some("401 Unauthorized"))),
+                Node::new("status", Expression::get("login", "status")),
+                Node::new("is-ok", Expression::equals("status", 200)),
+            ]
+            .into(),
+        );
+
+        let got = db.explain("is-ok".into()).unwrap();
+
+        assert_eq!(
+            got.result,
+            Err(EvaluationError::UpstreamFailed {
+                node: "login".into(),
+                message: "401 Unauthorized".into(),
+            })
+        );
+        assert_eq!(
+            got.derivation,
+            Derivation::UpstreamFailed {
+                node: "status".into()
+            }
+        );
+        assert_eq!(
+            got.inputs[0].derivation,
+            Derivation::UpstreamFailed {
+                node: "login".into()
+            }
+        );
+        let origin = got.origin();
+        assert_eq!(origin.node, Text::from("login"));
+        assert_eq!(origin.derivation, Derivation::RequestFailed);
+        assert_eq!(
+            origin.result,
+            Err(EvaluationError::from("401 Unauthorized"))
+        );
+    }
+
+    #[test]
+    fn trace_an_indeterminate_value_to_a_pending_request() {
+        let mut db = Database::default();
+        db.set_nodes(
+            vec![
+                Node::new("request", request(None, None)),
+                Node::new("status", Expression::get("request", "status")),
+            ]
+            .into(),
+        );
+
+        let got = db.explain("status".into()).unwrap();
+
+        assert_eq!(got.result, Ok(Value::Indeterminate));
+        assert_eq!(
+            got.derivation,
+            Derivation::UpstreamIndeterminate {
+                node: "request".into()
+            }
+        );
+        assert_eq!(got.origin().derivation, Derivation::RequestPending);
+    }
+
+    #[test]
+    fn missing_properties() {
+        let mut db = Database::default();
+        let response = Response {
+            status: 200,
+            status_text: "OK".into(),
+            url: "http://example.com/".into(),
+            body: Value::Number(42),
+            metrics: None,
+        };
+        db.set_nodes(
+            vec![
+                Node::new("request", request(Some(response), None)),
+                Node::new("missing", Expression::get("request", "headers")),
+                Node::new("not-object", Expression::get("missing-target", "x")),
+            ]
+            .into(),
+        );
+
+        let got = db.explain("missing".into()).unwrap();
+        assert_eq!(
+            got.derivation,
+            Derivation::MissingProperty {
+                field: "headers".into()
+            }
+        );
+        assert_eq!(
+            got.result,
+            Err(EvaluationError::from(
+                "\"request\" has no \"headers\" field"
+            ))
+        );
+
+        let got = db.explain("not-object".into()).unwrap();
+        assert_eq!(
+            got.derivation,
+            Derivation::UnknownNode {
+                name: "missing-target".into()
+            }
+        );
+    }
+
+    #[test]
+    fn cycles() {
+        let mut db = Database::default();
+        db.set_nodes(
+            vec![
+                Node::new("first", Expression::equals("second", 42)),
+                Node::new("second", Expression::equals("first", 42)),
+            ]
+            .into(),
+        );
+
+        let got = db.explain("first".into()).unwrap();
+
+        assert_eq!(
+            got.derivation,
+            Derivation::Cycle {
+                path: vec![Text::from("first"), "second".into(), "first".into()].into()
+            }
+        );
+        assert!(got.inputs.is_empty());
+    }
+}
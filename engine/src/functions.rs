@@ -0,0 +1,881 @@
+//! Built-in functions which can be used by an [`Expression::Call`].
+//!
+//! [`Expression::Call`]: crate::Expression::Call
+
+use crate::{Duration, Evaluate, EvaluationError, Instant, Sequence, Text, Type, Value};
+use std::{cmp::Ordering, fmt::Write};
+
+const SECOND: i64 = 1000;
+const MINUTE: i64 = 60 * SECOND;
+const HOUR: i64 = 60 * MINUTE;
+const DAY: i64 = 24 * HOUR;
+
+/// Every built-in function, along with its signature and implementation.
+///
+/// Both [`call_builtin()`] and [`builtin_type()`] are driven by this table so
+/// the type checker and evaluator can't disagree about which functions exist.
+static BUILTINS: &[Builtin] = &[
+    Builtin::pure(
+        "concat",
+        |n| Signature::new(strings(n), 0, Type::String),
+        |f| f.concat(),
+    ),
+    Builtin::pure("format", format_signature, |f| f.format()),
+    Builtin::pure("contains", compare_signature, |f| {
+        f.compare(|s, other| s.contains(other))
+    }),
+    Builtin::pure("starts_with", compare_signature, |f| {
+        f.compare(|s, prefix| s.starts_with(prefix))
+    }),
+    Builtin::pure("ends_with", compare_signature, |f| {
+        f.compare(|s, suffix| s.ends_with(suffix))
+    }),
+    Builtin::pure(
+        "replace",
+        |_| Signature::new(strings(3), 3, Type::String),
+        |f| {
+            f.arity(3)?;
+            let s = f.string(0)?;
+            Ok(Value::from(s.replace(&**f.string(1)?, f.string(2)?)))
+        },
+    ),
+    Builtin::pure(
+        "split",
+        |_| Signature::new(strings(2), 2, Type::array(Type::String)),
+        |f| f.split(),
+    ),
+    Builtin::pure(
+        "join",
+        |_| {
+            Signature::new(
+                vec![Type::array(Type::String), Type::String],
+                2,
+                Type::String,
+            )
+        },
+        |f| f.join(),
+    ),
+    Builtin::pure(
+        "substring",
+        |_| {
+            Signature::new(
+                vec![Type::String, Type::Number, Type::Number],
+                2,
+                Type::String,
+            )
+        },
+        |f| f.substring(),
+    ),
+    Builtin::pure(
+        "length",
+        |_| Signature::new(vec![Type::Unknown], 1, Type::Number),
+        |f| f.length(),
+    )
+    .check(length_type),
+    Builtin::pure("upper", transform_signature, |f| {
+        f.transform(str::to_uppercase)
+    }),
+    Builtin::pure("lower", transform_signature, |f| {
+        f.transform(str::to_lowercase)
+    }),
+    Builtin::pure("trim", transform_signature, |f| {
+        f.transform(|s| s.trim().to_string())
+    }),
+    Builtin {
+        name: "now",
+        signature: |_| Signature::new(Vec::new(), 0, Type::Instant),
+        implementation: Implementation::Database(now),
+        check: None,
+    },
+    Builtin::pure(
+        "parse_instant",
+        |_| Signature::new(strings(1), 1, Type::Instant),
+        |f| f.parse_instant(),
+    ),
+    Builtin::pure("milliseconds", duration_signature, |f| f.duration(1)),
+    Builtin::pure("seconds", duration_signature, |f| f.duration(SECOND)),
+    Builtin::pure("minutes", duration_signature, |f| f.duration(MINUTE)),
+    Builtin::pure("hours", duration_signature, |f| f.duration(HOUR)),
+    Builtin::pure("days", duration_signature, |f| f.duration(DAY)),
+    Builtin::pure(
+        "total_seconds",
+        |_| Signature::new(vec![Type::Duration], 1, Type::Number),
+        |f| f.total_seconds(),
+    ),
+    // The return type depends on the arguments and is worked out by the check
+    Builtin::pure("add", arithmetic_signature, |f| f.add()).check(arithmetic_type),
+    Builtin::pure("subtract", arithmetic_signature, |f| f.subtract()).check(arithmetic_type),
+    Builtin::pure("less_than", order_signature, |f| f.order(Ordering::is_lt))
+        .check(comparison_type),
+    Builtin::pure("greater_than", order_signature, |f| {
+        f.order(Ordering::is_gt)
+    })
+    .check(comparison_type),
+];
+
+/// A built-in function.
+struct Builtin {
+    name: &'static str,
+    /// The function's signature when called with a certain number of
+    /// arguments.
+    signature: fn(usize) -> Signature,
+    implementation: Implementation,
+    /// Extra type checking for functions whose return type depends on their
+    /// arguments.
+    check: Option<TypeCheck>,
+}
+
+impl Builtin {
+    const fn pure(
+        name: &'static str,
+        signature: fn(usize) -> Signature,
+        implementation: fn(&Function<'_>) -> Result<Value, EvaluationError>,
+    ) -> Self {
+        Builtin {
+            name,
+            signature,
+            implementation: Implementation::Pure(implementation),
+            check: None,
+        }
+    }
+
+    const fn check(self, check: TypeCheck) -> Self {
+        Builtin {
+            check: Some(check),
+            ..self
+        }
+    }
+}
+
+type TypeCheck = fn(&str, &[Type]) -> Result<Type, EvaluationError>;
+
+enum Implementation {
+    Pure(fn(&Function<'_>) -> Result<Value, EvaluationError>),
+    /// Functions like `now()` which read from the database, so salsa knows
+    /// which nodes need to be re-evaluated when their input changes.
+    Database(fn(&dyn Evaluate, &Function<'_>) -> Result<Value, EvaluationError>),
+}
+
+/// The types of a built-in function's parameters, how many of them are
+/// required, and the type being returned.
+struct Signature {
+    parameters: Vec<Type>,
+    required: usize,
+    returns: Type,
+}
+
+impl Signature {
+    fn new(parameters: Vec<Type>, required: usize, returns: Type) -> Self {
+        Signature {
+            parameters,
+            required,
+            returns,
+        }
+    }
+}
+
+fn strings(n: usize) -> Vec<Type> {
+    vec![Type::String; n]
+}
+
+fn format_signature(args: usize) -> Signature {
+    // The template can be followed by any number of scalars
+    let mut parameters = vec![Type::String];
+    parameters.resize(args.max(1), Type::Unknown);
+    Signature::new(parameters, 1, Type::String)
+}
+
+fn compare_signature(_: usize) -> Signature {
+    Signature::new(strings(2), 2, Type::Boolean)
+}
+
+fn transform_signature(_: usize) -> Signature {
+    Signature::new(strings(1), 1, Type::String)
+}
+
+fn duration_signature(_: usize) -> Signature {
+    Signature::new(vec![Type::Number], 1, Type::Duration)
+}
+
+fn arithmetic_signature(_: usize) -> Signature {
+    Signature::new(vec![Type::Unknown; 2], 2, Type::Unknown)
+}
+
+fn order_signature(_: usize) -> Signature {
+    Signature::new(vec![Type::Unknown; 2], 2, Type::Boolean)
+}
+
+fn builtin(name: &str) -> Option<&'static Builtin> {
+    BUILTINS.iter().find(|b| b.name == name)
+}
+
+/// Is there a built-in function called `name`?
+pub(crate) fn is_builtin(name: &str) -> bool {
+    builtin(name).is_some()
+}
+
+fn unknown_function(name: &str) -> EvaluationError {
+    format!("No \"{}\" function found", name).into()
+}
+
+/// Call the built-in function called `name`.
+pub(crate) fn call_builtin(
+    db: &dyn Evaluate,
+    name: &str,
+    args: &[Value],
+) -> Result<Value, EvaluationError> {
+    let builtin = builtin(name).ok_or_else(|| unknown_function(name))?;
+    let f = Function { name, args };
+
+    match builtin.implementation {
+        Implementation::Pure(implementation) => implementation(&f),
+        Implementation::Database(implementation) => implementation(db, &f),
+    }
+}
+
+/// The current time, as set by [`Inputs::now()`][crate::Inputs::now].
+fn now(db: &dyn Evaluate, f: &Function<'_>) -> Result<Value, EvaluationError> {
+    f.arity(0)?;
+
+    Ok(Value::Instant(db.now()))
+}
+
+/// Work out the type returned by a built-in function, checking the types of
+/// its arguments.
+pub(crate) fn builtin_type(name: &str, args: &[Type]) -> Result<Type, EvaluationError> {
+    let builtin = builtin(name).ok_or_else(|| unknown_function(name))?;
+    let Signature {
+        parameters,
+        required,
+        returns,
+    } = (builtin.signature)(args.len());
+
+    if !(required..=parameters.len()).contains(&args.len()) {
+        return Err(arity_error(name, required, parameters.len(), args.len()));
+    }
+
+    for (i, (expected, actual)) in parameters.iter().zip(args).enumerate() {
+        if !expected.accepts_type(actual) {
+            let msg = format!(
+                "{}() expects {} for argument {}, found {}",
+                name,
+                expected,
+                i + 1,
+                actual
+            );
+            return Err(msg.into());
+        }
+    }
+
+    match builtin.check {
+        Some(check) => check(name, args),
+        None => Ok(returns),
+    }
+}
+
+fn length_type(name: &str, args: &[Type]) -> Result<Type, EvaluationError> {
+    if !matches!(args[0], Type::Unknown | Type::String | Type::Array { .. }) {
+        let msg = format!(
+            "{}() expects a string or array for argument 1, found {}",
+            name, args[0]
+        );
+        return Err(msg.into());
+    }
+
+    Ok(Type::Number)
+}
+
+fn comparison_type(name: &str, args: &[Type]) -> Result<Type, EvaluationError> {
+    let comparable =
+        |t: &Type| !matches!(t, Type::Boolean | Type::Object { .. } | Type::Array { .. });
+    let (a, b) = (&args[0], &args[1]);
+
+    if a.is_unknown() || b.is_unknown() || (a == b && comparable(a)) {
+        Ok(Type::Boolean)
+    } else {
+        Err(comparison_error(name, a, b))
+    }
+}
+
+/// The type returned by `add()` or `subtract()`.
+fn arithmetic_type(name: &str, args: &[Type]) -> Result<Type, EvaluationError> {
+    let (a, b) = (&args[0], &args[1]);
+    let returns = match (name, a, b) {
+        (_, Type::Unknown, _) | (_, _, Type::Unknown) => Type::Unknown,
+        (_, Type::Number, Type::Number) => Type::Number,
+        (_, Type::Duration, Type::Duration) => Type::Duration,
+        (_, Type::Instant, Type::Duration) => Type::Instant,
+        ("add", Type::Duration, Type::Instant) => Type::Instant,
+        ("subtract", Type::Instant, Type::Instant) => Type::Duration,
+        _ => return Err(arithmetic_error(name, a, b)),
+    };
+
+    Ok(returns)
+}
+
+fn arithmetic_error(name: &str, a: &Type, b: &Type) -> EvaluationError {
+    format!("{}() can't be used with {} and {}", name, a, b).into()
+}
+
+fn comparison_error(name: &str, a: &Type, b: &Type) -> EvaluationError {
+    format!("{}() can't compare {} with {}", name, a, b).into()
+}
+
+fn arity_error(name: &str, min: usize, max: usize, found: usize) -> EvaluationError {
+    let expected = if min == max {
+        min.to_string()
+    } else {
+        format!("{} to {}", min, max)
+    };
+    let plural = if max == 1 { "" } else { "s" };
+
+    format!(
+        "{}() expects {} argument{}, found {}",
+        name, expected, plural, found
+    )
+    .into()
+}
+
+/// A call to a built-in function.
+struct Function<'a> {
+    name: &'a str,
+    args: &'a [Value],
+}
+
+impl<'a> Function<'a> {
+    fn arity(&self, expected: usize) -> Result<(), EvaluationError> {
+        self.arity_between(expected, expected)
+    }
+
+    fn arity_between(&self, min: usize, max: usize) -> Result<(), EvaluationError> {
+        let found = self.args.len();
+
+        if (min..=max).contains(&found) {
+            return Ok(());
+        }
+
+        Err(arity_error(self.name, min, max, found))
+    }
+
+    fn type_error(&self, index: usize, expected: &str) -> EvaluationError {
+        format!(
+            "{}() expects {} for argument {}, found {}",
+            self.name,
+            expected,
+            index + 1,
+            self.args[index]
+        )
+        .into()
+    }
+
+    fn string(&self, index: usize) -> Result<&'a Text, EvaluationError> {
+        match &self.args[index] {
+            Value::String(s) => Ok(s),
+            _ => Err(self.type_error(index, "a string")),
+        }
+    }
+
+    fn number(&self, index: usize) -> Result<i32, EvaluationError> {
+        match self.args[index] {
+            Value::Number(n) => Ok(n),
+            _ => Err(self.type_error(index, "a number")),
+        }
+    }
+
+    fn index(&self, index: usize) -> Result<usize, EvaluationError> {
+        let n = self.number(index)?;
+        usize::try_from(n).map_err(|_| self.type_error(index, "a non-negative number"))
+    }
+
+    fn duration_arg(&self, index: usize) -> Result<Duration, EvaluationError> {
+        match self.args[index] {
+            Value::Duration(d) => Ok(d),
+            _ => Err(self.type_error(index, "a duration")),
+        }
+    }
+
+    fn overflowed(&self) -> EvaluationError {
+        format!("{}() overflowed", self.name).into()
+    }
+
+    fn concat(&self) -> Result<Value, EvaluationError> {
+        let mut concatenated = String::new();
+
+        for i in 0..self.args.len() {
+            concatenated.push_str(self.string(i)?);
+        }
+
+        Ok(Value::from(concatenated))
+    }
+
+    /// Fill in the `{}` placeholders in a template, where `{}` uses the next
+    /// argument and `{n}` uses the n'th one. Braces are escaped by doubling
+    /// them (`{{` and `}}`).
+    fn format(&self) -> Result<Value, EvaluationError> {
+        if self.args.is_empty() {
+            return Err(format!("{}() expects a template", self.name).into());
+        }
+
+        let template = self.string(0)?;
+        let mut formatted = String::new();
+        let mut next = 0;
+        let mut chars = template.chars().peekable();
+
+        while let Some(c) = chars.next() {
+            match c {
+                '{' if chars.peek() == Some(&'{') => {
+                    chars.next();
+                    formatted.push('{');
+                }
+                '}' if chars.peek() == Some(&'}') => {
+                    chars.next();
+                    formatted.push('}');
+                }
+                '{' => {
+                    let mut placeholder = String::new();
+                    loop {
+                        match chars.next() {
+                            Some('}') => break,
+                            Some(c) => placeholder.push(c),
+                            None => return Err("Unterminated \"{\" in the template".into()),
+                        }
+                    }
+
+                    let position = if placeholder.is_empty() {
+                        next += 1;
+                        next - 1
+                    } else {
+                        placeholder
+                            .trim()
+                            .parse()
+                            .map_err(|_| format!("Invalid placeholder, \"{{{}}}\"", placeholder))?
+                    };
+
+                    let index = position + 1;
+                    if index >= self.args.len() {
+                        let msg = format!(
+                            "The template refers to argument {}, which wasn't provided",
+                            position
+                        );
+                        return Err(msg.into());
+                    }
+                    self.write_scalar(&mut formatted, index)?;
+                }
+                '}' => return Err("Unmatched \"}\" in the template".into()),
+                other => formatted.push(other),
+            }
+        }
+
+        Ok(Value::from(formatted))
+    }
+
+    fn write_scalar(&self, buffer: &mut String, index: usize) -> Result<(), EvaluationError> {
+        match &self.args[index] {
+            Value::String(s) => buffer.push_str(s),
+            Value::Number(n) => {
+                let _ = write!(buffer, "{}", n);
+            }
+            Value::Boolean(b) => {
+                let _ = write!(buffer, "{}", b);
+            }
+            Value::Instant(instant) => {
+                let _ = write!(buffer, "{}", instant);
+            }
+            Value::Duration(duration) => {
+                let _ = write!(buffer, "{}", duration);
+            }
+            _ => {
+                return Err(
+                    self.type_error(index, "a string, number, boolean, instant, or duration")
+                )
+            }
+        }
+
+        Ok(())
+    }
+
+    fn compare(&self, predicate: impl Fn(&str, &str) -> bool) -> Result<Value, EvaluationError> {
+        self.arity(2)?;
+        Ok(Value::from(predicate(self.string(0)?, self.string(1)?)))
+    }
+
+    fn transform(&self, transform: impl Fn(&str) -> String) -> Result<Value, EvaluationError> {
+        self.arity(1)?;
+        Ok(Value::from(transform(self.string(0)?)))
+    }
+
+    /// Split a string on a separator, or into its characters if the separator
+    /// is empty.
+    fn split(&self) -> Result<Value, EvaluationError> {
+        self.arity(2)?;
+        let s = self.string(0)?;
+        let separator = self.string(1)?;
+
+        let pieces: Sequence<Value> = if separator.is_empty() {
+            s.chars().map(|c| Value::from(c.to_string())).collect()
+        } else {
+            s.split(&**separator).map(Value::from).collect()
+        };
+
+        Ok(Value::Array(pieces))
+    }
+
+    fn join(&self) -> Result<Value, EvaluationError> {
+        self.arity(2)?;
+        let separator = self.string(1)?;
+        let items = match &self.args[0] {
+            Value::Array(items) => items,
+            _ => return Err(self.type_error(0, "an array")),
+        };
+
+        let mut joined = String::new();
+
+        for (i, item) in items.iter().enumerate() {
+            if i > 0 {
+                joined.push_str(separator);
+            }
+            match item {
+                Value::String(s) => joined.push_str(s),
+                other => {
+                    let msg = format!(
+                        "{}() expects an array of strings, but item {} is {}",
+                        self.name, i, other
+                    );
+                    return Err(msg.into());
+                }
+            }
+        }
+
+        Ok(Value::from(joined))
+    }
+
+    /// The characters from `start` up to (but not including) `end`, where
+    /// `end` defaults to the end of the string.
+    fn substring(&self) -> Result<Value, EvaluationError> {
+        self.arity_between(2, 3)?;
+        let s = self.string(0)?;
+        let length = s.chars().count();
+        let start = self.index(1)?;
+        let end = if self.args.len() == 3 {
+            self.index(2)?
+        } else {
+            length
+        };
+
+        if start > end || end > length {
+            let msg = format!(
+                "The range {}..{} is out of bounds for a string with {} characters",
+                start, end, length
+            );
+            return Err(msg.into());
+        }
+
+        let substring: String = s.chars().skip(start).take(end - start).collect();
+        Ok(Value::from(substring))
+    }
+
+    /// The number of characters in a string or items in an array.
+    fn length(&self) -> Result<Value, EvaluationError> {
+        self.arity(1)?;
+        let length = match &self.args[0] {
+            Value::String(s) => s.chars().count(),
+            Value::Array(items) => items.len(),
+            _ => return Err(self.type_error(0, "a string or array")),
+        };
+
+        i32::try_from(length)
+            .map(Value::from)
+            .map_err(|_| self.overflowed())
+    }
+
+    fn parse_instant(&self) -> Result<Value, EvaluationError> {
+        self.arity(1)?;
+        let s = self.string(0)?;
+
+        s.parse::<Instant>().map(Value::from).map_err(|_| {
+            self.type_error(0, "an RFC 3339 timestamp (e.g. \"2024-01-31T09:30:00Z\")")
+        })
+    }
+
+    /// Create a duration from a number of `unit`-millisecond units.
+    fn duration(&self, unit: i64) -> Result<Value, EvaluationError> {
+        self.arity(1)?;
+        let n = self.number(0)?;
+
+        Ok(Value::from(Duration::from_millis(i64::from(n) * unit)))
+    }
+
+    /// The number of whole seconds in a duration.
+    fn total_seconds(&self) -> Result<Value, EvaluationError> {
+        self.arity(1)?;
+        let seconds = self.duration_arg(0)?.as_millis() / SECOND;
+
+        i32::try_from(seconds)
+            .map(Value::from)
+            .map_err(|_| self.overflowed())
+    }
+
+    /// Add two numbers or durations, or add a duration to an instant.
+    fn add(&self) -> Result<Value, EvaluationError> {
+        self.arity(2)?;
+
+        let sum = match (&self.args[0], &self.args[1]) {
+            (Value::Number(a), Value::Number(b)) => a.checked_add(*b).map(Value::from),
+            (Value::Duration(a), Value::Duration(b)) => a.checked_add(*b).map(Value::from),
+            (Value::Instant(a), Value::Duration(b)) | (Value::Duration(b), Value::Instant(a)) => {
+                a.checked_add(*b).map(Value::from)
+            }
+            (a, b) => return Err(arithmetic_error(self.name, &Type::of(a), &Type::of(b))),
+        };
+
+        sum.ok_or_else(|| self.overflowed())
+    }
+
+    /// Subtract two numbers or durations, subtract a duration from an
+    /// instant, or find the time between two instants.
+    fn subtract(&self) -> Result<Value, EvaluationError> {
+        self.arity(2)?;
+
+        let difference = match (&self.args[0], &self.args[1]) {
+            (Value::Number(a), Value::Number(b)) => a.checked_sub(*b).map(Value::from),
+            (Value::Duration(a), Value::Duration(b)) => a.checked_sub(*b).map(Value::from),
+            (Value::Instant(a), Value::Duration(b)) => a.checked_sub(*b).map(Value::from),
+            (Value::Instant(a), Value::Instant(b)) => a.duration_since(*b).map(Value::from),
+            (a, b) => return Err(arithmetic_error(self.name, &Type::of(a), &Type::of(b))),
+        };
+
+        difference.ok_or_else(|| self.overflowed())
+    }
+
+    fn order(&self, predicate: fn(Ordering) -> bool) -> Result<Value, EvaluationError> {
+        self.arity(2)?;
+        let (a, b) = (&self.args[0], &self.args[1]);
+
+        match crate::collections::compare(a, b) {
+            Some(ordering) if !matches!(a, Value::Boolean(_)) => {
+                Ok(Value::from(predicate(ordering)))
+            }
+            _ => Err(comparison_error(self.name, &Type::of(a), &Type::of(b))),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_utils::Database;
+
+    fn strings(items: &[&str]) -> Value {
+        items
+            .iter()
+            .copied()
+            .map(Value::from)
+            .collect::<Vec<_>>()
+            .into()
+    }
+
+    #[test]
+    fn string_functions() {
+        let db = Database::default();
+        let inputs = vec![
+            (
+                "concat",
+                vec!["Hello, ".into(), "World".into()],
+                "Hello, World".into(),
+            ),
+            ("concat", vec![], "".into()),
+            ("contains", vec!["Hello".into(), "ell".into()], true.into()),
+            (
+                "starts_with",
+                vec!["Hello".into(), "He".into()],
+                true.into(),
+            ),
+            ("ends_with", vec!["Hello".into(), "He".into()], false.into()),
+            (
+                "replace",
+                vec!["a-b-c".into(), "-".into(), "+".into()],
+                "a+b+c".into(),
+            ),
+            (
+                "split",
+                vec!["a,b,c".into(), ",".into()],
+                strings(&["a", "b", "c"]),
+            ),
+            ("split", vec!["ab".into(), "".into()], strings(&["a", "b"])),
+            (
+                "join",
+                vec![strings(&["a", "b"]), ", ".into()],
+                "a, b".into(),
+            ),
+            (
+                "substring",
+                vec!["héllo".into(), 1.into(), 3.into()],
+                "él".into(),
+            ),
+            ("substring", vec!["héllo".into(), 2.into()], "llo".into()),
+            ("length", vec!["héllo".into()], 5.into()),
+            ("length", vec![strings(&["a", "b"])], 2.into()),
+            ("upper", vec!["Hello".into()], "HELLO".into()),
+            ("lower", vec!["Hello".into()], "hello".into()),
+            ("trim", vec!["  Hello\n".into()], "Hello".into()),
+        ];
+
+        for (name, args, should_be) in inputs {
+            let got = call_builtin(&db, name, &args).unwrap();
+            assert_eq!(got, should_be, "{}({:?})", name, args);
+        }
+    }
+
+    #[test]
+    fn format_strings() {
+        let db = Database::default();
+        let inputs = vec![
+            (vec!["Hello, {}!".into(), "World".into()], "Hello, World!"),
+            (
+                vec!["{} is {}, {0}".into(), "x".into(), 42.into()],
+                "x is 42, x",
+            ),
+            (vec!["{{literal}} {}".into(), true.into()], "{literal} true"),
+        ];
+
+        for (args, should_be) in inputs {
+            let got = call_builtin(&db, "format", &args).unwrap();
+            assert_eq!(got, Value::from(should_be));
+        }
+    }
+
+    fn instant(timestamp: &str) -> Value {
+        Value::from(timestamp.parse::<Instant>().unwrap())
+    }
+
+    fn duration(ms: i64) -> Value {
+        Value::from(Duration::from_millis(ms))
+    }
+
+    #[test]
+    fn time_functions() {
+        let db = Database::default();
+        let inputs = vec![
+            (
+                "parse_instant",
+                vec!["2024-01-31T19:30:00+10:00".into()],
+                instant("2024-01-31T09:30:00Z"),
+            ),
+            ("minutes", vec![90.into()], duration(90 * MINUTE)),
+            ("total_seconds", vec![duration(90 * MINUTE)], 5400.into()),
+            (
+                "add",
+                vec![instant("2024-01-31T09:30:00Z"), duration(DAY)],
+                instant("2024-02-01T09:30:00Z"),
+            ),
+            (
+                "subtract",
+                vec![
+                    instant("2024-01-31T09:30:00Z"),
+                    instant("2024-01-31T08:00:00Z"),
+                ],
+                duration(90 * MINUTE),
+            ),
+            (
+                "subtract",
+                vec![instant("2024-01-31T09:30:00Z"), duration(HOUR)],
+                instant("2024-01-31T08:30:00Z"),
+            ),
+            ("add", vec![1.into(), 2.into()], 3.into()),
+            (
+                "less_than",
+                vec![duration(30 * MINUTE), duration(HOUR)],
+                true.into(),
+            ),
+            (
+                "greater_than",
+                vec![
+                    instant("2024-01-31T09:30:00Z"),
+                    instant("2024-01-31T09:30:00Z"),
+                ],
+                false.into(),
+            ),
+            (
+                "format",
+                vec![
+                    "{} ({})".into(),
+                    instant("2024-01-31T09:30:00Z"),
+                    duration(90 * MINUTE),
+                ],
+                "2024-01-31T09:30:00Z (1h 30m)".into(),
+            ),
+        ];
+
+        for (name, args, should_be) in inputs {
+            let got = call_builtin(&db, name, &args).unwrap();
+            assert_eq!(got, should_be, "{}({:?})", name, args);
+        }
+    }
+
+    #[test]
+    fn errors() {
+        let db = Database::default();
+        let inputs = vec![
+            ("missing", vec![], "No \"missing\" function found"),
+            ("trim", vec![], "trim() expects 1 argument, found 0"),
+            (
+                "substring",
+                vec!["x".into()],
+                "substring() expects 2 to 3 arguments, found 1",
+            ),
+            (
+                "upper",
+                vec![42.into()],
+                "upper() expects a string for argument 1, found 42",
+            ),
+            (
+                "substring",
+                vec!["abc".into(), (-1).into()],
+                "substring() expects a non-negative number for argument 2, found -1",
+            ),
+            (
+                "substring",
+                vec!["abc".into(), 1.into(), 5.into()],
+                "The range 1..5 is out of bounds for a string with 3 characters",
+            ),
+            (
+                "format",
+                vec!["{} {}".into(), "x".into()],
+                "The template refers to argument 1, which wasn't provided",
+            ),
+            (
+                "join",
+                vec![vec![Value::from(1)].into(), ",".into()],
+                "join() expects an array of strings, but item 0 is 1",
+            ),
+            (
+                "parse_instant",
+                vec!["yesterday".into()],
+                "parse_instant() expects an RFC 3339 timestamp (e.g. \"2024-01-31T09:30:00Z\") for argument 1, found \"yesterday\"",
+            ),
+            (
+                "add",
+                vec![
+                    instant("2024-01-31T09:30:00Z"),
+                    instant("2024-01-31T09:30:00Z"),
+                ],
+                "add() can't be used with an instant and an instant",
+            ),
+            (
+                "less_than",
+                vec![duration(0), 0.into()],
+                "less_than() can't compare a duration with a number",
+            ),
+            (
+                "add",
+                vec![instant("9999-12-31T23:59:59Z"), duration(DAY)],
+                "add() overflowed",
+            ),
+        ];
+
+        for (name, args, should_be) in inputs {
+            let got = call_builtin(&db, name, &args).unwrap_err();
+            assert_eq!(got, EvaluationError::from(should_be));
+        }
+    }
+}
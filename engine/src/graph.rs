@@ -0,0 +1,375 @@
+use crate::{evaluate::dependencies, Evaluate, EvaluationError, Text, Value};
+use std::{
+    collections::{BTreeMap, BTreeSet},
+    fmt::Write,
+};
+
+/// The dependencies between nodes, derived from their expressions.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct DependencyGraph {
+    pub nodes: Vec<GraphNode>,
+    /// Edges going from a node to the nodes which use it.
+    pub edges: Vec<Edge>,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct GraphNode {
+    pub name: Text,
+    /// The node's current result, if the graph was annotated with
+    /// [`DependencyGraph::with_results()`].
+    pub result: Option<Result<Value, EvaluationError>>,
+    pub in_cycle: bool,
+    /// The node is referenced, but doesn't exist.
+    pub missing: bool,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Edge {
+    pub from: Text,
+    pub to: Text,
+    pub in_cycle: bool,
+}
+
+impl DependencyGraph {
+    pub fn new(db: &dyn Evaluate) -> Self {
+        let mut nodes = Vec::new();
+        let mut edges = Vec::new();
+        let mut seen = BTreeSet::new();
+
+        for node in db.nodes().iter() {
+            if !seen.insert(node.name.clone()) {
+                continue;
+            }
+
+            let in_cycle = db.reference_cycle(node.name.clone()).is_some();
+
+            // An expression can refer to the same node more than once (e.g.
+            // a function called with the same argument twice)
+            let mut targets = Vec::new();
+            for target in dependencies(&node.expr) {
+                if !targets.contains(&target) {
+                    targets.push(target);
+                }
+            }
+
+            for target in targets {
+                edges.push(Edge {
+                    from: target.clone(),
+                    to: node.name.clone(),
+                    // The edge is only part of a cycle if we can get back
+                    // to the node by following the target's dependencies
+                    in_cycle: in_cycle && depends_on(db, target, &node.name),
+                });
+            }
+
+            nodes.push(GraphNode {
+                name: node.name.clone(),
+                result: None,
+                in_cycle,
+                missing: false,
+            });
+        }
+
+        for edge in &edges {
+            if seen.insert(edge.from.clone()) {
+                nodes.push(GraphNode {
+                    name: edge.from.clone(),
+                    result: None,
+                    in_cycle: false,
+                    missing: true,
+                });
+            }
+        }
+
+        DependencyGraph { nodes, edges }
+    }
+
+    /// Annotate each node with its current value or error.
+    pub fn with_results(mut self, db: &dyn Evaluate) -> Self {
+        for node in &mut self.nodes {
+            if let Some(expression) = db.expression_of(node.name.clone()) {
+                node.result = Some(db.eval(node.name.clone(), expression));
+            }
+        }
+
+        self
+    }
+
+    /// Render the graph using Graphviz's DOT language.
+    pub fn to_dot(&self) -> String {
+        let mut dot = String::from("digraph laskea {\n");
+
+        for node in &self.nodes {
+            let mut attributes = vec![format!("label={}", dot_string(&label(node, "\n")))];
+
+            if node.in_cycle {
+                attributes.push(String::from("color=red"));
+            }
+            if node.missing {
+                attributes.push(String::from("style=dashed"));
+            }
+            if let Some(Err(_)) = node.result {
+                attributes.push(String::from("fontcolor=red"));
+            }
+
+            let _ = writeln!(
+                dot,
+                "    {} [{}];",
+                dot_string(&node.name),
+                attributes.join(", ")
+            );
+        }
+
+        for edge in &self.edges {
+            let style = if edge.in_cycle { " [color=red]" } else { "" };
+            let _ = writeln!(
+                dot,
+                "    {} -> {}{};",
+                dot_string(&edge.from),
+                dot_string(&edge.to),
+                style
+            );
+        }
+
+        dot.push_str("}\n");
+        dot
+    }
+
+    /// Render the graph as a Mermaid flowchart.
+    pub fn to_mermaid(&self) -> String {
+        let ids: BTreeMap<&Text, String> = self
+            .nodes
+            .iter()
+            .enumerate()
+            .map(|(i, node)| (&node.name, format!("n{}", i)))
+            .collect();
+
+        let mut mermaid = String::from("flowchart LR\n");
+        let mut classes: BTreeMap<&str, Vec<&str>> = BTreeMap::new();
+
+        for node in &self.nodes {
+            let id = &ids[&node.name];
+            let _ = writeln!(
+                mermaid,
+                "    {}[\"{}\"]",
+                id,
+                mermaid_escape(&label(node, "<br/>"))
+            );
+
+            if node.in_cycle {
+                classes.entry("cycle").or_default().push(id);
+            }
+            if node.missing {
+                classes.entry("missing").or_default().push(id);
+            }
+            if let Some(Err(_)) = node.result {
+                classes.entry("failed").or_default().push(id);
+            }
+        }
+
+        let mut cycle_edges = Vec::new();
+
+        for (i, edge) in self.edges.iter().enumerate() {
+            let _ = writeln!(mermaid, "    {} --> {}", ids[&edge.from], ids[&edge.to]);
+
+            if edge.in_cycle {
+                cycle_edges.push(i.to_string());
+            }
+        }
+
+        for (class, members) in &classes {
+            let style = match *class {
+                "cycle" => "stroke:#d33,stroke-width:2px",
+                "missing" => "stroke-dasharray:4",
+                _ => "color:#d33",
+            };
+            let _ = writeln!(mermaid, "    classDef {} {}", class, style);
+            let _ = writeln!(mermaid, "    class {} {}", members.join(","), class);
+        }
+
+        if !cycle_edges.is_empty() {
+            let _ = writeln!(
+                mermaid,
+                "    linkStyle {} stroke:#d33",
+                cycle_edges.join(",")
+            );
+        }
+
+        mermaid
+    }
+}
+
+/// Does `node` use `target`, either directly or through other nodes?
+fn depends_on(db: &dyn Evaluate, node: &Text, target: &Text) -> bool {
+    let mut to_visit = vec![node.clone()];
+    let mut visited = BTreeSet::new();
+
+    while let Some(current) = to_visit.pop() {
+        if !visited.insert(current.clone()) {
+            continue;
+        }
+
+        if let Some(expression) = db.expression_of(current) {
+            for dep in dependencies(&expression) {
+                if dep == target {
+                    return true;
+                }
+                to_visit.push(dep.clone());
+            }
+        }
+    }
+
+    false
+}
+
+fn label(node: &GraphNode, newline: &str) -> String {
+    match &node.result {
+        Some(Ok(value)) => format!("{}{}= {}", node.name, newline, value),
+        Some(Err(e)) => format!("{}{}error: {}", node.name, newline, e),
+        None => node.name.to_string(),
+    }
+}
+
+fn dot_string(s: &str) -> String {
+    let escaped = s
+        .replace('\\', "\\\\")
+        .replace('"', "\\\"")
+        .replace('\n', "\\n");
+    format!("\"{}\"", escaped)
+}
+
+fn mermaid_escape(s: &str) -> String {
+    s.replace('"', "#quot;")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{test_utils::Database, Argument, Expression, Node};
+
+    #[test]
+    fn render_dot() {
+        let db = Database::with_nodes(vec![
+            Node::new("greeting", Expression::string("Hello")),
+            Node::new("is-hello", Expression::equals("greeting", "Hello")),
+        ]);
+
+        let got = DependencyGraph::new(&db).to_dot();
+
+        assert_eq!(
+            got,
+            concat!(
+                "digraph laskea {\n",
+                "    \"greeting\" [label=\"greeting\"];\n",
+                "    \"is-hello\" [label=\"is-hello\"];\n",
+                "    \"greeting\" -> \"is-hello\";\n",
+                "}\n",
+            )
+        );
+    }
+
+    #[test]
+    fn render_mermaid_with_results() {
+        let db = Database::with_nodes(vec![
+            Node::new("greeting", Expression::string("Hello")),
+            Node::new("is-hello", Expression::equals("greeting", "Hello")),
+            Node::new("broken", Expression::get("missing", "field")),
+        ]);
+
+        let got = DependencyGraph::new(&db).with_results(&db).to_mermaid();
+
+        assert_eq!(
+            got,
+            concat!(
+                "flowchart LR\n",
+                "    n0[\"greeting<br/>= #quot;Hello#quot;\"]\n",
+                "    n1[\"is-hello<br/>= true\"]\n",
+                "    n2[\"broken<br/>error: No #quot;missing#quot; input found\"]\n",
+                "    n3[\"missing\"]\n",
+                "    n0 --> n1\n",
+                "    n3 --> n2\n",
+                "    classDef failed color:#d33\n",
+                "    class n2 failed\n",
+                "    classDef missing stroke-dasharray:4\n",
+                "    class n3 missing\n",
+            )
+        );
+    }
+
+    #[test]
+    fn cycles_are_highlighted() {
+        let db = Database::with_nodes(vec![
+            Node::new("first", Expression::equals("second", 42)),
+            Node::new("second", Expression::equals("first", 42)),
+            Node::new("third", Expression::equals("first", 42)),
+        ]);
+
+        let got = DependencyGraph::new(&db);
+
+        let in_cycle: Vec<_> = got.nodes.iter().map(|n| n.in_cycle).collect();
+        assert_eq!(in_cycle, vec![true, true, false]);
+        let in_cycle: Vec<_> = got.edges.iter().map(|e| e.in_cycle).collect();
+        assert_eq!(in_cycle, vec![true, true, false]);
+        assert!(got
+            .to_dot()
+            .contains("    \"second\" -> \"first\" [color=red];\n"));
+        assert!(got.to_mermaid().contains("    linkStyle 0,1 stroke:#d33\n"));
+    }
+
+    #[test]
+    fn edges_between_cycles_are_not_highlighted() {
+        let db = Database::with_nodes(vec![
+            Node::new("a", Expression::equals("b", 42)),
+            Node::new("b", Expression::equals("a", 42)),
+            Node::new(
+                "c",
+                Expression::call("concat", [Argument::node("b"), Argument::node("d")]),
+            ),
+            Node::new("d", Expression::equals("c", 42)),
+        ]);
+
+        let got = DependencyGraph::new(&db);
+
+        assert!(got.nodes.iter().all(|n| n.in_cycle));
+        let in_cycle: Vec<_> = got
+            .edges
+            .iter()
+            .map(|e| (&*e.from, &*e.to, e.in_cycle))
+            .collect();
+        assert_eq!(
+            in_cycle,
+            vec![
+                ("b", "a", true),
+                ("a", "b", true),
+                ("b", "c", false),
+                ("d", "c", true),
+                ("c", "d", true),
+            ]
+        );
+    }
+
+    #[test]
+    fn each_dependency_has_one_edge() {
+        let db = Database::with_nodes(vec![
+            Node::new("name", Expression::string("World")),
+            Node::new(
+                "greeting",
+                Expression::call("concat", [Argument::node("name"), Argument::node("name")]),
+            ),
+        ]);
+
+        let got = DependencyGraph::new(&db);
+
+        let edges: Vec<_> = got.edges.iter().map(|e| (&*e.from, &*e.to)).collect();
+        assert_eq!(edges, vec![("name", "greeting")]);
+    }
+
+    #[test]
+    fn names_are_escaped() {
+        let db = Database::with_nodes(vec![Node::new("say \"hi\"", Expression::string("hi"))]);
+
+        let got = DependencyGraph::new(&db).to_dot();
+
+        assert!(got.contains("    \"say \\\"hi\\\"\" [label=\"say \\\"hi\\\"\"];\n"));
+    }
+}
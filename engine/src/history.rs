@@ -0,0 +1,415 @@
+//! Recording each node's results in a SQLite database so they can be
+//! compared across evaluations.
+
+use crate::{evaluate::fingerprinted, Evaluate, EvaluationError, Instant, Text, Value};
+use rusqlite::{params, types::FromSqlError, Connection, OptionalExtension, Row};
+use serde::{ser::SerializeStruct, Serialize, Serializer};
+use std::{
+    collections::BTreeMap,
+    error::Error,
+    fmt::{self, Display, Formatter},
+    path::Path,
+};
+
+/// A node's result at a point in time.
+#[derive(Debug, Clone, PartialEq, Eq, serde::Serialize, serde::Deserialize)]
+pub struct Entry {
+    pub timestamp: Instant,
+    pub result: Result<Value, EvaluationError>,
+}
+
+/// A record of every result each node has had, stored in a SQLite database.
+///
+/// Values are stored as JSON, with each [`Secret`][crate::Secret] replaced by
+/// its [`fingerprint`][crate::Secret::fingerprint] so secrets are never
+/// written to disk but `changed()` can still tell when they change.
+#[derive(Debug)]
+pub struct History {
+    conn: Connection,
+}
+
+impl History {
+    /// Open (or create) the history stored at `path`.
+    pub fn open(path: impl AsRef<Path>) -> Result<Self, HistoryError> {
+        History::new(Connection::open(path)?)
+    }
+
+    /// Create a history which is only kept in memory.
+    pub fn in_memory() -> Result<Self, HistoryError> {
+        History::new(Connection::open_in_memory()?)
+    }
+
+    fn new(conn: Connection) -> Result<Self, HistoryError> {
+        conn.execute_batch(
+            "CREATE TABLE IF NOT EXISTS results (
+                id INTEGER PRIMARY KEY,
+                node TEXT NOT NULL,
+                timestamp INTEGER NOT NULL,
+                value TEXT,
+                error TEXT
+            );
+            CREATE INDEX IF NOT EXISTS results_by_node ON results (node, timestamp);",
+        )?;
+
+        Ok(History { conn })
+    }
+
+    /// Evaluate the workspace and record every node's result.
+    pub fn record(&mut self, db: &dyn Evaluate, timestamp: Instant) -> Result<(), HistoryError> {
+        let nodes = db.nodes();
+        let results = db.evaluate();
+        let tx = self.conn.transaction()?;
+
+        {
+            let mut insert = tx.prepare(
+                "INSERT INTO results (node, timestamp, value, error) VALUES (?1, ?2, ?3, ?4)",
+            )?;
+
+            for (node, result) in nodes.iter().zip(results.iter()) {
+                let (value, error) = match result {
+                    Ok(value) => (Some(to_json(&Exposed(&fingerprinted(value)))?), None),
+                    Err(e) => (None, Some(to_json(e)?)),
+                };
+                insert.execute(params![&*node.name, timestamp.unix_millis(), value, error])?;
+            }
+        }
+
+        tx.commit()?;
+        Ok(())
+    }
+
+    /// Every result recorded for a node, oldest first.
+    pub fn entries(&self, node: &str) -> Result<Vec<Entry>, HistoryError> {
+        let mut query = self.conn.prepare(
+            "SELECT timestamp, value, error FROM results WHERE node = ?1 ORDER BY timestamp, id",
+        )?;
+        let entries = query.query_map([node], entry)?.collect::<Result<_, _>>()?;
+
+        Ok(entries)
+    }
+
+    /// The most recent result recorded for a node.
+    pub fn latest(&self, node: &str) -> Result<Option<Entry>, HistoryError> {
+        let entry = self
+            .conn
+            .query_row(
+                "SELECT timestamp, value, error FROM results WHERE node = ?1
+                ORDER BY timestamp DESC, id DESC LIMIT 1",
+                [node],
+                entry,
+            )
+            .optional()?;
+
+        Ok(entry)
+    }
+
+    /// The most recent result recorded for every node, suitable for
+    /// [`Inputs::set_previous_results()`][crate::Inputs::set_previous_results].
+    pub fn latest_results(
+        &self,
+    ) -> Result<BTreeMap<Text, Result<Value, EvaluationError>>, HistoryError> {
+        let mut query = self.conn.prepare(
+            "SELECT node, timestamp, value, error FROM results AS r
+            WHERE id = (
+                SELECT id FROM results WHERE node = r.node
+                ORDER BY timestamp DESC, id DESC LIMIT 1
+            )",
+        )?;
+        let results = query
+            .query_map([], |row| {
+                let node: String = row.get(0)?;
+                let Entry { result, .. } = entry_from(row, 1)?;
+                Ok((Text::from(node), result))
+            })?
+            .collect::<Result<_, _>>()?;
+
+        Ok(results)
+    }
+
+    /// The percentage of checks an assertion passed since `since` (or since
+    /// it was first recorded), or `None` if it was never checked.
+    ///
+    /// Checks where the assertion's value was indeterminate (e.g. because a
+    /// request hadn't been sent yet) aren't counted.
+    pub fn uptime(&self, node: &str, since: Option<Instant>) -> Result<Option<f64>, HistoryError> {
+        let since = since.unwrap_or(Instant::UNIX_EPOCH);
+        let mut passed = 0_u32;
+        let mut checked = 0_u32;
+
+        for entry in self.entries(node)? {
+            if entry.timestamp < since {
+                continue;
+            }
+
+            match entry.result {
+                Ok(Value::Indeterminate) => continue,
+                Ok(_) => passed += 1,
+                Err(_) => {}
+            }
+            checked += 1;
+        }
+
+        if checked == 0 {
+            Ok(None)
+        } else {
+            Ok(Some(f64::from(passed) * 100.0 / f64::from(checked)))
+        }
+    }
+}
+
+fn entry(row: &Row<'_>) -> rusqlite::Result<Entry> {
+    entry_from(row, 0)
+}
+
+/// Read an [`Entry`] from the `timestamp`, `value`, and `error` columns,
+/// starting at `column`.
+fn entry_from(row: &Row<'_>, column: usize) -> rusqlite::Result<Entry> {
+    let ms: i64 = row.get(column)?;
+    let value: Option<String> = row.get(column + 1)?;
+    let error: Option<String> = row.get(column + 2)?;
+
+    let timestamp = Instant::from_unix_millis(ms)
+        .ok_or_else(|| conversion_error(column, FromSqlError::OutOfRange(ms)))?;
+    let result = match (value, error) {
+        (Some(value), _) => Ok(from_json(column + 1, &value)?),
+        (None, Some(error)) => Err(from_json(column + 2, &error)?),
+        (None, None) => Ok(Value::Indeterminate),
+    };
+
+    Ok(Entry { timestamp, result })
+}
+
+/// Serializes a [`Value`] the same way it normally would be, except the
+/// secrets inside it keep their real values.
+/// That's safe here because secrets are replaced by their fingerprints
+/// before being stored.
+struct Exposed<'a>(&'a Value);
+
+impl Serialize for Exposed<'_> {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        let tag = match self.0 {
+            Value::Object(_) => "object",
+            Value::Array(_) => "array",
+            Value::Secret(_) => "secret",
+            other => return other.serialize(serializer),
+        };
+
+        let mut value = serializer.serialize_struct("Value", 2)?;
+        value.serialize_field("type", tag)?;
+        match self.0 {
+            Value::Object(obj) => {
+                let fields: BTreeMap<_, _> = obj.iter().map(|(k, v)| (k, Exposed(v))).collect();
+                value.serialize_field("value", &fields)?;
+            }
+            Value::Array(items) => {
+                let items: Vec<_> = items.iter().map(Exposed).collect();
+                value.serialize_field("value", &items)?;
+            }
+            Value::Secret(secret) => value.serialize_field("value", secret.expose())?,
+            _ => unreachable!(),
+        }
+        value.end()
+    }
+}
+
+fn to_json(value: &impl serde::Serialize) -> Result<String, HistoryError> {
+    serde_json::to_string(value)
+        .map_err(|e| HistoryError(rusqlite::Error::ToSqlConversionFailure(Box::new(e))))
+}
+
+fn from_json<T: serde::de::DeserializeOwned>(column: usize, json: &str) -> rusqlite::Result<T> {
+    serde_json::from_str(json).map_err(|e| conversion_error(column, FromSqlError::Other(e.into())))
+}
+
+fn conversion_error(column: usize, e: FromSqlError) -> rusqlite::Error {
+    rusqlite::Error::FromSqlConversionFailure(column, rusqlite::types::Type::Text, e.into())
+}
+
+/// The error returned when the [`History`] database can't be used.
+#[derive(Debug)]
+pub struct HistoryError(rusqlite::Error);
+
+impl From<rusqlite::Error> for HistoryError {
+    fn from(e: rusqlite::Error) -> Self {
+        HistoryError(e)
+    }
+}
+
+impl Display for HistoryError {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        write!(f, "Unable to access the history: {}", self.0)
+    }
+}
+
+impl Error for HistoryError {
+    fn source(&self) -> Option<&(dyn Error + 'static)> {
+        Some(&self.0)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{test_utils::Database, Expression, Inputs, InputsExt, Node, Secret};
+
+    fn at(seconds_after_epoch: i64) -> Instant {
+        Instant::from_unix_millis(seconds_after_epoch * 1000).unwrap()
+    }
+
+    fn set_status(db: &mut Database, status: &str) {
+        db.set_nodes(
+            vec![
+                Node {
+                    name: "status".into(),
+                    expr: Expression::string(status).into(),
+                },
+                Node {
+                    name: "is-up".into(),
+                    expr: Expression::equals("status", "up").into(),
+                },
+                Node {
+                    name: "assertion".into(),
+                    expr: Expression::assert("is-up", "The service should be up").into(),
+                },
+            ]
+            .into(),
+        );
+    }
+
+    #[test]
+    fn record_and_query_a_nodes_history() {
+        let mut db = Database::default();
+        let mut history = History::in_memory().unwrap();
+
+        for (time, status) in [(0, "up"), (10, "down"), (20, "up")] {
+            set_status(&mut db, status);
+            history.record(&db, at(time)).unwrap();
+        }
+
+        let entries = history.entries("status").unwrap();
+        assert_eq!(
+            entries,
+            vec![
+                Entry {
+                    timestamp: at(0),
+                    result: Ok(Value::from("up")),
+                },
+                Entry {
+                    timestamp: at(10),
+                    result: Ok(Value::from("down")),
+                },
+                Entry {
+                    timestamp: at(20),
+                    result: Ok(Value::from("up")),
+                },
+            ]
+        );
+        assert_eq!(
+            history.entries("assertion").unwrap()[1].result,
+            Err(EvaluationError::from("The service should be up"))
+        );
+        assert_eq!(history.latest("status").unwrap(), entries.last().cloned());
+        assert_eq!(history.latest("missing").unwrap(), None);
+    }
+
+    #[test]
+    fn uptime_of_an_assertion() {
+        let mut db = Database::default();
+        let mut history = History::in_memory().unwrap();
+
+        for (time, status) in [(0, "up"), (10, "down"), (20, "up"), (30, "up")] {
+            set_status(&mut db, status);
+            history.record(&db, at(time)).unwrap();
+        }
+
+        assert_eq!(history.uptime("assertion", None).unwrap(), Some(75.0));
+        assert_eq!(
+            history.uptime("assertion", Some(at(20))).unwrap(),
+            Some(100.0)
+        );
+        assert_eq!(history.uptime("missing", None).unwrap(), None);
+    }
+
+    #[test]
+    fn compare_with_the_previous_run() {
+        let mut db = Database::default();
+        let mut history = History::in_memory().unwrap();
+        db.set_previous_results(history.latest_results().unwrap());
+        set_status(&mut db, "up");
+        let mut nodes = db.nodes().to_vec();
+        nodes.push(Node {
+            name: "was".into(),
+            expr: Expression::previous("status").into(),
+        });
+        nodes.push(Node {
+            name: "changed".into(),
+            expr: Expression::changed("status").into(),
+        });
+        db.set_nodes(nodes.clone().into());
+
+        // Nothing has been recorded yet
+        assert_eq!(db.evaluate()[3], Ok(Value::Indeterminate));
+        assert_eq!(db.evaluate()[4], Ok(Value::Indeterminate));
+        history.record(&db, at(0)).unwrap();
+        db.set_previous_results(history.latest_results().unwrap());
+
+        assert_eq!(db.evaluate()[3], Ok(Value::from("up")));
+        assert_eq!(db.evaluate()[4], Ok(Value::from(false)));
+
+        nodes[0] = Node {
+            name: "status".into(),
+            expr: Expression::string("down").into(),
+        };
+        db.set_nodes(nodes.into());
+
+        assert_eq!(db.evaluate()[3], Ok(Value::from("up")));
+        assert_eq!(db.evaluate()[4], Ok(Value::from(true)));
+    }
+
+    #[test]
+    fn secrets_are_stored_as_fingerprints() {
+        let mut db = Database::with_nodes(vec![
+            Node::new("token", Expression::secret("api_token")),
+            Node::new("changed", Expression::changed("token")),
+        ]);
+        let mut history = History::in_memory().unwrap();
+        let set_token = |db: &mut Database, token: &str| {
+            db.set_secrets(
+                [(Text::from("api_token"), Secret::new(token))]
+                    .into_iter()
+                    .collect(),
+            )
+        };
+        set_token(&mut db, "hunter2");
+
+        history.record(&db, at(0)).unwrap();
+        db.set_previous_results(history.latest_results().unwrap());
+
+        let stored = history.latest("token").unwrap().unwrap().result;
+        assert_eq!(
+            stored,
+            Ok(Value::Secret(Secret::new("hunter2").fingerprint()))
+        );
+        assert!(!serde_json::to_string(&stored).unwrap().contains("hunter2"));
+        assert_eq!(db.evaluate()[1], Ok(Value::from(false)));
+
+        set_token(&mut db, "correct horse battery staple");
+
+        assert_eq!(db.evaluate()[1], Ok(Value::from(true)));
+    }
+
+    #[test]
+    fn history_is_kept_on_disk() {
+        let dir = std::env::temp_dir().join(format!("laskea-history-{}", std::process::id()));
+        let _ = std::fs::remove_dir_all(&dir);
+        std::fs::create_dir_all(&dir).unwrap();
+        let path = dir.join("history.sqlite");
+        let mut db = Database::default();
+        set_status(&mut db, "up");
+
+        History::open(&path).unwrap().record(&db, at(0)).unwrap();
+        let history = History::open(&path).unwrap();
+
+        assert_eq!(history.entries("status").unwrap().len(), 1);
+    }
+}
@@ -1,7 +1,91 @@
-use crate::{Node, Sequence};
+use crate::{
+    Environment, EvaluationError, Function, Instant, Json, Node, Secret, Sequence, Text, Value,
+};
+use std::collections::BTreeMap;
 
 #[salsa::query_group(InputsStorage)]
 pub trait Inputs {
+    /// The order nodes appear in the workspace.
     #[salsa::input]
+    fn node_ids(&self) -> Sequence<NodeId>;
+    /// Each node is its own input, so editing one node doesn't invalidate
+    /// anything which only depends on the others.
+    ///
+    /// Use [`InputsExt`][crate::InputsExt] to add, edit, and remove nodes
+    /// rather than setting this directly.
+    #[salsa::input]
+    fn node(&self, id: NodeId) -> Node;
+    /// Every node in the workspace, in order.
     fn nodes(&self) -> Sequence<Node>;
+    /// Every known environment, keyed by name.
+    #[salsa::input]
+    fn environments(&self) -> BTreeMap<Text, Environment>;
+    /// The environment [`Expression::Variable`][crate::Expression::Variable]
+    /// nodes are resolved from, if any.
+    #[salsa::input]
+    fn active_environment(&self) -> Option<Text>;
+    /// Secrets which have been loaded for
+    /// [`Expression::Secret`][crate::Expression::Secret] nodes.
+    #[salsa::input]
+    fn secrets(&self) -> BTreeMap<Text, Secret>;
+    /// User-defined functions which can be called by an
+    /// [`Expression::Call`][crate::Expression::Call], keyed by name.
+    #[salsa::input]
+    fn functions(&self) -> BTreeMap<Text, Function>;
+    /// JSON Schemas which have been read from disk for
+    /// [`SchemaSource::File`][crate::SchemaSource::File], keyed by path.
+    #[salsa::input]
+    fn schema_files(&self) -> BTreeMap<Text, Result<Json, EvaluationError>>;
+    /// The current time, as returned by the `now()` function.
+    ///
+    /// This is only updated when the host sets it, so evaluation is
+    /// deterministic and only nodes which call `now()` are re-evaluated
+    /// when it changes.
+    #[salsa::input]
+    fn now(&self) -> Instant;
+    /// The result each node had the previous time the workspace was
+    /// evaluated, keyed by name, for
+    /// [`Expression::Previous`][crate::Expression::Previous] and
+    /// [`Expression::Changed`][crate::Expression::Changed] nodes.
+    ///
+    /// Secrets are stored as their [`Secret::fingerprint()`] so the real
+    /// values don't need to be kept around.
+    #[salsa::input]
+    fn previous_results(&self) -> BTreeMap<Text, Result<Value, EvaluationError>>;
+}
+
+/// A stable identifier for a node, which doesn't change when the node is
+/// renamed, edited, or moved.
+#[derive(Debug, Copy, Clone, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct NodeId(u32);
+
+impl NodeId {
+    /// An identifier which isn't used by any of the `existing` nodes.
+    pub(crate) fn unused(existing: &[NodeId]) -> NodeId {
+        existing
+            .iter()
+            .max()
+            .map_or(NodeId(0), |NodeId(id)| NodeId(id + 1))
+    }
+}
+
+fn nodes(db: &dyn Inputs) -> Sequence<Node> {
+    db.node_ids().iter().map(|&id| db.node(id)).collect()
+}
+
+/// Give every input an empty starting value (no nodes, environments,
+/// secrets, functions, or previous results) so a new database can be
+/// evaluated.
+///
+/// The time returned by `now()` starts at the Unix epoch, so hosts should
+/// follow this with a call to [`Inputs::set_now()`].
+pub fn init_inputs(db: &mut dyn Inputs) {
+    db.set_node_ids(Sequence::empty());
+    db.set_environments(BTreeMap::new());
+    db.set_active_environment(None);
+    db.set_secrets(BTreeMap::new());
+    db.set_functions(BTreeMap::new());
+    db.set_schema_files(BTreeMap::new());
+    db.set_now(Instant::UNIX_EPOCH);
+    db.set_previous_results(BTreeMap::new());
 }
@@ -2,16 +2,57 @@
 #[macro_use]
 extern crate pretty_assertions;
 
+mod alerts;
+mod auth;
+mod changes;
+mod collections;
+mod datetime;
+mod diff;
+mod edits;
 mod evaluate;
+mod explain;
+mod functions;
+mod graph;
+#[cfg(feature = "history")]
+mod history;
 mod inputs;
+mod patterns;
+mod scheduler;
+pub mod schemas;
+mod secrets;
 mod sequence;
+#[cfg(test)]
+mod test_utils;
+mod testing;
 mod text;
+mod typecheck;
 mod types;
+#[cfg(feature = "typescript")]
+mod typescript;
+mod user_functions;
 
 pub use self::{
+    alerts::{Alert, AlertKind, AlertPolicy, Alerter},
+    auth::{ApiKeyLocation, Auth, PreparedRequest},
+    changes::{Change, ChangeTracker},
+    datetime::{Duration, Instant, InvalidTimestamp},
+    diff::{diff, merge, Conflict, Merged, NodeChange},
+    edits::{EditError, InputsExt},
     evaluate::{Evaluate, EvaluateStorage},
-    inputs::{Inputs, InputsStorage},
+    explain::{Derivation, Explanation},
+    graph::{DependencyGraph, Edge, GraphNode},
+    inputs::{init_inputs, Inputs, InputsStorage, NodeId},
+    patterns::Pattern,
+    scheduler::{Clock, Scheduler, SystemClock},
+    schemas::Schema,
+    secrets::{EnvironmentVariables, Keyring, Redact, Secret, SecretSource, SecretsFile, REDACTED},
     sequence::Sequence,
+    testing::{run_tests, Outcome, TestCase, TestReport},
     text::Text,
     types::*,
 };
+
+#[cfg(feature = "history")]
+pub use crate::history::{Entry, History, HistoryError};
+#[cfg(feature = "typescript")]
+pub use crate::typescript::typescript_declarations;
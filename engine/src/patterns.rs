@@ -0,0 +1,165 @@
+use crate::{Evaluate, EvaluationError, Object, Text, Value};
+use regex::{Captures, Regex};
+use std::{collections::BTreeMap, sync::Arc};
+
+/// A compiled regular expression.
+///
+/// Patterns are compiled by the [`Evaluate::pattern()`] query, which doesn't
+/// depend on any inputs, so each pattern is only compiled once no matter how
+/// many nodes use it or how often the workspace changes.
+#[derive(Debug, Clone)]
+pub struct Pattern(Arc<Regex>);
+
+impl Pattern {
+    pub fn as_str(&self) -> &str {
+        self.0.as_str()
+    }
+}
+
+impl PartialEq for Pattern {
+    fn eq(&self, other: &Pattern) -> bool {
+        self.as_str() == other.as_str()
+    }
+}
+
+impl Eq for Pattern {}
+
+pub(crate) fn pattern(_db: &dyn Evaluate, pattern: Text) -> Result<Pattern, EvaluationError> {
+    Regex::new(&pattern)
+        .map(|re| Pattern(Arc::new(re)))
+        .map_err(|e| format!("Invalid regular expression, /{}/: {}", pattern, e).into())
+}
+
+/// Does the string match the pattern anywhere?
+pub(crate) fn matches(pattern: &Pattern, value: &str) -> bool {
+    pattern.0.is_match(value)
+}
+
+/// Extract the text matched by a capture group, where `group` is either the
+/// group's name or its number.
+///
+/// When no group is specified, every match is returned as an array of objects
+/// mapping each group (by number and by name) to the text it matched.
+pub(crate) fn capture(
+    pattern: &Pattern,
+    value: &str,
+    group: Option<&Text>,
+) -> Result<Value, EvaluationError> {
+    let re = &pattern.0;
+
+    let group = match group {
+        Some(group) => group,
+        None => {
+            let matches = re
+                .captures_iter(value)
+                .map(|captures| Value::Object(groups(re, &captures)))
+                .collect();
+            return Ok(Value::Array(matches));
+        }
+    };
+
+    let known_group = match group.parse::<usize>() {
+        Ok(index) => index < re.captures_len(),
+        Err(_) => re.capture_names().flatten().any(|name| name == &**group),
+    };
+    if !known_group {
+        let msg = format!("/{}/ has no \"{}\" group", re.as_str(), group);
+        return Err(msg.into());
+    }
+
+    let captures = re
+        .captures(value)
+        .ok_or_else(|| format!("{:?} doesn't match /{}/", value, re.as_str()))?;
+    let matched = match group.parse::<usize>() {
+        Ok(index) => captures.get(index),
+        Err(_) => captures.name(group),
+    };
+
+    matched
+        .map(|m| Value::from(m.as_str()))
+        .ok_or_else(|| format!("The \"{}\" group didn't match anything", group).into())
+}
+
+fn groups(re: &Regex, captures: &Captures<'_>) -> Object {
+    let mut groups = BTreeMap::new();
+
+    for (index, name) in re.capture_names().enumerate() {
+        if let Some(m) = captures.get(index) {
+            let text = Value::from(m.as_str());
+            if let Some(name) = name {
+                groups.insert(Text::from(name), text.clone());
+            }
+            groups.insert(Text::from(index.to_string()), text);
+        }
+    }
+
+    groups.into()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn compile(pattern: &str) -> Pattern {
+        Pattern(Arc::new(Regex::new(pattern).unwrap()))
+    }
+
+    #[test]
+    fn capture_a_group() {
+        let pattern = compile(r"(?P<major>\d+)\.(\d+)");
+        let inputs = [("major", "1"), ("2", "2"), ("0", "1.2")];
+
+        for (group, should_be) in inputs {
+            let got = capture(&pattern, "version 1.2", Some(&Text::from(group))).unwrap();
+            assert_eq!(got, Value::from(should_be));
+        }
+    }
+
+    #[test]
+    fn capture_every_match() {
+        let pattern = compile(r"(?P<key>\w+)=(\d+)?");
+
+        let got = capture(&pattern, "a=1, b=", None).unwrap();
+
+        let mut first = BTreeMap::new();
+        first.insert(Text::from("0"), Value::from("a=1"));
+        first.insert(Text::from("1"), Value::from("a"));
+        first.insert(Text::from("key"), Value::from("a"));
+        first.insert(Text::from("2"), Value::from("1"));
+        let mut second = BTreeMap::new();
+        second.insert(Text::from("0"), Value::from("b="));
+        second.insert(Text::from("1"), Value::from("b"));
+        second.insert(Text::from("key"), Value::from("b"));
+        assert_eq!(
+            got,
+            Value::from(vec![
+                Value::Object(first.into()),
+                Value::Object(second.into())
+            ])
+        );
+    }
+
+    #[test]
+    fn capture_errors() {
+        let pattern = compile(r"v(\d+)(-beta)?");
+        let inputs = [
+            (
+                "v1",
+                "missing",
+                "/v(\\d+)(-beta)?/ has no \"missing\" group",
+            ),
+            ("v1", "3", "/v(\\d+)(-beta)?/ has no \"3\" group"),
+            (
+                "version",
+                "1",
+                "\"version\" doesn't match /v(\\d+)(-beta)?/",
+            ),
+            ("v1", "2", "The \"2\" group didn't match anything"),
+        ];
+
+        for (value, group, should_be) in inputs {
+            let got = capture(&pattern, value, Some(&Text::from(group))).unwrap_err();
+            assert_eq!(got, EvaluationError::from(should_be));
+        }
+    }
+}
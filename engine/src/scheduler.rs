@@ -0,0 +1,329 @@
+//! Periodically re-sending [`Expression::Request`]s which have a `refresh`
+//! interval, so a workspace can be used as a monitor.
+
+use crate::{Change, ChangeTracker, Duration, Evaluate, Expression, Instant, Text};
+use std::{collections::BTreeMap, ops::ControlFlow};
+
+/// Something which can tell the time and wait, letting the [`Scheduler`] be
+/// tested without actually sleeping.
+pub trait Clock {
+    fn now(&self) -> Instant;
+    fn sleep(&self, duration: Duration);
+}
+
+/// A [`Clock`] which uses the system time.
+#[derive(Debug, Default, Copy, Clone, PartialEq, Eq)]
+pub struct SystemClock;
+
+impl Clock for SystemClock {
+    fn now(&self) -> Instant {
+        Instant::now()
+    }
+
+    fn sleep(&self, duration: Duration) {
+        if let Ok(ms) = u64::try_from(duration.as_millis()) {
+            std::thread::sleep(std::time::Duration::from_millis(ms));
+        }
+    }
+}
+
+/// Keeps track of when each request was last sent, re-sending them once
+/// their `refresh` interval has elapsed.
+///
+/// Sending a request is left to the caller, who should store the response
+/// using [`InputsExt::update_expression()`][crate::InputsExt::update_expression].
+/// Each node is its own [`Inputs::node()`][crate::Inputs::node], so only the
+/// request's input changes and salsa will only re-evaluate the nodes which
+/// depend on it.
+#[derive(Debug, Default, Clone, PartialEq)]
+pub struct Scheduler {
+    last_sent: BTreeMap<Text, Instant>,
+    tracker: ChangeTracker,
+}
+
+impl Scheduler {
+    pub fn new() -> Self {
+        Scheduler::default()
+    }
+
+    /// The names of every request which should be re-sent at `now`, in the
+    /// order they appear in the workspace.
+    ///
+    /// Requests which haven't been sent by the scheduler yet are always due.
+    pub fn due(&self, db: &dyn Evaluate, now: Instant) -> Vec<Text> {
+        db.nodes()
+            .iter()
+            .filter(|node| match refresh_interval(&node.expr) {
+                Some(interval) => match self.last_sent.get(&node.name) {
+                    Some(&last_sent) => last_sent
+                        .checked_add(interval)
+                        .is_some_and(|due| due <= now),
+                    None => true,
+                },
+                None => false,
+            })
+            .map(|node| node.name.clone())
+            .collect()
+    }
+
+    /// When the next request will be due, or `None` if no requests have a
+    /// refresh interval.
+    pub fn next_due(&self, db: &dyn Evaluate, now: Instant) -> Option<Instant> {
+        db.nodes()
+            .iter()
+            .filter_map(|node| {
+                let interval = refresh_interval(&node.expr)?;
+                match self.last_sent.get(&node.name) {
+                    Some(last_sent) => last_sent.checked_add(interval),
+                    None => Some(now),
+                }
+            })
+            .min()
+    }
+
+    /// Update [`Inputs::now()`][crate::Inputs::now], re-send every request
+    /// which is due, and return what changed since the last tick.
+    ///
+    /// The first tick reports every node, because nothing has been seen
+    /// before.
+    pub fn tick<DB>(
+        &mut self,
+        db: &mut DB,
+        now: Instant,
+        mut send: impl FnMut(&mut DB, &Text),
+    ) -> Vec<Change>
+    where
+        DB: Evaluate,
+    {
+        db.set_now(now);
+
+        for name in self.due(db, now) {
+            send(db, &name);
+            self.last_sent.insert(name, now);
+        }
+
+        // Forget about requests which have been removed or renamed
+        let nodes = db.nodes();
+        self.last_sent
+            .retain(|name, _| nodes.iter().any(|n| n.name == *name));
+
+        self.tracker.update(db)
+    }
+
+    /// Keep re-sending requests as they become due, passing the database and
+    /// the changes from each tick to `on_changes` until it breaks or no
+    /// requests have a refresh interval.
+    pub fn run<DB>(
+        &mut self,
+        db: &mut DB,
+        clock: &dyn Clock,
+        mut send: impl FnMut(&mut DB, &Text),
+        mut on_changes: impl FnMut(&mut DB, &[Change]) -> ControlFlow<()>,
+    ) where
+        DB: Evaluate,
+    {
+        loop {
+            let now = clock.now();
+            let changes = self.tick(db, now, &mut send);
+
+            if on_changes(db, &changes).is_break() {
+                return;
+            }
+
+            match self.next_due(db, now) {
+                Some(due) => {
+                    if let Some(wait) = due.duration_since(clock.now()) {
+                        if wait > Duration::ZERO {
+                            clock.sleep(wait);
+                        }
+                    }
+                }
+                None => return,
+            }
+        }
+    }
+}
+
+/// How often a node should be refreshed. Intervals which aren't positive are
+/// ignored so the scheduler can't get stuck in a busy loop.
+fn refresh_interval(expr: &Expression) -> Option<Duration> {
+    match expr {
+        Expression::Request {
+            refresh: Some(interval),
+            ..
+        } if *interval > Duration::ZERO => Some(*interval),
+        _ => None,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{test_utils::Database, EvaluationError, Inputs, InputsExt, Node, Response, Value};
+    use std::{cell::Cell, sync::Arc};
+
+    /// A clock which only moves forward when something sleeps.
+    struct FakeClock {
+        now: Cell<Instant>,
+    }
+
+    impl Clock for FakeClock {
+        fn now(&self) -> Instant {
+            self.now.get()
+        }
+
+        fn sleep(&self, duration: Duration) {
+            self.now.set(self.now.get().checked_add(duration).unwrap());
+        }
+    }
+
+    fn seconds(n: i64) -> Duration {
+        Duration::from_millis(n * 1000)
+    }
+
+    fn at(seconds_after_epoch: i64) -> Instant {
+        Instant::from_unix_millis(seconds_after_epoch * 1000).unwrap()
+    }
+
+    fn request(refresh: Option<Duration>) -> Expression {
+        Expression::Request {
+            url: "http://example.com/".into(),
+            auth: None,
+            refresh,
+            response: None,
+            error: None,
+        }
+    }
+
+    fn database() -> Database {
+        Database::with_nodes(vec![
+            Node::new("fast", request(Some(seconds(10)))),
+            Node::new("slow", request(Some(seconds(60)))),
+            Node::new("once", request(None)),
+            Node::new("fast-status", Expression::get("fast", "status")),
+            Node::new("slow-status", Expression::get("slow", "status")),
+        ])
+    }
+
+    /// Pretend to send a request, responding with the number of times it has
+    /// been sent.
+    fn send(sent: &mut BTreeMap<Text, i32>) -> impl FnMut(&mut Database, &Text) + '_ {
+        move |db, name| {
+            let count = sent.entry(name.clone()).or_default();
+            *count += 1;
+
+            let mut expr = Expression::clone(&db.expression_of(name.clone()).unwrap());
+            if let Expression::Request { response, .. } = &mut expr {
+                *response = Some(Response {
+                    url: "http://example.com/".into(),
+                    status: *count,
+                    status_text: "OK".into(),
+                    body: Value::Indeterminate,
+                    metrics: None,
+                });
+            }
+            db.update_expression(name, Arc::new(expr)).unwrap();
+        }
+    }
+
+    fn updated(changes: &[Change]) -> Vec<(&str, &Result<Value, EvaluationError>)> {
+        changes
+            .iter()
+            .filter_map(|c| match c {
+                Change::Updated { name, result, .. } => Some((&**name, result)),
+                Change::Removed { .. } => None,
+            })
+            .collect()
+    }
+
+    /// The names of the nodes salsa re-evaluated.
+    fn reevaluated(db: &Database) -> Vec<String> {
+        db.executed("eval")
+            .iter()
+            .filter_map(|key| key.split('"').nth(1).map(String::from))
+            .collect()
+    }
+
+    #[test]
+    fn only_dependents_of_due_requests_are_reevaluated() {
+        let mut db = database();
+        let mut scheduler = Scheduler::new();
+        let mut sent = BTreeMap::new();
+
+        scheduler.tick(&mut db, at(0), send(&mut sent));
+        assert_eq!(scheduler.due(&db, at(5)), Vec::<Text>::new());
+        assert_eq!(scheduler.next_due(&db, at(5)), Some(at(10)));
+        reevaluated(&db);
+
+        let changes = scheduler.tick(&mut db, at(10), send(&mut sent));
+
+        assert_eq!(sent[&Text::from("fast")], 2);
+        assert_eq!(sent[&Text::from("slow")], 1);
+        assert!(!sent.contains_key(&Text::from("once")));
+        assert_eq!(reevaluated(&db), ["fast", "fast-status"]);
+        assert_eq!(
+            updated(&changes),
+            [
+                ("fast", &db.evaluate()[0]),
+                ("fast-status", &Ok(Value::from(2)))
+            ]
+        );
+        assert_eq!(db.now(), at(10));
+    }
+
+    #[test]
+    fn run_until_stopped() {
+        let mut db = database();
+        let mut scheduler = Scheduler::new();
+        let clock = FakeClock {
+            now: Cell::new(at(0)),
+        };
+        let mut sent = BTreeMap::new();
+        let mut ticks = Vec::new();
+
+        scheduler.run(&mut db, &clock, send(&mut sent), |_, changes| {
+            ticks.push((clock.now(), changes.len()));
+            if ticks.len() < 7 {
+                ControlFlow::Continue(())
+            } else {
+                ControlFlow::Break(())
+            }
+        });
+
+        let times: Vec<_> = ticks.iter().map(|(time, _)| *time).collect();
+        assert_eq!(
+            times,
+            [at(0), at(10), at(20), at(30), at(40), at(50), at(60)]
+        );
+        assert_eq!(sent[&Text::from("fast")], 7);
+        assert_eq!(sent[&Text::from("slow")], 2);
+        assert_eq!(
+            db.evaluate()[4],
+            Ok(Value::from(2)),
+            "The slow request was re-sent after a minute"
+        );
+    }
+
+    #[test]
+    fn stop_when_nothing_needs_refreshing() {
+        let mut db = database();
+        db.set_nodes(vec![Node::new("once", request(None))].into());
+        let mut scheduler = Scheduler::new();
+        let clock = FakeClock {
+            now: Cell::new(at(0)),
+        };
+        let mut ticks = 0;
+
+        scheduler.run(
+            &mut db,
+            &clock,
+            |_, _| unreachable!(),
+            |_, _| {
+                ticks += 1;
+                ControlFlow::Continue(())
+            },
+        );
+
+        assert_eq!(ticks, 1);
+    }
+}
@@ -0,0 +1,315 @@
+//! Compiling JSON Schemas and validating values against them.
+
+use crate::{
+    Evaluate, EvaluationError, Expression, Inputs, Json, Node, SchemaSource, Secret, Text, Value,
+    REDACTED,
+};
+use jsonschema::Validator;
+use std::{
+    collections::{BTreeMap, BTreeSet},
+    fmt,
+    path::Path,
+    sync::Arc,
+};
+
+/// A compiled JSON Schema.
+///
+/// Like [`Pattern`][crate::Pattern], schemas are compiled by a query so nodes
+/// using the same schema share a single validator.
+#[derive(Clone)]
+pub struct Schema {
+    source: Json,
+    validator: Arc<Validator>,
+}
+
+impl Schema {
+    pub fn as_json(&self) -> &serde_json::Value {
+        &self.source
+    }
+}
+
+impl fmt::Debug for Schema {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_tuple("Schema").field(&*self.source).finish()
+    }
+}
+
+impl PartialEq for Schema {
+    fn eq(&self, other: &Schema) -> bool {
+        self.source == other.source
+    }
+}
+
+impl Eq for Schema {}
+
+/// Read the JSON Schema files used by [`Expression::ValidateSchema`]
+/// expressions, resolving relative paths against `base_dir`.
+///
+/// This includes schemas used inside user-defined functions and lambdas.
+/// Files which can't be read or parsed are recorded as errors, and are
+/// reported by the nodes that use them.
+pub fn load_files(db: &mut dyn Inputs, base_dir: &Path) {
+    let mut paths = BTreeSet::new();
+    schema_paths(&db.nodes(), &mut paths);
+    for function in db.functions().values() {
+        schema_paths(&function.body, &mut paths);
+    }
+
+    let schemas: BTreeMap<_, _> = paths
+        .into_iter()
+        .map(|path| {
+            let schema = read_file(&base_dir.join(&*path));
+            (path, schema)
+        })
+        .collect();
+
+    // Setting an input invalidates everything using it, even if nothing
+    // changed
+    if schemas != db.schema_files() {
+        db.set_schema_files(schemas);
+    }
+}
+
+fn schema_paths(body: &[Node], paths: &mut BTreeSet<Text>) {
+    for node in body {
+        match &*node.expr {
+            Expression::ValidateSchema {
+                schema: SchemaSource::File { path },
+                ..
+            } => {
+                paths.insert(path.clone());
+            }
+            Expression::Collection {
+                lambda: Some(lambda),
+                ..
+            } => schema_paths(&lambda.body, paths),
+            _ => {}
+        }
+    }
+}
+
+fn read_file(path: &Path) -> Result<Json, EvaluationError> {
+    let contents = std::fs::read_to_string(path)
+        .map_err(|e| format!("Unable to read \"{}\": {}", path.display(), e))?;
+
+    serde_json::from_str::<serde_json::Value>(&contents)
+        .map(Json::from)
+        .map_err(|e| format!("Unable to parse \"{}\": {}", path.display(), e).into())
+}
+
+pub(crate) fn schema(db: &dyn Evaluate, source: SchemaSource) -> Result<Schema, EvaluationError> {
+    let json = match source {
+        SchemaSource::Inline { schema } => schema,
+        SchemaSource::File { path } => match db.schema_files().get(&path) {
+            Some(loaded) => loaded.clone()?,
+            None => return Err(format!("The \"{}\" schema hasn't been loaded", path).into()),
+        },
+    };
+
+    let validator =
+        jsonschema::validator_for(&json).map_err(|e| format!("Invalid JSON Schema: {}", e))?;
+
+    Ok(Schema {
+        source: json,
+        validator: Arc::new(validator),
+    })
+}
+
+/// Check `value` against a schema, returning an object containing whether it
+/// is `valid` and the `path` and `message` for each violation.
+pub(crate) fn validate(schema: &Schema, value: &Value) -> Value {
+    let instance = to_json(value);
+    let mut secrets = Vec::new();
+    find_secrets(value, &mut secrets);
+
+    let violations: Vec<Value> = schema
+        .validator
+        .iter_errors(&instance)
+        .map(|error| {
+            let mut violation = BTreeMap::new();
+            violation.insert(
+                Text::from("path"),
+                Value::from(error.instance_path.as_str()),
+            );
+            violation.insert(
+                Text::from("message"),
+                Value::from(redact(error.to_string(), &secrets)),
+            );
+            Value::Object(violation.into())
+        })
+        .collect();
+
+    let mut result = BTreeMap::new();
+    result.insert(Text::from("valid"), Value::from(violations.is_empty()));
+    result.insert(Text::from("violations"), Value::from(violations));
+
+    Value::Object(result.into())
+}
+
+pub(crate) fn is_valid(schema: &Schema, value: &Value) -> bool {
+    schema.validator.is_valid(&to_json(value))
+}
+
+/// Convert a [`Value`] to plain JSON.
+///
+/// Secrets are validated using their real values, so callers must make sure
+/// they don't end up in any messages (see [`redact()`]).
+fn to_json(value: &Value) -> serde_json::Value {
+    match value {
+        Value::Number(n) => serde_json::Value::from(*n),
+        Value::String(s) => serde_json::Value::from(&**s),
+        Value::Boolean(b) => serde_json::Value::from(*b),
+        Value::Instant(instant) => serde_json::Value::from(instant.to_string()),
+        Value::Duration(duration) => serde_json::Value::from(duration.as_millis()),
+        Value::Object(obj) => obj
+            .iter()
+            .map(|(key, value)| (key.to_string(), to_json(value)))
+            .collect::<serde_json::Map<_, _>>()
+            .into(),
+        Value::Array(items) => items.iter().map(to_json).collect(),
+        Value::Secret(secret) => serde_json::Value::from(secret.expose()),
+        Value::Indeterminate => serde_json::Value::Null,
+    }
+}
+
+fn find_secrets<'a>(value: &'a Value, secrets: &mut Vec<&'a Secret>) {
+    match value {
+        Value::Secret(secret) => secrets.push(secret),
+        Value::Object(obj) => obj.values().for_each(|v| find_secrets(v, secrets)),
+        Value::Array(items) => items.iter().for_each(|v| find_secrets(v, secrets)),
+        _ => {}
+    }
+}
+
+/// Violation messages may include the value that was checked, so replace
+/// any secrets it contained.
+fn redact(mut message: String, secrets: &[&Secret]) -> String {
+    for secret in secrets.iter().filter(|s| !s.expose().is_empty()) {
+        message = message.replace(secret.expose(), REDACTED);
+    }
+
+    message
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{test_utils::Database, Function, InputsExt, Lambda, Operation};
+    use serde_json::json;
+
+    fn compile(schema: serde_json::Value) -> Schema {
+        let source: Json = schema.into();
+        let validator = jsonschema::validator_for(&source).unwrap();
+
+        Schema {
+            source,
+            validator: Arc::new(validator),
+        }
+    }
+
+    fn object(fields: Vec<(&str, Value)>) -> Value {
+        let fields: BTreeMap<Text, Value> = fields
+            .into_iter()
+            .map(|(k, v)| (Text::from(k), v))
+            .collect();
+        Value::Object(fields.into())
+    }
+
+    #[test]
+    fn report_every_violation() {
+        let schema = compile(json!({
+            "type": "object",
+            "required": ["id", "name"],
+            "properties": {
+                "id": { "type": "integer" },
+                "tags": { "type": "array", "items": { "type": "string" } },
+            },
+        }));
+        let value = object(vec![
+            ("id", Value::from("42")),
+            ("tags", Value::from(vec![Value::from("a"), Value::from(1)])),
+        ]);
+
+        let got = validate(&schema, &value);
+
+        let violation = |path: &str, message: &str| {
+            object(vec![
+                ("path", Value::from(path)),
+                ("message", Value::from(message)),
+            ])
+        };
+        let should_be = object(vec![
+            ("valid", Value::from(false)),
+            (
+                "violations",
+                Value::from(vec![
+                    violation("/id", "\"42\" is not of type \"integer\""),
+                    violation("/tags/1", "1 is not of type \"string\""),
+                    violation("", "\"name\" is a required property"),
+                ]),
+            ),
+        ]);
+        assert_eq!(got, should_be);
+    }
+
+    #[test]
+    fn secrets_are_checked_but_never_shown() {
+        let schema = compile(json!({ "type": "string", "pattern": "^sk-" }));
+
+        assert!(is_valid(&schema, &Value::Secret(Secret::new("sk-abc123"))));
+        let got = validate(&schema, &Value::Secret(Secret::new("hunter2")));
+
+        let message = match &got {
+            Value::Object(obj) => match &obj["violations"] {
+                Value::Array(violations) => violations[0].to_string(),
+                _ => unreachable!(),
+            },
+            _ => unreachable!(),
+        };
+        assert!(!message.contains("hunter2"));
+        assert!(message.contains(REDACTED));
+    }
+
+    #[test]
+    fn load_every_schema_file_in_use() {
+        let directory = std::env::temp_dir().join(format!("laskea-schemas-{}", std::process::id()));
+        std::fs::create_dir_all(&directory).unwrap();
+        for name in ["node.json", "function.json", "lambda.json"] {
+            std::fs::write(directory.join(name), r#"{ "type": "integer" }"#).unwrap();
+        }
+        let validate = |path: &str| Expression::validate_schema("x", SchemaSource::file(path));
+        let mut db = Database::with_nodes(vec![
+            Node::new("valid", validate("node.json")),
+            Node::new("missing", validate("missing.json")),
+            Node::new(
+                "each",
+                Expression::collection(
+                    Operation::Map,
+                    "items",
+                    Lambda::new("x", vec![Node::new("valid", validate("lambda.json"))]),
+                ),
+            ),
+        ]);
+        db.set_function(
+            "check".into(),
+            Function::new(
+                Vec::new(),
+                vec![Node::new("valid", validate("function.json"))],
+            ),
+        )
+        .unwrap();
+
+        load_files(&mut db, &directory);
+
+        std::fs::remove_dir_all(&directory).unwrap();
+        let schemas = db.schema_files();
+        let paths: Vec<_> = schemas.keys().map(|p| &**p).collect();
+        assert_eq!(
+            paths,
+            ["function.json", "lambda.json", "missing.json", "node.json"]
+        );
+        assert!(schemas[&Text::from("lambda.json")].is_ok());
+        let err = schemas[&Text::from("missing.json")].clone().unwrap_err();
+        assert!(err.message().starts_with("Unable to read"));
+    }
+}
@@ -0,0 +1,246 @@
+use crate::Text;
+use serde::{Deserialize, Deserializer, Serialize, Serializer};
+use sha2::{Digest, Sha256};
+use std::{
+    collections::BTreeMap,
+    fmt::{self, Debug, Display, Formatter, Write},
+    path::Path,
+};
+
+/// What is shown instead of a [`Secret`]'s contents.
+pub const REDACTED: &str = "[REDACTED]";
+
+/// A sensitive string (e.g. an auth token) which is redacted whenever it is
+/// printed or serialized.
+///
+/// Use [`Secret::expose()`] when the real value is needed.
+#[derive(Clone, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "typescript", derive(ts_rs::TS), ts(type = "string"))]
+pub struct Secret(Text);
+
+impl Secret {
+    pub fn new(value: impl Into<Text>) -> Self {
+        Secret(value.into())
+    }
+
+    pub fn expose(&self) -> &str {
+        &self.0
+    }
+
+    /// A SHA-256 hash of the secret, which can be stored and compared
+    /// without revealing its value.
+    ///
+    /// This is how secrets are kept in
+    /// [`Inputs::previous_results()`][crate::Inputs::previous_results].
+    pub fn fingerprint(&self) -> Secret {
+        let digest = Sha256::digest(self.0.as_bytes());
+        let mut hex = String::from("sha256:");
+        for byte in digest {
+            let _ = write!(hex, "{:02x}", byte);
+        }
+
+        Secret::new(hex)
+    }
+}
+
+impl Debug for Secret {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        write!(f, "Secret({})", REDACTED)
+    }
+}
+
+impl Display for Secret {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        f.write_str(REDACTED)
+    }
+}
+
+impl Serialize for Secret {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.serialize_str(REDACTED)
+    }
+}
+
+impl<'de> Deserialize<'de> for Secret {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        Text::deserialize(deserializer).map(Secret)
+    }
+}
+
+/// Replace every [`Secret`] with [`REDACTED`], for values which are about to
+/// be displayed, logged, or sent somewhere else.
+pub trait Redact {
+    fn redacted(&self) -> Self;
+}
+
+impl Redact for Secret {
+    fn redacted(&self) -> Self {
+        Secret::new(REDACTED)
+    }
+}
+
+impl<T: Redact, E: Clone> Redact for Result<T, E> {
+    fn redacted(&self) -> Self {
+        self.as_ref().map(T::redacted).map_err(E::clone)
+    }
+}
+
+/// Somewhere secrets can be loaded from.
+pub trait SecretSource {
+    fn secret(&self, name: &str) -> Option<Secret>;
+}
+
+/// Try each source in turn, using the first one which has the secret.
+impl SecretSource for &[&dyn SecretSource] {
+    fn secret(&self, name: &str) -> Option<Secret> {
+        self.iter().find_map(|source| source.secret(name))
+    }
+}
+
+/// Read secrets from environment variables, where a secret called
+/// `api_token` is read from `$LASKEA_SECRET_API_TOKEN`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct EnvironmentVariables {
+    prefix: String,
+}
+
+impl EnvironmentVariables {
+    pub const DEFAULT_PREFIX: &'static str = "LASKEA_SECRET_";
+
+    pub fn new() -> Self {
+        EnvironmentVariables::with_prefix(EnvironmentVariables::DEFAULT_PREFIX)
+    }
+
+    pub fn with_prefix(prefix: impl Into<String>) -> Self {
+        EnvironmentVariables {
+            prefix: prefix.into(),
+        }
+    }
+
+    fn variable_name(&self, name: &str) -> String {
+        let name: String = name
+            .chars()
+            .map(|c| match c {
+                'a'..='z' | 'A'..='Z' | '0'..='9' => c.to_ascii_uppercase(),
+                _ => '_',
+            })
+            .collect();
+        format!("{}{}", self.prefix, name)
+    }
+}
+
+impl Default for EnvironmentVariables {
+    fn default() -> Self {
+        EnvironmentVariables::new()
+    }
+}
+
+impl SecretSource for EnvironmentVariables {
+    fn secret(&self, name: &str) -> Option<Secret> {
+        std::env::var(self.variable_name(name))
+            .ok()
+            .map(Secret::new)
+    }
+}
+
+/// Secrets read from a local file containing `name=value` lines.
+///
+/// Blank lines and lines starting with `#` are ignored.
+#[derive(Debug, Default, Clone, PartialEq, Eq)]
+pub struct SecretsFile {
+    secrets: BTreeMap<Text, Secret>,
+}
+
+impl SecretsFile {
+    pub fn load(path: impl AsRef<Path>) -> std::io::Result<Self> {
+        std::fs::read_to_string(path).map(|contents| SecretsFile::parse(&contents))
+    }
+
+    pub fn parse(contents: &str) -> Self {
+        let secrets = contents
+            .lines()
+            .map(str::trim)
+            .filter(|line| !line.is_empty() && !line.starts_with('#'))
+            .filter_map(|line| line.split_once('='))
+            .map(|(name, value)| (Text::from(name.trim()), Secret::new(value.trim())))
+            .collect();
+
+        SecretsFile { secrets }
+    }
+}
+
+impl SecretSource for SecretsFile {
+    fn secret(&self, name: &str) -> Option<Secret> {
+        self.secrets.get(name).cloned()
+    }
+}
+
+/// An in-memory stand-in for the operating system's keyring.
+#[derive(Debug, Default, Clone, PartialEq, Eq)]
+pub struct Keyring {
+    secrets: BTreeMap<Text, Secret>,
+}
+
+impl Keyring {
+    pub fn new() -> Self {
+        Keyring::default()
+    }
+
+    pub fn insert(&mut self, name: impl Into<Text>, secret: Secret) {
+        self.secrets.insert(name.into(), secret);
+    }
+
+    pub fn remove(&mut self, name: &str) -> Option<Secret> {
+        self.secrets.remove(name)
+    }
+}
+
+impl SecretSource for Keyring {
+    fn secret(&self, name: &str) -> Option<Secret> {
+        self.secrets.get(name).cloned()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn secrets_are_redacted() {
+        let secret = Secret::new("hunter2");
+
+        assert_eq!(secret.to_string(), REDACTED);
+        assert_eq!(format!("{:?}", secret), "Secret([REDACTED])");
+        assert_eq!(serde_json::to_string(&secret).unwrap(), "\"[REDACTED]\"");
+        assert_eq!(secret.expose(), "hunter2");
+    }
+
+    #[test]
+    fn parse_a_secrets_file() {
+        let file = SecretsFile::parse("# API credentials\napi_token = abc=123\n\nnot a secret\n");
+
+        assert_eq!(file.secret("api_token"), Some(Secret::new("abc=123")));
+        assert_eq!(file.secret("not a secret"), None);
+    }
+
+    #[test]
+    fn sources_are_checked_in_order() {
+        let mut first = Keyring::new();
+        first.insert("token", Secret::new("first"));
+        let mut second = Keyring::new();
+        second.insert("token", Secret::new("second"));
+        second.insert("password", Secret::new("hunter2"));
+        let sources: &[&dyn SecretSource] = &[&first, &second];
+
+        assert_eq!(sources.secret("token"), Some(Secret::new("first")));
+        assert_eq!(sources.secret("password"), Some(Secret::new("hunter2")));
+        assert_eq!(sources.secret("missing"), None);
+    }
+
+    #[test]
+    fn environment_variable_names() {
+        let env = EnvironmentVariables::new();
+
+        assert_eq!(env.variable_name("api-token"), "LASKEA_SECRET_API_TOKEN");
+    }
+}
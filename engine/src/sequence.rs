@@ -7,6 +7,7 @@ use std::{
 #[derive(
     Debug, Clone, PartialEq, Eq, PartialOrd, Ord, Hash, serde::Serialize, serde::Deserialize,
 )]
+#[cfg_attr(feature = "typescript", derive(ts_rs::TS))]
 pub struct Sequence<T>(Arc<[T]>);
 
 impl<T> Sequence<T> {
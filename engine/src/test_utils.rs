@@ -0,0 +1,53 @@
+//! Helpers shared by the engine's tests.
+
+use crate::{init_inputs, EvaluateStorage, InputsExt, InputsStorage, Node};
+use std::sync::Mutex;
+
+/// A database where every input has been initialised, which keeps track of
+/// the queries salsa executes.
+#[salsa::database(InputsStorage, EvaluateStorage)]
+pub(crate) struct Database {
+    storage: salsa::Storage<Self>,
+    executed: Mutex<Vec<String>>,
+}
+
+impl Database {
+    pub(crate) fn with_nodes(nodes: impl IntoIterator<Item = Node>) -> Self {
+        let mut db = Database::default();
+        db.set_nodes(nodes.into_iter().collect());
+        db
+    }
+
+    /// Every time the `query` query (e.g. `"eval"`) was executed since the
+    /// last call to this method, in the form `query(key)`.
+    pub(crate) fn executed(&self, query: &str) -> Vec<String> {
+        let prefix = format!("{}(", query);
+
+        self.executed
+            .lock()
+            .unwrap()
+            .drain(..)
+            .filter(|key| key.starts_with(&prefix))
+            .collect()
+    }
+}
+
+impl Default for Database {
+    fn default() -> Self {
+        let mut db = Database {
+            storage: salsa::Storage::default(),
+            executed: Mutex::new(Vec::new()),
+        };
+        init_inputs(&mut db);
+        db
+    }
+}
+
+impl salsa::Database for Database {
+    fn salsa_event(&self, event: salsa::Event) {
+        if let salsa::EventKind::WillExecute { database_key } = event.kind {
+            let key = format!("{:?}", database_key.debug(self));
+            self.executed.lock().unwrap().push(key);
+        }
+    }
+}
@@ -0,0 +1,391 @@
+use crate::{Evaluate, Expression, Text, Value};
+use std::{collections::BTreeSet, fmt::Write};
+
+/// The results from running every [`Expression::Assert`] node in a
+/// workspace.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct TestReport {
+    pub cases: Vec<TestCase>,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct TestCase {
+    pub name: Text,
+    pub outcome: Outcome,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Outcome {
+    Passed,
+    /// The assertion's target was `false`.
+    Failed {
+        message: Text,
+    },
+    /// The assertion couldn't be checked, either because something it
+    /// depends on failed or because its target is missing, isn't a boolean,
+    /// or is part of a cycle.
+    Errored {
+        message: Text,
+    },
+    /// The assertion depends on something which hasn't been determined yet
+    /// (e.g. a request that hasn't been sent).
+    Indeterminate,
+}
+
+/// Evaluate the workspace and check each of its assertions.
+pub fn run_tests(db: &dyn Evaluate) -> TestReport {
+    let mut cases = Vec::new();
+    let mut seen = BTreeSet::new();
+
+    for (node, result) in db.nodes().iter().zip(db.evaluate().iter()) {
+        let target = match &*node.expr {
+            Expression::Assert { target, .. } if seen.insert(&node.name) => target,
+            _ => continue,
+        };
+
+        let outcome = match result {
+            Ok(Value::Indeterminate) => Outcome::Indeterminate,
+            Ok(_) => Outcome::Passed,
+            Err(e) if is_false(db, target) => Outcome::Failed {
+                message: e.message().clone(),
+            },
+            Err(e) => Outcome::Errored {
+                message: e.to_string().into(),
+            },
+        };
+
+        cases.push(TestCase {
+            name: node.name.clone(),
+            outcome,
+        });
+    }
+
+    TestReport { cases }
+}
+
+/// Did an assertion fail because its target was `false`, rather than because
+/// the target couldn't be evaluated or wasn't a boolean?
+pub(crate) fn is_false(db: &dyn Evaluate, target: &Text) -> bool {
+    let result = db
+        .expression_of(target.clone())
+        .map(|expr| db.eval(target.clone(), expr));
+
+    result == Some(Ok(Value::Boolean(false)))
+}
+
+impl TestReport {
+    pub fn passed(&self) -> usize {
+        self.count(|o| matches!(o, Outcome::Passed))
+    }
+
+    pub fn failed(&self) -> usize {
+        self.count(|o| matches!(o, Outcome::Failed { .. }))
+    }
+
+    pub fn errored(&self) -> usize {
+        self.count(|o| matches!(o, Outcome::Errored { .. }))
+    }
+
+    pub fn indeterminate(&self) -> usize {
+        self.count(|o| matches!(o, Outcome::Indeterminate))
+    }
+
+    /// Did every assertion that could be checked pass?
+    pub fn is_success(&self) -> bool {
+        self.failed() == 0 && self.errored() == 0
+    }
+
+    fn count(&self, predicate: impl Fn(&Outcome) -> bool) -> usize {
+        self.cases.iter().filter(|c| predicate(&c.outcome)).count()
+    }
+
+    /// Render the report as JUnit XML, treating indeterminate assertions as
+    /// skipped.
+    pub fn to_junit(&self, suite: &str) -> String {
+        let counts = format!(
+            "tests=\"{}\" failures=\"{}\" errors=\"{}\" skipped=\"{}\"",
+            self.cases.len(),
+            self.failed(),
+            self.errored(),
+            self.indeterminate()
+        );
+
+        let mut xml = String::from("<?xml version=\"1.0\" encoding=\"UTF-8\"?>\n");
+        let _ = writeln!(xml, "<testsuites {}>", counts);
+        let _ = writeln!(
+            xml,
+            "  <testsuite name=\"{}\" {}>",
+            xml_escape(suite),
+            counts
+        );
+
+        for case in &self.cases {
+            let _ = write!(
+                xml,
+                "    <testcase name=\"{}\" classname=\"{}\"",
+                xml_escape(&case.name),
+                xml_escape(suite)
+            );
+
+            let (tag, message) = match &case.outcome {
+                Outcome::Passed => {
+                    xml.push_str("/>\n");
+                    continue;
+                }
+                Outcome::Failed { message } => ("failure", &**message),
+                Outcome::Errored { message } => ("error", &**message),
+                Outcome::Indeterminate => ("skipped", "The result is indeterminate"),
+            };
+
+            let _ = writeln!(
+                xml,
+                ">\n      <{} message=\"{}\"/>\n    </testcase>",
+                tag,
+                xml_escape(message)
+            );
+        }
+
+        xml.push_str("  </testsuite>\n</testsuites>\n");
+        xml
+    }
+
+    /// Render the report using the Test Anything Protocol, treating
+    /// indeterminate assertions as skipped.
+    pub fn to_tap(&self) -> String {
+        let mut tap = String::from("TAP version 13\n");
+        let _ = writeln!(tap, "1..{}", self.cases.len());
+
+        for (i, case) in self.cases.iter().enumerate() {
+            let number = i + 1;
+            let name = case.name.replace('#', "\\#");
+
+            match &case.outcome {
+                Outcome::Passed => {
+                    let _ = writeln!(tap, "ok {} - {}", number, name);
+                }
+                Outcome::Indeterminate => {
+                    let _ = writeln!(tap, "ok {} - {} # SKIP indeterminate", number, name);
+                }
+                Outcome::Failed { message } | Outcome::Errored { message } => {
+                    let _ = writeln!(tap, "not ok {} - {}", number, name);
+                    let _ = writeln!(tap, "  ---\n  message: {:?}\n  ...", &**message);
+                }
+            }
+        }
+
+        tap
+    }
+}
+
+fn xml_escape(s: &str) -> String {
+    s.replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+        .replace('"', "&quot;")
+        .replace('\'', "&apos;")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{test_utils::Database, Node};
+
+    fn database() -> Database {
+        Database::with_nodes(vec![
+            Node::new("greeting", Expression::string("Hello")),
+            Node::new("is-hello", Expression::equals("greeting", "Hello")),
+            Node::new("is-goodbye", Expression::equals("greeting", "Goodbye")),
+            Node::new("pending", Expression::get("request", "status")),
+            Node::new(
+                "request",
+                Expression::Request {
+                    url: "http://example.com/".into(),
+                    auth: None,
+                    refresh: None,
+                    response: None,
+                    error: None,
+                },
+            ),
+            Node::new("is-ok", Expression::equals("pending", 200)),
+            Node::new("check-hello", Expression::assert("is-hello", "Not hello")),
+            Node::new(
+                "check-goodbye",
+                Expression::assert("is-goodbye", "Not goodbye"),
+            ),
+            Node::new("check-status", Expression::assert("is-ok", "Bad status")),
+            Node::new("check-missing", Expression::assert("missing", "Missing")),
+            Node::new("check-checks", Expression::assert("check-missing", "?")),
+        ])
+    }
+
+    #[test]
+    fn assertions_are_test_cases() {
+        let db = database();
+
+        let got = run_tests(&db);
+
+        let outcomes: Vec<_> = got
+            .cases
+            .iter()
+            .map(|c| (&*c.name, c.outcome.clone()))
+            .collect();
+        assert_eq!(
+            outcomes,
+            vec![
+                ("check-hello", Outcome::Passed),
+                (
+                    "check-goodbye",
+                    Outcome::Failed {
+                        message: "Not goodbye".into()
+                    }
+                ),
+                ("check-status", Outcome::Indeterminate),
+                (
+                    "check-missing",
+                    Outcome::Errored {
+                        message: "No \"missing\" input found".into()
+                    }
+                ),
+                (
+                    "check-checks",
+                    Outcome::Errored {
+                        message:
+                            "blocked by failing node `check-missing`: No \"missing\" input found"
+                                .into()
+                    }
+                ),
+            ]
+        );
+        assert_eq!(got.passed(), 1);
+        assert_eq!(got.failed(), 1);
+        assert_eq!(got.errored(), 2);
+        assert_eq!(got.indeterminate(), 1);
+        assert!(!got.is_success());
+    }
+
+    /// The outcome of the last node, which should be an assertion.
+    fn outcome(nodes: Vec<Node>) -> Outcome {
+        let db = Database::with_nodes(nodes);
+        run_tests(&db).cases.pop().unwrap().outcome
+    }
+
+    #[test]
+    fn asserting_a_missing_node_is_an_error() {
+        let got = outcome(vec![Node::new("check", Expression::assert("missing", "?"))]);
+
+        assert_eq!(
+            got,
+            Outcome::Errored {
+                message: "No \"missing\" input found".into()
+            }
+        );
+    }
+
+    #[test]
+    fn asserting_a_non_boolean_is_an_error() {
+        let got = outcome(vec![
+            Node::new("greeting", Expression::string("Hello")),
+            Node::new("check", Expression::assert("greeting", "?")),
+        ]);
+
+        assert_eq!(
+            got,
+            Outcome::Errored {
+                message: "\"greeting\" is not a boolean".into()
+            }
+        );
+    }
+
+    #[test]
+    fn assertions_in_a_cycle_are_errors() {
+        let got = outcome(vec![
+            Node::new("is-ok", Expression::equals("check", true)),
+            Node::new("check", Expression::assert("is-ok", "?")),
+        ]);
+
+        assert_eq!(
+            got,
+            Outcome::Errored {
+                message: "Cycle detected: check → is-ok → check".into()
+            }
+        );
+    }
+
+    #[test]
+    fn junit_report() {
+        let report = TestReport {
+            cases: vec![
+                TestCase {
+                    name: "passes".into(),
+                    outcome: Outcome::Passed,
+                },
+                TestCase {
+                    name: "fails".into(),
+                    outcome: Outcome::Failed {
+                        message: "Expected <200>".into(),
+                    },
+                },
+                TestCase {
+                    name: "pending".into(),
+                    outcome: Outcome::Indeterminate,
+                },
+            ],
+        };
+
+        let got = report.to_junit("health-checks");
+
+        assert_eq!(
+            got,
+            r#"<?xml version="1.0" encoding="UTF-8"?>
+<testsuites tests="3" failures="1" errors="0" skipped="1">
+  <testsuite name="health-checks" tests="3" failures="1" errors="0" skipped="1">
+    <testcase name="passes" classname="health-checks"/>
+    <testcase name="fails" classname="health-checks">
+      <failure message="Expected &lt;200&gt;"/>
+    </testcase>
+    <testcase name="pending" classname="health-checks">
+      <skipped message="The result is indeterminate"/>
+    </testcase>
+  </testsuite>
+</testsuites>
+"#
+        );
+    }
+
+    #[test]
+    fn tap_report() {
+        let report = TestReport {
+            cases: vec![
+                TestCase {
+                    name: "passes".into(),
+                    outcome: Outcome::Passed,
+                },
+                TestCase {
+                    name: "fails".into(),
+                    outcome: Outcome::Errored {
+                        message: "Upstream \"failure\"".into(),
+                    },
+                },
+                TestCase {
+                    name: "pending #1".into(),
+                    outcome: Outcome::Indeterminate,
+                },
+            ],
+        };
+
+        let got = report.to_tap();
+
+        assert_eq!(
+            got,
+            r#"TAP version 13
+1..3
+ok 1 - passes
+not ok 2 - fails
+  ---
+  message: "Upstream \"failure\""
+  ...
+ok 3 - pending \#1 # SKIP indeterminate
+"#
+        );
+    }
+}
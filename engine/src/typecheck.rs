@@ -0,0 +1,540 @@
+//! Static type checking, so mistakes can be reported before any requests are
+//! sent.
+//!
+//! Type inference mirrors [`crate::evaluate`], except it works with the
+//! [`Type`] of each node instead of its [`Value`]. Anything which can't be
+//! known ahead of time (e.g. the body of a request which hasn't been sent) is
+//! [`Type::Unknown`], and unknown types are compatible with everything, so
+//! only definite mistakes are reported.
+
+use crate::{
+    functions, Argument, Evaluate, EvaluationError, Expression, Lambda, Node, Operation, Sequence,
+    Text, Type, Value,
+};
+use std::collections::BTreeMap;
+
+pub(crate) fn type_of(db: &dyn Evaluate, name: Text) -> Result<Type, EvaluationError> {
+    let expression = db
+        .expression_of(name.clone())
+        .ok_or_else(|| format!("No \"{}\" input found", name))?;
+
+    if db.reference_cycle(name.clone()).is_some() {
+        // Evaluation will report the cycle for us
+        return Ok(Type::Unknown);
+    }
+
+    infer(db, &NodeTypes(db), &expression)
+}
+
+pub(crate) fn return_type(db: &dyn Evaluate, function: Text) -> Result<Type, EvaluationError> {
+    if let Some(cycle) = db.function_cycle(function.clone()) {
+        let msg = format!("Recursion detected: {}", cycle.join(" → "));
+        return Err(msg.into());
+    }
+
+    let definition = db
+        .function(function.clone())
+        .ok_or_else(|| format!("No \"{}\" function found", function))?;
+
+    let parameters = ParameterTypes {
+        function: &function,
+    };
+    let mut locals = LocalTypes::new(&parameters);
+    for parameter in definition.parameters.iter() {
+        locals.bind(parameter.name.clone(), Ok(parameter.ty.clone()));
+    }
+
+    match locals.infer_body(db, &definition.body) {
+        Some(Ok(ty)) => Ok(ty),
+        Some(Err(e)) => Err(format!("In {}(): {}", function, e.message()).into()),
+        None => Err(format!("{}() has no body", function).into()),
+    }
+}
+
+/// Somewhere the types of the names used by an expression can be looked up.
+trait TypeScope {
+    fn lookup(&self, name: &Text) -> Result<Type, EvaluationError>;
+}
+
+/// The types of the nodes in the workspace.
+struct NodeTypes<'db>(&'db dyn Evaluate);
+
+impl TypeScope for NodeTypes<'_> {
+    fn lookup(&self, name: &Text) -> Result<Type, EvaluationError> {
+        self.0
+            .type_of(name.clone())
+            .map_err(|e| EvaluationError::upstream(name.clone(), e))
+    }
+}
+
+/// The scope a function's body is checked in, which only contains the
+/// function's parameters.
+struct ParameterTypes<'a> {
+    function: &'a Text,
+}
+
+impl TypeScope for ParameterTypes<'_> {
+    fn lookup(&self, name: &Text) -> Result<Type, EvaluationError> {
+        Err(format!(
+            "{}() has no \"{}\" parameter or local node",
+            self.function, name
+        )
+        .into())
+    }
+}
+
+/// The types of names bound inside a lambda or function body.
+struct LocalTypes<'a> {
+    parent: &'a dyn TypeScope,
+    types: BTreeMap<Text, Result<Type, EvaluationError>>,
+}
+
+impl<'a> LocalTypes<'a> {
+    fn new(parent: &'a dyn TypeScope) -> Self {
+        LocalTypes {
+            parent,
+            types: BTreeMap::new(),
+        }
+    }
+
+    fn bind(&mut self, name: Text, ty: Result<Type, EvaluationError>) {
+        self.types.insert(name, ty);
+    }
+
+    fn infer_body(
+        &mut self,
+        db: &dyn Evaluate,
+        body: &Sequence<Node>,
+    ) -> Option<Result<Type, EvaluationError>> {
+        let mut result = None;
+
+        for node in body.iter() {
+            let ty = infer(db, self, &node.expr);
+            self.bind(node.name.clone(), ty.clone());
+            result = Some(ty);
+        }
+
+        result
+    }
+}
+
+impl TypeScope for LocalTypes<'_> {
+    fn lookup(&self, name: &Text) -> Result<Type, EvaluationError> {
+        match self.types.get(name) {
+            Some(ty) => ty.clone(),
+            None => self.parent.lookup(name),
+        }
+    }
+}
+
+/// The type of a [`Response`][crate::Response] after it has been converted
+/// to a [`Value`], when we don't know what the body will contain.
+///
+/// This assumes the host will record the response's
+/// [`Metrics`][crate::Metrics]. The `dns_ms` and `connect_ms` timings aren't
+/// included because they are often unavailable.
+fn response_type() -> Type {
+    Type::object_with([
+        (Text::from("url"), Type::String),
+        (Text::from("status"), Type::Number),
+        (Text::from("status_text"), Type::String),
+        (Text::from("body"), Type::Unknown),
+        (Text::from("latency_ms"), Type::Number),
+        (Text::from("ttfb_ms"), Type::Number),
+        (Text::from("size_bytes"), Type::Number),
+        (Text::from("redirects"), Type::array(Type::String)),
+        (Text::from("timestamp"), Type::Instant),
+    ])
+}
+
+fn infer(
+    db: &dyn Evaluate,
+    scope: &dyn TypeScope,
+    expr: &Expression,
+) -> Result<Type, EvaluationError> {
+    match expr {
+        Expression::StringConstant { .. } | Expression::Variable { .. } => Ok(Type::String),
+        Expression::Request {
+            response: Some(response),
+            ..
+        } => Ok(Type::of(&Value::from(response.clone()))),
+        Expression::Request { .. } => Ok(response_type()),
+        // Previous values aren't checked against the target's type because
+        // a node is allowed to refer to its own previous value
+        Expression::Secret { .. }
+        | Expression::OAuth2Token { .. }
+        | Expression::Previous { .. } => Ok(Type::Unknown),
+        Expression::Changed { target } => {
+            scope.lookup(target)?;
+            Ok(Type::Boolean)
+        }
+        Expression::Equals { target, value } => {
+            let target_type = scope.lookup(target)?;
+            let value_type = Type::of(value);
+
+            if target_type.accepts_type(&value_type) || value_type.accepts_type(&target_type) {
+                Ok(Type::Boolean)
+            } else {
+                let msg = format!(
+                    "\"{}\" is {}, so comparing it with {} will always be false",
+                    target, target_type, value
+                );
+                Err(msg.into())
+            }
+        }
+        Expression::GetProperty { target, field } => match scope.lookup(target)? {
+            Type::Object {
+                fields: Some(fields),
+            } => fields
+                .get(field)
+                .cloned()
+                .ok_or_else(|| format!("\"{}\" has no \"{}\" field", target, field).into()),
+            Type::Object { fields: None } | Type::Unknown => Ok(Type::Unknown),
+            _ => Err(format!("\"{}\" is not an object", target).into()),
+        },
+        Expression::Assert { target, .. } => {
+            expect(scope, target, Type::Boolean, "a boolean")?;
+            Ok(Type::Boolean)
+        }
+        Expression::Matches { target, .. } => {
+            expect(scope, target, Type::String, "a string")?;
+            Ok(Type::Boolean)
+        }
+        Expression::Capture { target, group, .. } => {
+            expect(scope, target, Type::String, "a string")?;
+            match group {
+                Some(_) => Ok(Type::String),
+                None => Ok(Type::array(Type::object())),
+            }
+        }
+        Expression::Call {
+            function,
+            arguments,
+        } => {
+            let mut types = Vec::new();
+            for argument in arguments.iter() {
+                let ty = match argument {
+                    Argument::Constant { value } => Type::of(value),
+                    Argument::Node { name } => scope.lookup(name)?,
+                };
+                types.push(ty);
+            }
+
+            if functions::is_builtin(function) {
+                functions::builtin_type(function, &types)
+            } else {
+                call_type(db, function, &types)
+            }
+        }
+        Expression::Collection {
+            operation,
+            target,
+            lambda,
+        } => collection_type(db, scope, *operation, target, lambda.as_ref()),
+        Expression::ValidateSchema { target, .. } => {
+            scope.lookup(target)?;
+            let violation = Type::object_with([
+                (Text::from("path"), Type::String),
+                (Text::from("message"), Type::String),
+            ]);
+            Ok(Type::object_with([
+                (Text::from("valid"), Type::Boolean),
+                (Text::from("violations"), Type::array(violation)),
+            ]))
+        }
+    }
+}
+
+/// Make sure `target` has the `expected` type.
+fn expect(
+    scope: &dyn TypeScope,
+    target: &Text,
+    expected: Type,
+    description: &str,
+) -> Result<(), EvaluationError> {
+    let ty = scope.lookup(target)?;
+
+    if expected.accepts_type(&ty) {
+        Ok(())
+    } else {
+        Err(format!("\"{}\" is not {}", target, description).into())
+    }
+}
+
+/// Check a call to a user-defined function.
+fn call_type(db: &dyn Evaluate, function: &Text, args: &[Type]) -> Result<Type, EvaluationError> {
+    let definition = db
+        .function(function.clone())
+        .ok_or_else(|| format!("No \"{}\" function found", function))?;
+
+    let expected = definition.parameters.len();
+    if args.len() != expected {
+        let plural = if expected == 1 { "" } else { "s" };
+        let msg = format!(
+            "{}() expects {} argument{}, found {}",
+            function,
+            expected,
+            plural,
+            args.len()
+        );
+        return Err(msg.into());
+    }
+
+    for (parameter, actual) in definition.parameters.iter().zip(args) {
+        if !parameter.ty.accepts_type(actual) {
+            let msg = format!(
+                "{}() expects {} for the \"{}\" parameter, found {}",
+                function, parameter.ty, parameter.name, actual
+            );
+            return Err(msg.into());
+        }
+    }
+
+    db.return_type(function.clone())
+}
+
+fn collection_type(
+    db: &dyn Evaluate,
+    scope: &dyn TypeScope,
+    operation: Operation,
+    target: &Text,
+    lambda: Option<&Lambda>,
+) -> Result<Type, EvaluationError> {
+    let name = operation.name();
+
+    let item = match scope.lookup(target)? {
+        Type::Array { item } => *item,
+        Type::Unknown => Type::Unknown,
+        _ => return Err(format!("\"{}\" is not an array", target).into()),
+    };
+
+    let key = match lambda {
+        Some(lambda) => {
+            let mut locals = LocalTypes::new(scope);
+            locals.bind(lambda.parameter.clone(), Ok(item.clone()));
+            match locals.infer_body(db, &lambda.body) {
+                Some(Ok(ty)) => ty,
+                Some(Err(e)) => {
+                    let msg = format!("In the lambda for {}(): {}", name, e.message());
+                    return Err(msg.into());
+                }
+                None => return Err("The lambda has no body".into()),
+            }
+        }
+        None if matches!(
+            operation,
+            Operation::Map | Operation::Filter | Operation::GroupBy
+        ) =>
+        {
+            return Err(format!("{}() needs a lambda", name).into());
+        }
+        None => item.clone(),
+    };
+
+    let expect_key = |expected: Type| {
+        if expected.accepts_type(&key) {
+            Ok(())
+        } else {
+            let msg = format!("{}() expects {}, but the items are {}", name, expected, key);
+            Err(EvaluationError::from(msg))
+        }
+    };
+
+    match operation {
+        Operation::Map => Ok(Type::array(key)),
+        Operation::Filter => {
+            expect_key(Type::Boolean)?;
+            Ok(Type::array(item))
+        }
+        Operation::Any | Operation::All => {
+            expect_key(Type::Boolean)?;
+            Ok(Type::Boolean)
+        }
+        Operation::Count => {
+            if lambda.is_some() {
+                expect_key(Type::Boolean)?;
+            }
+            Ok(Type::Number)
+        }
+        Operation::Sum => {
+            expect_key(Type::Number)?;
+            Ok(Type::Number)
+        }
+        Operation::Min | Operation::Max | Operation::First | Operation::Last => Ok(item),
+        Operation::SortBy | Operation::Unique => Ok(Type::array(item)),
+        Operation::GroupBy => Ok(Type::object()),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{test_utils::Database, Diagnostic, Function, Inputs, Parameter, Response};
+
+    fn request(response: Option<Response>) -> Expression {
+        Expression::Request {
+            url: "http://example.com/".into(),
+            auth: None,
+            refresh: None,
+            response,
+            error: None,
+        }
+    }
+
+    #[test]
+    fn report_mistakes_before_requests_are_sent() {
+        let db = Database::with_nodes(vec![
+            Node::new("response", request(None)),
+            Node::new("status", Expression::get("response", "status")),
+            Node::new("is-ok", Expression::equals("status", "200")),
+            Node::new(
+                "shouting",
+                Expression::call("upper", [Argument::node("status")]),
+            ),
+            Node::new("assertion", Expression::assert("is-ok", "Not OK")),
+        ]);
+
+        assert!(db.evaluate().iter().all(|r| *r == Ok(Value::Indeterminate)));
+        assert_eq!(db.type_of("status".into()), Ok(Type::Number));
+        assert_eq!(
+            db.type_of("assertion".into()),
+            Err(EvaluationError::upstream(
+                "is-ok",
+                "\"status\" is a number, so comparing it with \"200\" will always be false".into()
+            ))
+        );
+        assert_eq!(
+            db.diagnostics(),
+            Sequence::from(vec![
+                Diagnostic::new(
+                    "is-ok",
+                    "\"status\" is a number, so comparing it with \"200\" will always be false"
+                ),
+                Diagnostic::new(
+                    "shouting",
+                    "upper() expects a string for argument 1, found a number"
+                ),
+            ])
+        );
+    }
+
+    #[test]
+    fn fields_are_known_once_a_response_arrives() {
+        let body: BTreeMap<Text, Value> = [(Text::from("name"), Value::from("Laskea"))]
+            .into_iter()
+            .collect();
+        let response = Response {
+            url: "http://example.com/".into(),
+            status: 200,
+            status_text: "OK".into(),
+            body: Value::Object(body.into()),
+            metrics: None,
+        };
+        let db = Database::with_nodes(vec![
+            Node::new("response", request(Some(response))),
+            Node::new("body", Expression::get("response", "body")),
+            Node::new("name", Expression::get("body", "name")),
+            Node::new("missing", Expression::get("body", "id")),
+        ]);
+
+        assert_eq!(
+            db.type_of("body".into()),
+            Ok(Type::object_with([(Text::from("name"), Type::String)]))
+        );
+        assert_eq!(db.type_of("name".into()), Ok(Type::String));
+        assert_eq!(
+            db.type_of("missing".into()),
+            Err(EvaluationError::from("\"body\" has no \"id\" field"))
+        );
+    }
+
+    #[test]
+    fn collections_and_functions() {
+        let mut db = Database::with_nodes(vec![
+            Node::new(
+                "names",
+                Expression::call(
+                    "split",
+                    [Argument::constant("a,b"), Argument::constant(",")],
+                ),
+            ),
+            Node::new(
+                "lengths",
+                Expression::collection(
+                    Operation::Map,
+                    "names",
+                    Lambda::new(
+                        "name",
+                        [Node::new(
+                            "length",
+                            Expression::call("length", [Argument::node("name")]),
+                        )],
+                    ),
+                ),
+            ),
+            Node::new(
+                "total",
+                Expression::Collection {
+                    operation: Operation::Sum,
+                    target: "names".into(),
+                    lambda: None,
+                },
+            ),
+            Node::new(
+                "doubled",
+                Expression::call("double", [Argument::node("lengths")]),
+            ),
+        ]);
+        let double = Function::new(
+            [Parameter::new("n", Type::Number)],
+            [Node::new(
+                "result",
+                Expression::call("concat", [Argument::node("n")]),
+            )],
+        );
+        db.set_functions([(Text::from("double"), double)].into_iter().collect());
+
+        assert_eq!(db.type_of("names".into()), Ok(Type::array(Type::String)));
+        assert_eq!(db.type_of("lengths".into()), Ok(Type::array(Type::Number)));
+        assert_eq!(
+            db.type_of("total".into()),
+            Err(EvaluationError::from(
+                "sum() expects a number, but the items are a string"
+            ))
+        );
+        assert_eq!(
+            db.type_of("doubled".into()),
+            Err(EvaluationError::from(
+                "double() expects a number for the \"n\" parameter, found an array"
+            ))
+        );
+        assert_eq!(
+            db.return_type("double".into()),
+            Err(EvaluationError::from(
+                "In double(): concat() expects a string for argument 1, found a number"
+            ))
+        );
+    }
+
+    #[test]
+    fn values_have_a_type() {
+        let inputs = vec![
+            (Value::from(1), Type::Number),
+            (Value::from("x"), Type::String),
+            (
+                Value::from(vec![Value::from(1), Value::from(2)]),
+                Type::array(Type::Number),
+            ),
+            (
+                Value::from(vec![Value::from(1), Value::from("x")]),
+                Type::array(Type::Unknown),
+            ),
+            (Value::Indeterminate, Type::Unknown),
+        ];
+
+        for (value, should_be) in inputs {
+            assert_eq!(Type::of(&value), should_be);
+            assert!(should_be.accepts(&value) || value == Value::Indeterminate);
+        }
+    }
+}
@@ -1,24 +1,52 @@
-use crate::Text;
+use crate::{Auth, Duration, Instant, Redact, Secret, Sequence, Text};
 use std::{
     collections::BTreeMap,
     fmt::{self, Display, Formatter},
-    hash::Hash,
+    hash::{Hash, Hasher},
     ops::Deref,
     sync::Arc,
 };
 
 #[derive(Debug, Clone, PartialEq, Eq, Hash, serde::Serialize, serde::Deserialize)]
+#[cfg_attr(feature = "typescript", derive(ts_rs::TS))]
 pub struct Node {
     pub name: Text,
+    #[serde(rename = "expression")]
     pub expr: Arc<Expression>,
 }
 
+impl Node {
+    pub fn new(name: impl Into<Text>, expr: impl Into<Arc<Expression>>) -> Self {
+        Node {
+            name: name.into(),
+            expr: expr.into(),
+        }
+    }
+}
+
 #[derive(Debug, Clone, PartialEq, Eq, Hash, serde::Serialize, serde::Deserialize)]
+#[cfg_attr(feature = "typescript", derive(ts_rs::TS))]
+#[serde(tag = "type", rename_all = "kebab-case")]
 pub enum Expression {
-    StringConstant(Text),
+    #[serde(rename = "string")]
+    StringConstant {
+        value: Text,
+    },
     Request {
         url: Text,
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        #[cfg_attr(feature = "typescript", ts(optional))]
+        auth: Option<Auth>,
+        /// How often the request should be re-sent when the workspace is
+        /// being monitored (see [`Scheduler`][crate::Scheduler]).
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        #[cfg_attr(feature = "typescript", ts(optional))]
+        refresh: Option<Duration>,
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        #[cfg_attr(feature = "typescript", ts(optional))]
         response: Option<Response>,
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        #[cfg_attr(feature = "typescript", ts(optional))]
         error: Option<EvaluationError>,
     },
     Equals {
@@ -29,11 +57,407 @@ pub enum Expression {
         target: Text,
         field: Text,
     },
+    /// Check that a boolean node is `true`, failing with `message` if it
+    /// isn't.
+    Assert {
+        target: Text,
+        message: Text,
+    },
+    /// Look up a variable in the active environment.
+    Variable {
+        name: Text,
+    },
+    /// A sensitive value (e.g. an auth token) loaded from a
+    /// [`SecretSource`][crate::SecretSource].
+    Secret {
+        name: Text,
+    },
+    /// Fetch an access token using the OAuth2 client credentials flow,
+    /// where `client_id` and `client_secret` are the nodes providing the
+    /// client's credentials.
+    #[serde(rename = "oauth2-token")]
+    OAuth2Token {
+        token_url: Text,
+        client_id: Text,
+        client_secret: Text,
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        #[cfg_attr(feature = "typescript", ts(optional))]
+        scope: Option<Text>,
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        #[cfg_attr(feature = "typescript", ts(optional))]
+        response: Option<Response>,
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        #[cfg_attr(feature = "typescript", ts(optional))]
+        error: Option<EvaluationError>,
+    },
+    /// Check whether a string matches a regular expression.
+    Matches {
+        target: Text,
+        pattern: Text,
+    },
+    /// Extract text from a string using a regular expression's capture
+    /// groups, where `group` is the group's name or number.
+    ///
+    /// If no group is given, every match is returned as an array of objects
+    /// containing the text matched by each group.
+    Capture {
+        target: Text,
+        pattern: Text,
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        #[cfg_attr(feature = "typescript", ts(optional))]
+        group: Option<Text>,
+    },
+    /// Call a built-in function (e.g. `concat` or `trim`).
+    Call {
+        function: Text,
+        arguments: Sequence<Argument>,
+    },
+    /// Apply an operation (e.g. `filter` or `sum`) to the items in an array,
+    /// using the lambda to compute something from each item.
+    Collection {
+        operation: Operation,
+        target: Text,
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        #[cfg_attr(feature = "typescript", ts(optional))]
+        lambda: Option<Lambda>,
+    },
+    /// Check a value against a JSON Schema, returning whether it is `valid`
+    /// and any `violations` that were found.
+    ValidateSchema {
+        target: Text,
+        schema: SchemaSource,
+    },
+    /// The result `target` had the previous time the workspace was evaluated
+    /// (see [`Inputs::previous_results()`][crate::Inputs::previous_results]).
+    ///
+    /// This doesn't depend on the target's current value, so a node can
+    /// refer to its own previous value.
+    Previous {
+        target: Text,
+    },
+    /// Whether `target`'s value is different from the previous time the
+    /// workspace was evaluated, including when it starts or stops failing.
+    Changed {
+        target: Text,
+    },
+}
+
+/// Where the JSON Schema for an [`Expression::ValidateSchema`] comes from.
+#[derive(Debug, Clone, PartialEq, Eq, Hash, serde::Serialize, serde::Deserialize)]
+#[cfg_attr(feature = "typescript", derive(ts_rs::TS))]
+#[serde(tag = "type", rename_all = "kebab-case")]
+pub enum SchemaSource {
+    /// The schema is stored in the workspace.
+    Inline {
+        #[cfg_attr(feature = "typescript", ts(type = "unknown"))]
+        schema: Json,
+    },
+    /// The schema is read from a file when the workspace is loaded (see
+    /// [`schemas::load_files()`][crate::schemas::load_files]).
+    File { path: Text },
+}
+
+impl SchemaSource {
+    pub fn inline(schema: serde_json::Value) -> Self {
+        SchemaSource::Inline {
+            schema: schema.into(),
+        }
+    }
+
+    pub fn file(path: impl Into<Text>) -> Self {
+        SchemaSource::File { path: path.into() }
+    }
+}
+
+/// An arbitrary JSON value (e.g. a JSON Schema).
+#[derive(Debug, Clone, PartialEq, Eq, serde::Serialize, serde::Deserialize)]
+#[serde(transparent)]
+pub struct Json(Arc<serde_json::Value>);
+
+impl From<serde_json::Value> for Json {
+    fn from(value: serde_json::Value) -> Self {
+        Json(Arc::new(value))
+    }
+}
+
+impl Deref for Json {
+    type Target = serde_json::Value;
+
+    fn deref(&self) -> &Self::Target {
+        &self.0
+    }
+}
+
+impl Hash for Json {
+    fn hash<H: Hasher>(&self, state: &mut H) {
+        // serde_json::Value doesn't implement Hash, but its string
+        // representation uniquely identifies it
+        self.0.to_string().hash(state);
+    }
+}
+
+/// An operation used by [`Expression::Collection`].
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Hash, serde::Serialize, serde::Deserialize)]
+#[cfg_attr(feature = "typescript", derive(ts_rs::TS))]
+#[serde(rename_all = "kebab-case")]
+pub enum Operation {
+    /// Transform each item.
+    Map,
+    /// Keep the items the lambda returns `true` for.
+    Filter,
+    /// Is the lambda `true` for any item?
+    Any,
+    /// Is the lambda `true` for every item?
+    All,
+    /// The number of items, or the number of items the lambda returns `true`
+    /// for.
+    Count,
+    Sum,
+    /// The item with the smallest value (or key, if there is a lambda).
+    Min,
+    /// The item with the largest value (or key, if there is a lambda).
+    Max,
+    SortBy,
+    /// Group the items into an object by the key returned from the lambda.
+    GroupBy,
+    /// Remove items with duplicate values (or keys, if there is a lambda).
+    Unique,
+    /// The first item (which the lambda returns `true` for).
+    First,
+    /// The last item (which the lambda returns `true` for).
+    Last,
+}
+
+/// A function which is evaluated for each item in a collection.
+///
+/// The body is a list of nodes which can refer to the current item (using
+/// the parameter's name), any earlier nodes in the body, or any node in the
+/// workspace. The lambda returns the value of the last node.
+#[derive(Debug, Clone, PartialEq, Eq, Hash, serde::Serialize, serde::Deserialize)]
+#[cfg_attr(feature = "typescript", derive(ts_rs::TS))]
+pub struct Lambda {
+    pub parameter: Text,
+    pub body: Sequence<Node>,
+}
+
+impl Lambda {
+    pub fn new(parameter: impl Into<Text>, body: impl IntoIterator<Item = Node>) -> Self {
+        Lambda {
+            parameter: parameter.into(),
+            body: body.into_iter().collect(),
+        }
+    }
+}
+
+/// A reusable function defined by the workspace, which can be called using
+/// an [`Expression::Call`].
+///
+/// Like a [`Lambda`], the body is a list of nodes which can refer to the
+/// parameters or any earlier node in the body, and the function returns the
+/// value of the last node. The body can't refer to nodes in the workspace, so
+/// a function's result only depends on its arguments.
+#[derive(Debug, Clone, PartialEq, Eq, Hash, serde::Serialize, serde::Deserialize)]
+#[cfg_attr(feature = "typescript", derive(ts_rs::TS))]
+// Avoid clashing with JavaScript's built-in Function type
+#[cfg_attr(feature = "typescript", ts(rename = "UserFunction"))]
+pub struct Function {
+    pub parameters: Sequence<Parameter>,
+    pub body: Sequence<Node>,
+}
+
+impl Function {
+    pub fn new(
+        parameters: impl IntoIterator<Item = Parameter>,
+        body: impl IntoIterator<Item = Node>,
+    ) -> Self {
+        Function {
+            parameters: parameters.into_iter().collect(),
+            body: body.into_iter().collect(),
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Hash, serde::Serialize, serde::Deserialize)]
+#[cfg_attr(feature = "typescript", derive(ts_rs::TS))]
+pub struct Parameter {
+    pub name: Text,
+    /// The type of value this parameter accepts.
+    #[serde(rename = "type", default, skip_serializing_if = "Type::is_unknown")]
+    pub ty: Type,
+}
+
+impl Parameter {
+    pub fn new(name: impl Into<Text>, ty: Type) -> Self {
+        Parameter {
+            name: name.into(),
+            ty,
+        }
+    }
+}
+
+/// The type of a [`Value`].
+#[derive(Debug, Default, Clone, PartialEq, Eq, Hash, serde::Serialize, serde::Deserialize)]
+#[cfg_attr(feature = "typescript", derive(ts_rs::TS))]
+#[serde(tag = "type", rename_all = "kebab-case")]
+pub enum Type {
+    /// The type isn't known, so any value is allowed.
+    #[default]
+    Unknown,
+    Number,
+    String,
+    Boolean,
+    Instant,
+    Duration,
+    Object {
+        /// The type of each field, or `None` if the object's fields aren't
+        /// known.
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        #[cfg_attr(feature = "typescript", ts(optional))]
+        fields: Option<BTreeMap<Text, Type>>,
+    },
+    Array {
+        /// The type of every item in the array.
+        #[serde(default, skip_serializing_if = "Type::is_unknown")]
+        item: Box<Type>,
+    },
+}
+
+impl Type {
+    /// An object whose fields aren't known.
+    pub fn object() -> Self {
+        Type::Object { fields: None }
+    }
+
+    pub fn object_with(fields: impl IntoIterator<Item = (Text, Type)>) -> Self {
+        Type::Object {
+            fields: Some(fields.into_iter().collect()),
+        }
+    }
+
+    pub fn array(item: Type) -> Self {
+        Type::Array {
+            item: Box::new(item),
+        }
+    }
+
+    pub fn is_unknown(&self) -> bool {
+        *self == Type::Unknown
+    }
+
+    /// The most specific type which describes `value`.
+    pub fn of(value: &Value) -> Type {
+        match value {
+            Value::Number(_) => Type::Number,
+            Value::String(_) => Type::String,
+            Value::Boolean(_) => Type::Boolean,
+            Value::Instant(_) => Type::Instant,
+            Value::Duration(_) => Type::Duration,
+            Value::Object(obj) => {
+                Type::object_with(obj.iter().map(|(k, v)| (k.clone(), Type::of(v))))
+            }
+            Value::Array(items) => {
+                let mut types = items.iter().map(Type::of);
+                let first = types.next().unwrap_or_default();
+                // Arrays with a mix of item types are treated as unknown
+                let item = if types.all(|t| t == first) {
+                    first
+                } else {
+                    Type::Unknown
+                };
+                Type::array(item)
+            }
+            Value::Secret(_) | Value::Indeterminate => Type::Unknown,
+        }
+    }
+
+    /// Can `value` be used where this type is expected?
+    pub fn accepts(&self, value: &Value) -> bool {
+        match (self, value) {
+            (Type::Unknown, _) => true,
+            (Type::Object { fields: None }, Value::Object(_)) => true,
+            (
+                Type::Object {
+                    fields: Some(fields),
+                },
+                Value::Object(obj),
+            ) => fields
+                .iter()
+                .all(|(name, ty)| obj.get(&**name).is_some_and(|v| ty.accepts(v))),
+            (Type::Array { item }, Value::Array(items)) => items.iter().all(|v| item.accepts(v)),
+            (expected, value) => *expected == Type::of(value),
+        }
+    }
+
+    /// Could a value of type `other` be used where this type is expected?
+    ///
+    /// Unknown types are compatible with everything, so this only returns
+    /// `false` when the two types definitely don't match.
+    pub fn accepts_type(&self, other: &Type) -> bool {
+        match (self, other) {
+            (Type::Unknown, _) | (_, Type::Unknown) => true,
+            (
+                Type::Object {
+                    fields: Some(expected),
+                },
+                Type::Object {
+                    fields: Some(actual),
+                },
+            ) => expected
+                .iter()
+                .all(|(name, ty)| actual.get(name).is_some_and(|t| ty.accepts_type(t))),
+            (Type::Object { .. }, Type::Object { .. }) => true,
+            (Type::Array { item: expected }, Type::Array { item: actual }) => {
+                expected.accepts_type(actual)
+            }
+            (expected, actual) => expected == actual,
+        }
+    }
+}
+
+impl Display for Type {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        match self {
+            Type::Unknown => write!(f, "any value"),
+            Type::Number => write!(f, "a number"),
+            Type::String => write!(f, "a string"),
+            Type::Boolean => write!(f, "a boolean"),
+            Type::Instant => write!(f, "an instant"),
+            Type::Duration => write!(f, "a duration"),
+            Type::Object { .. } => write!(f, "an object"),
+            Type::Array { .. } => write!(f, "an array"),
+        }
+    }
+}
+
+/// An argument passed to a function by an [`Expression::Call`].
+#[derive(Debug, Clone, PartialEq, Eq, Hash, serde::Serialize, serde::Deserialize)]
+#[cfg_attr(feature = "typescript", derive(ts_rs::TS))]
+#[serde(tag = "type", rename_all = "kebab-case")]
+pub enum Argument {
+    /// The value of another node.
+    Node {
+        name: Text,
+    },
+    Constant {
+        value: Value,
+    },
+}
+
+impl Argument {
+    pub fn node(name: impl Into<Text>) -> Self {
+        Argument::Node { name: name.into() }
+    }
+
+    pub fn constant(value: impl Into<Value>) -> Self {
+        Argument::Constant {
+            value: value.into(),
+        }
+    }
 }
 
 impl Expression {
     pub fn string(s: impl Into<Text>) -> Self {
-        Expression::StringConstant(s.into())
+        Expression::StringConstant { value: s.into() }
     }
 
     pub fn equals(target: impl Into<Text>, value: impl Into<Value>) -> Self {
@@ -49,33 +473,272 @@ impl Expression {
             field: field.into(),
         }
     }
+
+    pub fn assert(target: impl Into<Text>, message: impl Into<Text>) -> Self {
+        Expression::Assert {
+            target: target.into(),
+            message: message.into(),
+        }
+    }
+
+    pub fn variable(name: impl Into<Text>) -> Self {
+        Expression::Variable { name: name.into() }
+    }
+
+    pub fn secret(name: impl Into<Text>) -> Self {
+        Expression::Secret { name: name.into() }
+    }
+
+    pub fn matches(target: impl Into<Text>, pattern: impl Into<Text>) -> Self {
+        Expression::Matches {
+            target: target.into(),
+            pattern: pattern.into(),
+        }
+    }
+
+    pub fn capture(
+        target: impl Into<Text>,
+        pattern: impl Into<Text>,
+        group: impl Into<Text>,
+    ) -> Self {
+        Expression::Capture {
+            target: target.into(),
+            pattern: pattern.into(),
+            group: Some(group.into()),
+        }
+    }
+
+    pub fn validate_schema(target: impl Into<Text>, schema: SchemaSource) -> Self {
+        Expression::ValidateSchema {
+            target: target.into(),
+            schema,
+        }
+    }
+
+    pub fn previous(target: impl Into<Text>) -> Self {
+        Expression::Previous {
+            target: target.into(),
+        }
+    }
+
+    pub fn changed(target: impl Into<Text>) -> Self {
+        Expression::Changed {
+            target: target.into(),
+        }
+    }
+
+    pub fn collection(operation: Operation, target: impl Into<Text>, lambda: Lambda) -> Self {
+        Expression::Collection {
+            operation,
+            target: target.into(),
+            lambda: Some(lambda),
+        }
+    }
+
+    pub fn call(function: impl Into<Text>, arguments: impl IntoIterator<Item = Argument>) -> Self {
+        Expression::Call {
+            function: function.into(),
+            arguments: arguments.into_iter().collect(),
+        }
+    }
 }
 
 #[derive(Debug, Clone, PartialEq, Eq, Hash, serde::Serialize, serde::Deserialize)]
+#[cfg_attr(feature = "typescript", derive(ts_rs::TS))]
 pub struct Response {
     pub status: i32,
     pub status_text: Text,
     pub url: Text,
     pub body: Value,
+    /// Measurements taken while the request was being sent, if the host
+    /// recorded them.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    #[cfg_attr(feature = "typescript", ts(optional))]
+    pub metrics: Option<Metrics>,
 }
 
+/// Timing and size measurements for a [`Response`].
+///
+/// These are exposed as `latency_ms`, `ttfb_ms`, `dns_ms`, `connect_ms`,
+/// `size_bytes`, `redirects` and `timestamp` fields on the response's
+/// [`Value`], where the timings are only present if they were measured.
 #[derive(Debug, Clone, PartialEq, Eq, Hash, serde::Serialize, serde::Deserialize)]
-pub struct EvaluationError(String);
+#[cfg_attr(feature = "typescript", derive(ts_rs::TS))]
+pub struct Metrics {
+    /// When the request was sent.
+    pub timestamp: Instant,
+    /// How long it took to receive the whole response, including any
+    /// redirects.
+    pub total: Duration,
+    /// How long it took to receive the first byte of the final response.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    #[cfg_attr(feature = "typescript", ts(optional))]
+    pub ttfb: Option<Duration>,
+    /// How long it took to look up the server's address. This isn't known
+    /// when an existing connection was reused.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    #[cfg_attr(feature = "typescript", ts(optional))]
+    pub dns: Option<Duration>,
+    /// How long it took to connect to the server.
+    ///
+    /// Not every host can measure this (e.g. the CLI's HTTP client doesn't
+    /// report it), so it is `None` unless the host says otherwise.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    #[cfg_attr(feature = "typescript", ts(optional))]
+    pub connect: Option<Duration>,
+    /// The size of the response body, in bytes.
+    #[cfg_attr(feature = "typescript", ts(type = "number"))]
+    pub size: u64,
+    /// Every URL which was redirected from, in the order they were visited.
+    #[serde(default)]
+    pub redirects: Sequence<Text>,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Hash, serde::Serialize, serde::Deserialize)]
+#[cfg_attr(feature = "typescript", derive(ts_rs::TS))]
+#[serde(tag = "type", rename_all = "kebab-case")]
+pub enum EvaluationError {
+    /// The node itself failed.
+    Failed { message: Text },
+    /// The node couldn't be evaluated because a node it depends on failed.
+    UpstreamFailed {
+        /// The node the failure originated from.
+        node: Text,
+        message: Text,
+    },
+}
+
+impl EvaluationError {
+    /// Create an error for a node which was blocked by its dependency,
+    /// `node`, failing with `error`.
+    ///
+    /// Errors which were already propagated from further upstream keep
+    /// pointing at the node they originated from.
+    pub fn upstream(node: impl Into<Text>, error: EvaluationError) -> Self {
+        match error {
+            EvaluationError::Failed { message } => EvaluationError::UpstreamFailed {
+                node: node.into(),
+                message,
+            },
+            upstream @ EvaluationError::UpstreamFailed { .. } => upstream,
+        }
+    }
+
+    /// The original error message.
+    pub fn message(&self) -> &Text {
+        match self {
+            EvaluationError::Failed { message }
+            | EvaluationError::UpstreamFailed { message, .. } => message,
+        }
+    }
+
+    /// The upstream node this error originated from, if any.
+    pub fn origin(&self) -> Option<&Text> {
+        match self {
+            EvaluationError::Failed { .. } => None,
+            EvaluationError::UpstreamFailed { node, .. } => Some(node),
+        }
+    }
+}
 
 impl<D: Into<String>> From<D> for EvaluationError {
     fn from(value: D) -> Self {
-        EvaluationError(value.into())
+        EvaluationError::Failed {
+            message: value.into().into(),
+        }
     }
 }
 
 impl Display for EvaluationError {
     fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
-        Display::fmt(&self.0, f)
+        match self {
+            EvaluationError::Failed { message } => Display::fmt(message, f),
+            EvaluationError::UpstreamFailed { node, message } => {
+                write!(f, "blocked by failing node `{}`: {}", node, message)
+            }
+        }
+    }
+}
+
+/// A problem associated with a particular node.
+#[derive(Debug, Clone, PartialEq, Eq, Hash, serde::Serialize, serde::Deserialize)]
+#[cfg_attr(feature = "typescript", derive(ts_rs::TS))]
+pub struct Diagnostic {
+    pub node: Text,
+    pub message: Text,
+}
+
+impl Diagnostic {
+    pub fn new(node: impl Into<Text>, message: impl Into<Text>) -> Self {
+        Diagnostic {
+            node: node.into(),
+            message: message.into(),
+        }
+    }
+}
+
+/// The result of evaluating a single node, in a form which is convenient to
+/// send to other languages.
+#[derive(Debug, Clone, PartialEq, Eq, Hash, serde::Serialize, serde::Deserialize)]
+pub struct Evaluated {
+    pub name: Text,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub value: Option<Value>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub error: Option<EvaluationError>,
+}
+
+impl Evaluated {
+    pub fn new(name: impl Into<Text>, result: Result<Value, EvaluationError>) -> Self {
+        let name = name.into();
+
+        match result {
+            Ok(value) => Evaluated {
+                name,
+                value: Some(value),
+                error: None,
+            },
+            Err(error) => Evaluated {
+                name,
+                value: None,
+                error: Some(error),
+            },
+        }
+    }
+}
+
+impl Redact for Evaluated {
+    fn redacted(&self) -> Self {
+        Evaluated {
+            name: self.name.clone(),
+            value: self.value.as_ref().map(Value::redacted),
+            error: self.error.clone(),
+        }
+    }
+}
+
+/// A named set of variables (e.g. the base URL for "staging" or "prod").
+#[derive(Debug, Default, Clone, PartialEq, Eq, Hash, serde::Serialize, serde::Deserialize)]
+#[cfg_attr(feature = "typescript", derive(ts_rs::TS))]
+pub struct Environment(Arc<BTreeMap<Text, Text>>);
+
+impl From<BTreeMap<Text, Text>> for Environment {
+    fn from(m: BTreeMap<Text, Text>) -> Self {
+        Environment(Arc::new(m))
+    }
+}
+
+impl Deref for Environment {
+    type Target = BTreeMap<Text, Text>;
+
+    fn deref(&self) -> &Self::Target {
+        &self.0
     }
 }
 
 /// A reference-counted JSON-like object.
 #[derive(Debug, Default, Clone, PartialEq, Eq, Hash, serde::Serialize, serde::Deserialize)]
+#[cfg_attr(feature = "typescript", derive(ts_rs::TS))]
 pub struct Object(Arc<BTreeMap<Text, Value>>);
 
 impl From<BTreeMap<Text, Value>> for Object {
@@ -93,14 +756,78 @@ impl Deref for Object {
 }
 
 #[derive(Debug, Clone, PartialEq, Eq, Hash, serde::Serialize, serde::Deserialize)]
+#[cfg_attr(feature = "typescript", derive(ts_rs::TS))]
+#[serde(tag = "type", content = "value", rename_all = "kebab-case")]
 pub enum Value {
     Number(i32),
     String(Text),
     Boolean(bool),
+    Instant(Instant),
+    Duration(Duration),
     Object(Object),
+    Array(Sequence<Value>),
+    Secret(Secret),
     Indeterminate,
 }
 
+impl Display for Value {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        match self {
+            Value::Number(n) => Display::fmt(n, f),
+            Value::String(s) => write!(f, "{:?}", &**s),
+            Value::Boolean(b) => Display::fmt(b, f),
+            Value::Instant(instant) => Display::fmt(instant, f),
+            Value::Duration(duration) => Display::fmt(duration, f),
+            Value::Object(obj) => {
+                write!(f, "{{")?;
+                for (i, (key, value)) in obj.iter().enumerate() {
+                    if i > 0 {
+                        write!(f, ", ")?;
+                    }
+                    write!(f, "{}: {}", key, value)?;
+                }
+                write!(f, "}}")
+            }
+            Value::Array(items) => {
+                write!(f, "[")?;
+                for (i, item) in items.iter().enumerate() {
+                    if i > 0 {
+                        write!(f, ", ")?;
+                    }
+                    write!(f, "{}", item)?;
+                }
+                write!(f, "]")
+            }
+            Value::Secret(secret) => Display::fmt(secret, f),
+            Value::Indeterminate => write!(f, "indeterminate"),
+        }
+    }
+}
+
+impl Value {
+    /// Replace every [`Secret`] in this value (including inside objects and
+    /// arrays).
+    pub(crate) fn map_secrets(&self, f: &impl Fn(&Secret) -> Secret) -> Value {
+        match self {
+            Value::Object(obj) => Value::Object(
+                obj.iter()
+                    .map(|(key, value)| (key.clone(), value.map_secrets(f)))
+                    .collect::<BTreeMap<_, _>>()
+                    .into(),
+            ),
+            Value::Array(items) => Value::Array(items.iter().map(|v| v.map_secrets(f)).collect()),
+            Value::Secret(secret) => Value::Secret(f(secret)),
+            other => other.clone(),
+        }
+    }
+}
+
+impl Redact for Value {
+    fn redacted(&self) -> Self {
+        self.map_secrets(&Secret::redacted)
+    }
+}
+
 macro_rules! impl_value_from {
     ($($type:ty => $variant:ident),* $(,)*) => {
         $(
@@ -118,7 +845,12 @@ impl_value_from! {
     Text => String,
     String => String,
     bool => Boolean,
+    Instant => Instant,
+    Duration => Duration,
     Object => Object,
+    Sequence<Value> => Array,
+    Vec<Value> => Array,
+    Secret => Secret,
 }
 
 impl<'a> From<&'a str> for Value {
@@ -134,6 +866,7 @@ impl From<Response> for Value {
             status,
             status_text,
             body,
+            metrics,
         } = r;
 
         let mut obj = BTreeMap::default();
@@ -142,6 +875,256 @@ impl From<Response> for Value {
         obj.insert(Text::from("status_text"), status_text.into());
         obj.insert(Text::from("body"), body);
 
+        if let Some(metrics) = metrics {
+            let timings = [
+                ("latency_ms", Some(metrics.total)),
+                ("ttfb_ms", metrics.ttfb),
+                ("dns_ms", metrics.dns),
+                ("connect_ms", metrics.connect),
+            ];
+            for (name, duration) in timings {
+                if let Some(duration) = duration {
+                    obj.insert(Text::from(name), saturating_number(duration.as_millis()));
+                }
+            }
+
+            obj.insert(Text::from("size_bytes"), saturating_number(metrics.size));
+            obj.insert(
+                Text::from("redirects"),
+                metrics
+                    .redirects
+                    .iter()
+                    .cloned()
+                    .map(Value::from)
+                    .collect::<Vec<_>>()
+                    .into(),
+            );
+            obj.insert(Text::from("timestamp"), metrics.timestamp.into());
+        }
+
         Value::Object(obj.into())
     }
 }
+
+/// Convert a measurement to a [`Value::Number`], clamping it if it is too
+/// large.
+fn saturating_number(n: impl TryInto<i32>) -> Value {
+    Value::from(n.try_into().unwrap_or(i32::MAX))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    #[test]
+    fn expressions_are_tagged_with_their_type() {
+        let expressions = vec![
+            (
+                Expression::string("Hello"),
+                json!({ "type": "string", "value": "Hello" }),
+            ),
+            (
+                Expression::equals("x", 42),
+                json!({
+                    "type": "equals",
+                    "target": "x",
+                    "value": { "type": "number", "value": 42 },
+                }),
+            ),
+            (
+                Expression::get("x", "status"),
+                json!({ "type": "get-property", "target": "x", "field": "status" }),
+            ),
+            (
+                Expression::Request {
+                    url: "http://example.com/".into(),
+                    auth: None,
+                    refresh: None,
+                    response: None,
+                    error: None,
+                },
+                json!({ "type": "request", "url": "http://example.com/" }),
+            ),
+            (
+                Expression::call("trim", [Argument::node("x"), Argument::constant(" ")]),
+                json!({
+                    "type": "call",
+                    "function": "trim",
+                    "arguments": [
+                        { "type": "node", "name": "x" },
+                        {
+                            "type": "constant",
+                            "value": { "type": "string", "value": " " },
+                        },
+                    ],
+                }),
+            ),
+            (
+                Expression::collection(
+                    Operation::SortBy,
+                    "users",
+                    Lambda::new(
+                        "user",
+                        [Node {
+                            name: "id".into(),
+                            expr: Expression::get("user", "id").into(),
+                        }],
+                    ),
+                ),
+                json!({
+                    "type": "collection",
+                    "operation": "sort-by",
+                    "target": "users",
+                    "lambda": {
+                        "parameter": "user",
+                        "body": [
+                            {
+                                "name": "id",
+                                "expression": { "type": "get-property", "target": "user", "field": "id" },
+                            },
+                        ],
+                    },
+                }),
+            ),
+            (
+                Expression::validate_schema(
+                    "body",
+                    SchemaSource::inline(json!({ "type": "object" })),
+                ),
+                json!({
+                    "type": "validate-schema",
+                    "target": "body",
+                    "schema": { "type": "inline", "schema": { "type": "object" } },
+                }),
+            ),
+            (
+                Expression::previous("status"),
+                json!({ "type": "previous", "target": "status" }),
+            ),
+            (
+                Expression::changed("status"),
+                json!({ "type": "changed", "target": "status" }),
+            ),
+        ];
+
+        for (expr, should_be) in expressions {
+            let got = serde_json::to_value(&expr).unwrap();
+            assert_eq!(got, should_be);
+
+            let round_tripped: Expression = serde_json::from_value(got).unwrap();
+            assert_eq!(round_tripped, expr);
+        }
+    }
+
+    #[test]
+    fn types_are_tagged() {
+        let types = vec![
+            (Type::Number, json!({ "type": "number" })),
+            (Type::object(), json!({ "type": "object" })),
+            (
+                Type::object_with([(Text::from("id"), Type::Number)]),
+                json!({ "type": "object", "fields": { "id": { "type": "number" } } }),
+            ),
+            (
+                Type::array(Type::String),
+                json!({ "type": "array", "item": { "type": "string" } }),
+            ),
+            (Type::array(Type::Unknown), json!({ "type": "array" })),
+        ];
+
+        for (ty, should_be) in types {
+            let got = serde_json::to_value(&ty).unwrap();
+            assert_eq!(got, should_be);
+
+            let round_tripped: Type = serde_json::from_value(got).unwrap();
+            assert_eq!(round_tripped, ty);
+        }
+    }
+
+    #[test]
+    fn values_are_tagged_with_their_type() {
+        let mut obj = BTreeMap::new();
+        obj.insert(Text::from("answer"), Value::from(42));
+        let values = vec![
+            (
+                Value::from(true),
+                json!({ "type": "boolean", "value": true }),
+            ),
+            (Value::Indeterminate, json!({ "type": "indeterminate" })),
+            (
+                Value::from(vec![Value::from(1), Value::from("two")]),
+                json!({
+                    "type": "array",
+                    "value": [
+                        { "type": "number", "value": 1 },
+                        { "type": "string", "value": "two" },
+                    ],
+                }),
+            ),
+            (
+                Value::Object(obj.into()),
+                json!({
+                    "type": "object",
+                    "value": { "answer": { "type": "number", "value": 42 } },
+                }),
+            ),
+        ];
+
+        for (value, should_be) in values {
+            let got = serde_json::to_value(&value).unwrap();
+            assert_eq!(got, should_be);
+
+            let round_tripped: Value = serde_json::from_value(got).unwrap();
+            assert_eq!(round_tripped, value);
+        }
+    }
+
+    #[test]
+    fn secret_values_are_redacted() {
+        let value = Value::Array(vec![Value::Secret(Secret::new("hunter2"))].into());
+
+        let got = serde_json::to_value(&value).unwrap();
+
+        assert_eq!(
+            got,
+            json!({ "type": "array", "value": [{ "type": "secret", "value": "[REDACTED]" }] })
+        );
+        assert_eq!(value.to_string(), "[[REDACTED]]");
+        assert!(!format!("{:?}", value).contains("hunter2"));
+    }
+
+    #[test]
+    fn response_metrics_are_fields() {
+        let response = Response {
+            status: 200,
+            status_text: "OK".into(),
+            url: "http://example.com/".into(),
+            body: Value::from("Hello"),
+            metrics: Some(Metrics {
+                timestamp: "2024-01-31T09:30:00Z".parse().unwrap(),
+                total: Duration::from_millis(250),
+                ttfb: Some(Duration::from_millis(200)),
+                dns: None,
+                connect: None,
+                size: 5,
+                redirects: vec![Text::from("http://example.com/old")].into(),
+            }),
+        };
+
+        let got = match Value::from(response) {
+            Value::Object(obj) => obj,
+            other => panic!("Expected an object, found {}", other),
+        };
+
+        assert_eq!(got["latency_ms"], Value::from(250));
+        assert_eq!(got["ttfb_ms"], Value::from(200));
+        assert_eq!(got.get("dns_ms"), None);
+        assert_eq!(got["size_bytes"], Value::from(5));
+        assert_eq!(
+            got["redirects"],
+            Value::from(vec![Value::from("http://example.com/old")])
+        );
+        assert_eq!(got["timestamp"].to_string(), "2024-01-31T09:30:00Z");
+    }
+}
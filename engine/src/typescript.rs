@@ -0,0 +1,60 @@
+use crate::{
+    ApiKeyLocation, Argument, Auth, Change, Derivation, Diagnostic, Duration, Environment,
+    EvaluationError, Explanation, Expression, Function, Instant, Lambda, Metrics, Node, Object,
+    Operation, Parameter, PreparedRequest, Response, SchemaSource, Secret, Sequence, Text, Type,
+    Value,
+};
+use ts_rs::TS;
+
+/// Generate TypeScript declarations for every type which may be passed to or
+/// from JavaScript.
+///
+/// These match the `serde` representation of each type, so a value can be
+/// converted to JSON and used as-is.
+pub fn typescript_declarations() -> String {
+    let mut decls = vec![
+        String::from("// Generated from the laskea-engine types. Do not edit by hand."),
+        Text::decl(),
+        <Sequence<Text>>::decl(),
+        EvaluationError::decl(),
+        Secret::decl(),
+        Instant::decl(),
+        Duration::decl(),
+        Object::decl(),
+        Value::decl(),
+        Metrics::decl(),
+        Response::decl(),
+        ApiKeyLocation::decl(),
+        Auth::decl(),
+        Argument::decl(),
+        Operation::decl(),
+        Lambda::decl(),
+        SchemaSource::decl(),
+        Expression::decl(),
+        Node::decl(),
+        Environment::decl(),
+        Type::decl(),
+        Parameter::decl(),
+        Function::decl(),
+        format!(
+            "type EvaluationResult = {};",
+            <Result<Value, EvaluationError>>::name()
+        ),
+        format!(
+            "type TypeResult = {};",
+            <Result<Type, EvaluationError>>::name()
+        ),
+        PreparedRequest::decl(),
+        format!(
+            "type PreparedRequestResult = {};",
+            <Result<PreparedRequest, EvaluationError>>::name()
+        ),
+        Change::decl(),
+        Diagnostic::decl(),
+        Derivation::decl(),
+        Explanation::decl(),
+    ];
+    decls.push(String::new());
+
+    decls.join("\n")
+}
@@ -0,0 +1,367 @@
+//! Functions defined by the workspace, which can be used by an
+//! [`Expression::Call`].
+//!
+//! [`Expression::Call`]: crate::Expression::Call
+
+use crate::{
+    evaluate::{Locals, Scope},
+    Evaluate, EvaluationError, Expression, Function, Node, Sequence, Text, Value,
+};
+use std::collections::BTreeSet;
+
+pub(crate) fn call_function(
+    db: &dyn Evaluate,
+    function: Text,
+    arguments: Sequence<Value>,
+) -> Result<Value, EvaluationError> {
+    if let Some(cycle) = db.function_cycle(function.clone()) {
+        let msg = format!("Recursion detected: {}", cycle.join(" → "));
+        return Err(msg.into());
+    }
+
+    let definition = db
+        .function(function.clone())
+        .ok_or_else(|| format!("No \"{}\" function found", function))?;
+
+    let expected = definition.parameters.len();
+    if arguments.len() != expected {
+        let plural = if expected == 1 { "" } else { "s" };
+        let msg = format!(
+            "{}() expects {} argument{}, found {}",
+            function,
+            expected,
+            plural,
+            arguments.len()
+        );
+        return Err(msg.into());
+    }
+
+    let parameters = Parameters {
+        function: &function,
+    };
+    let mut locals = Locals::new(&parameters);
+
+    for (parameter, argument) in definition.parameters.iter().zip(arguments.iter()) {
+        if !parameter.ty.accepts(argument) {
+            let msg = format!(
+                "{}() expects {} for the \"{}\" parameter, found {}",
+                function, parameter.ty, parameter.name, argument
+            );
+            return Err(msg.into());
+        }
+
+        locals.bind(parameter.name.clone(), Ok(argument.clone()));
+    }
+
+    match locals.evaluate_body(db, &definition.body) {
+        Some(Ok(value)) => Ok(value),
+        Some(Err(e)) => Err(format!("{}() failed: {}", function, e.message()).into()),
+        None => Err(format!("{}() has no body", function).into()),
+    }
+}
+
+/// The scope a function's body is evaluated in, which only contains the
+/// function's parameters.
+struct Parameters<'a> {
+    function: &'a Text,
+}
+
+impl Scope for Parameters<'_> {
+    fn lookup(&self, name: &Text) -> Result<Value, EvaluationError> {
+        Err(format!(
+            "{}() has no \"{}\" parameter or local node",
+            self.function, name
+        )
+        .into())
+    }
+}
+
+pub(crate) fn function(db: &dyn Evaluate, name: Text) -> Option<Function> {
+    db.functions().get(&name).cloned()
+}
+
+pub(crate) fn function_cycle(db: &dyn Evaluate, function: Text) -> Option<Sequence<Text>> {
+    let mut path = vec![function.clone()];
+    let mut visited = BTreeSet::new();
+
+    if find_cycle(db, &function, &mut path, &mut visited) {
+        Some(path.into())
+    } else {
+        None
+    }
+}
+
+/// Do a depth-first search for a chain of calls from `function` back to the
+/// first function in `path`.
+fn find_cycle(
+    db: &dyn Evaluate,
+    function: &Text,
+    path: &mut Vec<Text>,
+    visited: &mut BTreeSet<Text>,
+) -> bool {
+    let definition = match db.function(function.clone()) {
+        Some(f) => f,
+        None => return false,
+    };
+    let mut callees = Vec::new();
+    called_functions(&definition.body, &mut callees);
+
+    for callee in callees {
+        if crate::functions::is_builtin(callee) {
+            // Built-in functions can't call anything
+            continue;
+        }
+
+        if *callee == path[0] {
+            path.push(callee.clone());
+            return true;
+        }
+
+        if !visited.insert(callee.clone()) {
+            continue;
+        }
+
+        path.push(callee.clone());
+        if find_cycle(db, callee, path, visited) {
+            return true;
+        }
+        path.pop();
+    }
+
+    false
+}
+
+/// Find the names of every function called by a list of nodes, including
+/// calls made by lambdas.
+fn called_functions<'a>(body: &'a Sequence<Node>, calls: &mut Vec<&'a Text>) {
+    for node in body.iter() {
+        match &*node.expr {
+            Expression::Call { function, .. } => calls.push(function),
+            Expression::Collection {
+                lambda: Some(lambda),
+                ..
+            } => called_functions(&lambda.body, calls),
+            _ => {}
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        test_utils::Database, Argument, EditError, Inputs, InputsExt, Lambda, Operation, Parameter,
+        Type,
+    };
+
+    /// `is_healthy(status: number)` checks whether the status is 200.
+    fn is_healthy() -> Function {
+        Function::new(
+            [Parameter::new("status", Type::Number)],
+            [Node::new("healthy", Expression::equals("status", 200))],
+        )
+    }
+
+    fn database(functions: Vec<(&str, Function)>, nodes: Vec<Node>) -> Database {
+        let mut db = Database::default();
+        db.set_functions(
+            functions
+                .into_iter()
+                .map(|(name, f)| (Text::from(name), f))
+                .collect(),
+        );
+        db.set_nodes(nodes.into());
+        db
+    }
+
+    fn call(function: &str, arguments: Vec<Argument>) -> Expression {
+        Expression::call(function, arguments)
+    }
+
+    #[test]
+    fn call_a_function_from_several_nodes() {
+        let db = database(
+            vec![("is_healthy", is_healthy())],
+            vec![
+                Node::new("first", call("is_healthy", vec![Argument::constant(200)])),
+                Node::new("second", call("is_healthy", vec![Argument::constant(200)])),
+                Node::new("third", call("is_healthy", vec![Argument::constant(500)])),
+            ],
+        );
+
+        let got = db.evaluate();
+
+        assert_eq!(
+            got,
+            [
+                Ok(Value::from(true)),
+                Ok(Value::from(true)),
+                Ok(Value::from(false))
+            ]
+        );
+        // The second call used the memoized result from the first
+        assert_eq!(
+            db.executed("call_function"),
+            [
+                "call_function((Text(\"is_healthy\"), Sequence([Number(200)])))",
+                "call_function((Text(\"is_healthy\"), Sequence([Number(500)])))",
+            ]
+        );
+    }
+
+    #[test]
+    fn functions_can_call_other_functions_and_use_lambdas() {
+        let all_healthy = Function::new(
+            [Parameter::new("statuses", Type::array(Type::String))],
+            [Node::new(
+                "all",
+                Expression::collection(
+                    Operation::All,
+                    "statuses",
+                    Lambda::new(
+                        "status",
+                        [Node::new(
+                            "ok",
+                            call("is_healthy", vec![Argument::node("status")]),
+                        )],
+                    ),
+                ),
+            )],
+        );
+        let db = database(
+            vec![("is_healthy", is_healthy()), ("all_healthy", all_healthy)],
+            vec![
+                Node::new(
+                    "statuses",
+                    call(
+                        "split",
+                        vec![Argument::constant("200,200"), Argument::constant(",")],
+                    ),
+                ),
+                Node::new(
+                    "healthy",
+                    call("all_healthy", vec![Argument::node("statuses")]),
+                ),
+            ],
+        );
+
+        let got = db.evaluate();
+
+        // split() gives us strings, not numbers
+        assert_eq!(
+            got[1],
+            Err(EvaluationError::from(
+                "all_healthy() failed: all() failed on item 0: is_healthy() expects a number for the \"status\" parameter, found \"200\""
+            ))
+        );
+    }
+
+    #[test]
+    fn editing_one_function_doesnt_rerun_calls_to_another() {
+        let mut db = database(
+            vec![("is_healthy", is_healthy())],
+            vec![Node::new(
+                "first",
+                call("is_healthy", vec![Argument::constant(200)]),
+            )],
+        );
+        db.evaluate();
+        db.executed("call_function");
+
+        db.set_function("unused".into(), is_healthy()).unwrap();
+        db.evaluate();
+
+        assert!(db.executed("call_function").is_empty());
+    }
+
+    #[test]
+    fn functions_cant_shadow_builtins() {
+        let mut db = Database::default();
+
+        let err = db.set_function("trim".into(), is_healthy()).unwrap_err();
+
+        assert_eq!(err, EditError::BuiltinFunction("trim".into()));
+        assert!(db.functions().is_empty());
+    }
+
+    #[test]
+    fn arity_and_type_errors() {
+        let db = database(
+            vec![("is_healthy", is_healthy())],
+            vec![
+                Node::new("none", call("is_healthy", vec![])),
+                Node::new("string", call("is_healthy", vec![Argument::constant("OK")])),
+                Node::new(
+                    "missing",
+                    call("is_healthy", vec![Argument::node("status")]),
+                ),
+            ],
+        );
+
+        let got = db.evaluate();
+
+        assert_eq!(
+            got,
+            [
+                Err(EvaluationError::from(
+                    "is_healthy() expects 1 argument, found 0"
+                )),
+                Err(EvaluationError::from(
+                    "is_healthy() expects a number for the \"status\" parameter, found \"OK\""
+                )),
+                Err(EvaluationError::from("No \"status\" input found")),
+            ]
+        );
+    }
+
+    #[test]
+    fn function_bodies_cant_see_workspace_nodes() {
+        let uses_global = Function::new([], [Node::new("copy", Expression::get("config", "url"))]);
+        let db = database(
+            vec![("uses_global", uses_global)],
+            vec![
+                Node::new("config", Expression::string("...")),
+                Node::new("value", call("uses_global", vec![])),
+            ],
+        );
+
+        assert_eq!(
+            db.evaluate()[1],
+            Err(EvaluationError::from(
+                "uses_global() failed: uses_global() has no \"config\" parameter or local node"
+            ))
+        );
+    }
+
+    #[test]
+    fn detect_recursion() {
+        let even = Function::new(
+            [Parameter::new("n", Type::Number)],
+            [Node::new("result", call("odd", vec![Argument::node("n")]))],
+        );
+        let odd = Function::new(
+            [Parameter::new("n", Type::Number)],
+            [Node::new("result", call("even", vec![Argument::node("n")]))],
+        );
+        let db = database(
+            vec![("even", even), ("odd", odd), ("is_healthy", is_healthy())],
+            vec![Node::new("x", call("even", vec![Argument::constant(4)]))],
+        );
+
+        assert_eq!(
+            db.function_cycle("even".into()),
+            Some(Sequence::from(vec![
+                Text::from("even"),
+                Text::from("odd"),
+                Text::from("even")
+            ]))
+        );
+        assert_eq!(db.function_cycle("is_healthy".into()), None);
+        assert_eq!(
+            db.evaluate()[0],
+            Err(EvaluationError::from(
+                "Recursion detected: even → odd → even"
+            ))
+        );
+    }
+}
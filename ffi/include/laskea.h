@@ -0,0 +1,144 @@
+#ifndef LASKEA_H
+#define LASKEA_H
+
+/* Generated from ffi/src/lib.rs. Do not edit by hand. */
+
+#include <stdarg.h>
+#include <stdbool.h>
+#include <stdint.h>
+#include <stdlib.h>
+
+/**
+ * The outcome of calling a laskea function.
+ */
+typedef enum LaskeaStatus {
+  LASKEA_STATUS_OK = 0,
+  /**
+   * A required pointer was null.
+   */
+  LASKEA_STATUS_NULL_POINTER = 1,
+  /**
+   * The input could not be parsed.
+   */
+  LASKEA_STATUS_INVALID_JSON = 2,
+  /**
+   * Laskea encountered an internal error.
+   */
+  LASKEA_STATUS_PANIC = 3,
+  /**
+   * A string argument was not valid UTF-8.
+   */
+  LASKEA_STATUS_INVALID_UTF8 = 4,
+} LaskeaStatus;
+
+/**
+ * An opaque handle to a laskea database.
+ */
+typedef struct Laskea Laskea;
+
+/**
+ * A buffer owned by laskea.
+ *
+ * Buffers must be freed with `laskea_buffer_free()`.
+ */
+typedef struct LaskeaBuffer {
+  uint8_t *data;
+  uintptr_t len;
+} LaskeaBuffer;
+
+/**
+ * Create a new database with no nodes, which reads JSON Schema files
+ * relative to the current directory.
+ *
+ * The database must be freed with `laskea_free()`.
+ */
+struct Laskea *laskea_new(void);
+
+/**
+ * Free a database.
+ *
+ * # Safety
+ *
+ * `laskea` must be null or a pointer returned by `laskea_new()` which
+ * hasn't already been freed.
+ */
+void laskea_free(struct Laskea *laskea);
+
+/**
+ * Replace the database's nodes with a JSON array of nodes (e.g.
+ * `[{"name": "x", "expression": {"type": "string", "value": "Hello"}}]`).
+ *
+ * # Safety
+ *
+ * `laskea` must be a valid database and `json` must point to `len` bytes of
+ * readable memory.
+ */
+enum LaskeaStatus laskea_set_nodes(struct Laskea *laskea, const uint8_t *json, uintptr_t len);
+
+/**
+ * Set the directory JSON Schema files are read from, and (re-)load every
+ * schema file used by the nodes.
+ *
+ * # Safety
+ *
+ * `laskea` must be a valid database and `path` must point to `len` bytes of
+ * readable memory.
+ */
+enum LaskeaStatus laskea_set_schema_dir(struct Laskea *laskea, const uint8_t *path, uintptr_t len);
+
+/**
+ * Set the time returned by `now()` using an RFC 3339 timestamp (e.g.
+ * `2024-01-31T09:30:00Z`).
+ *
+ * The time is set to the current time when the database is created.
+ *
+ * # Safety
+ *
+ * `laskea` must be a valid database and `timestamp` must point to `len`
+ * bytes of readable memory.
+ */
+enum LaskeaStatus laskea_set_now(struct Laskea *laskea, const uint8_t *timestamp, uintptr_t len);
+
+/**
+ * Evaluate every node, writing a JSON array of results to `results`.
+ *
+ * Each result is an object with the node's `name` and either a `value` or
+ * an `error`. Errors from a failing upstream node include the `node` they
+ * originated from.
+ *
+ * # Safety
+ *
+ * `laskea` must be a valid database and `results` must point to a
+ * `LaskeaBuffer` which can be written to.
+ */
+enum LaskeaStatus laskea_evaluate(const struct Laskea *laskea, struct LaskeaBuffer *results);
+
+/**
+ * Write a JSON array of diagnostics to `diagnostics`.
+ *
+ * # Safety
+ *
+ * `laskea` must be a valid database and `diagnostics` must point to a
+ * `LaskeaBuffer` which can be written to.
+ */
+enum LaskeaStatus laskea_diagnostics(const struct Laskea *laskea, struct LaskeaBuffer *diagnostics);
+
+/**
+ * Free a buffer that was created by laskea.
+ *
+ * # Safety
+ *
+ * The buffer must have been populated by laskea and not already freed.
+ */
+void laskea_buffer_free(struct LaskeaBuffer buffer);
+
+/**
+ * Get a message describing the last error that occurred on this thread, or
+ * null if the last call was successful.
+ *
+ * The message is owned by laskea and is only valid until the next call to a
+ * laskea function.
+ */
+const char *laskea_last_error_message(void);
+
+#endif  /* LASKEA_H */
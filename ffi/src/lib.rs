@@ -0,0 +1,322 @@
+//! A C interface to the [`laskea_engine`].
+//!
+//! Nodes and results are exchanged as UTF-8 encoded JSON using the engine's
+//! `serde` representation. Every fallible function returns a
+//! [`LaskeaStatus`], and [`laskea_last_error_message()`] can be used to find
+//! out what went wrong.
+//!
+//! The C header is generated from this file by `tests/header.rs`.
+
+use laskea_engine::{
+    Evaluate, EvaluateStorage, Evaluated, Inputs, InputsExt, InputsStorage, Instant, Node, Redact,
+};
+use std::{
+    any::Any,
+    cell::RefCell,
+    ffi::CString,
+    os::raw::c_char,
+    panic::{self, AssertUnwindSafe},
+    path::PathBuf,
+    ptr, slice,
+};
+
+/// An opaque handle to a laskea database.
+pub struct Laskea {
+    db: Database,
+    /// The directory JSON Schema files are read from.
+    schema_dir: PathBuf,
+}
+
+/// The outcome of calling a laskea function.
+#[repr(C)]
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub enum LaskeaStatus {
+    Ok = 0,
+    /// A required pointer was null.
+    NullPointer = 1,
+    /// The input could not be parsed.
+    InvalidJson = 2,
+    /// Laskea encountered an internal error.
+    Panic = 3,
+    /// A string argument was not valid UTF-8.
+    InvalidUtf8 = 4,
+}
+
+/// A buffer owned by laskea.
+///
+/// Buffers must be freed with `laskea_buffer_free()`.
+#[repr(C)]
+#[derive(Debug)]
+pub struct LaskeaBuffer {
+    pub data: *mut u8,
+    pub len: usize,
+}
+
+/// Create a new database with no nodes, which reads JSON Schema files
+/// relative to the current directory.
+///
+/// The database must be freed with `laskea_free()`.
+#[no_mangle]
+pub extern "C" fn laskea_new() -> *mut Laskea {
+    let mut db = Database::default();
+    laskea_engine::init_inputs(&mut db);
+    db.set_now(Instant::now());
+
+    Box::into_raw(Box::new(Laskea {
+        db,
+        schema_dir: PathBuf::from("."),
+    }))
+}
+
+/// Free a database.
+///
+/// # Safety
+///
+/// `laskea` must be null or a pointer returned by `laskea_new()` which
+/// hasn't already been freed.
+#[no_mangle]
+pub unsafe extern "C" fn laskea_free(laskea: *mut Laskea) {
+    if !laskea.is_null() {
+        drop(Box::from_raw(laskea));
+    }
+}
+
+/// Replace the database's nodes with a JSON array of nodes (e.g.
+/// `[{"name": "x", "expression": {"type": "string", "value": "Hello"}}]`).
+///
+/// # Safety
+///
+/// `laskea` must be a valid database and `json` must point to `len` bytes of
+/// readable memory.
+#[no_mangle]
+pub unsafe extern "C" fn laskea_set_nodes(
+    laskea: *mut Laskea,
+    json: *const u8,
+    len: usize,
+) -> LaskeaStatus {
+    guard(|| {
+        let laskea = laskea.as_mut().ok_or_else(Error::null)?;
+        let json = bytes(json, len)?;
+
+        let nodes: Vec<Node> = serde_json::from_slice(json).map_err(|e| Error {
+            status: LaskeaStatus::InvalidJson,
+            message: format!("Unable to parse the nodes: {}", e),
+        })?;
+
+        laskea.db.set_nodes(nodes.into());
+        laskea_engine::schemas::load_files(&mut laskea.db, &laskea.schema_dir);
+        Ok(())
+    })
+}
+
+/// Set the directory JSON Schema files are read from, and (re-)load every
+/// schema file used by the nodes.
+///
+/// # Safety
+///
+/// `laskea` must be a valid database and `path` must point to `len` bytes of
+/// readable memory.
+#[no_mangle]
+pub unsafe extern "C" fn laskea_set_schema_dir(
+    laskea: *mut Laskea,
+    path: *const u8,
+    len: usize,
+) -> LaskeaStatus {
+    guard(|| {
+        let laskea = laskea.as_mut().ok_or_else(Error::null)?;
+        let path = std::str::from_utf8(bytes(path, len)?).map_err(|_| Error {
+            status: LaskeaStatus::InvalidUtf8,
+            message: String::from("The path must be valid UTF-8"),
+        })?;
+
+        laskea.schema_dir = PathBuf::from(path);
+        laskea_engine::schemas::load_files(&mut laskea.db, &laskea.schema_dir);
+        Ok(())
+    })
+}
+
+/// Set the time returned by `now()` using an RFC 3339 timestamp (e.g.
+/// `2024-01-31T09:30:00Z`).
+///
+/// The time is set to the current time when the database is created.
+///
+/// # Safety
+///
+/// `laskea` must be a valid database and `timestamp` must point to `len`
+/// bytes of readable memory.
+#[no_mangle]
+pub unsafe extern "C" fn laskea_set_now(
+    laskea: *mut Laskea,
+    timestamp: *const u8,
+    len: usize,
+) -> LaskeaStatus {
+    guard(|| {
+        let laskea = laskea.as_mut().ok_or_else(Error::null)?;
+        let timestamp = bytes(timestamp, len)?;
+
+        let now = std::str::from_utf8(timestamp)
+            .ok()
+            .and_then(|s| s.parse::<Instant>().ok())
+            .ok_or_else(|| Error {
+                status: LaskeaStatus::InvalidJson,
+                message: String::from("Expected an RFC 3339 timestamp"),
+            })?;
+
+        laskea.db.set_now(now);
+        Ok(())
+    })
+}
+
+/// Evaluate every node, writing a JSON array of results to `results`.
+///
+/// Each result is an object with the node's `name` and either a `value` or
+/// an `error`. Errors from a failing upstream node include the `node` they
+/// originated from.
+///
+/// # Safety
+///
+/// `laskea` must be a valid database and `results` must point to a
+/// `LaskeaBuffer` which can be written to.
+#[no_mangle]
+pub unsafe extern "C" fn laskea_evaluate(
+    laskea: *const Laskea,
+    results: *mut LaskeaBuffer,
+) -> LaskeaStatus {
+    guard(|| {
+        let laskea = laskea.as_ref().ok_or_else(Error::null)?;
+        let results = results.as_mut().ok_or_else(Error::null)?;
+
+        let nodes = laskea.db.nodes();
+        let evaluated: Vec<_> = nodes
+            .iter()
+            .zip(laskea.db.evaluate().iter().cloned())
+            .map(|(node, result)| Evaluated::new(node.name.clone(), result).redacted())
+            .collect();
+
+        *results = LaskeaBuffer::json(&evaluated);
+        Ok(())
+    })
+}
+
+/// Write a JSON array of diagnostics to `diagnostics`.
+///
+/// # Safety
+///
+/// `laskea` must be a valid database and `diagnostics` must point to a
+/// `LaskeaBuffer` which can be written to.
+#[no_mangle]
+pub unsafe extern "C" fn laskea_diagnostics(
+    laskea: *const Laskea,
+    diagnostics: *mut LaskeaBuffer,
+) -> LaskeaStatus {
+    guard(|| {
+        let laskea = laskea.as_ref().ok_or_else(Error::null)?;
+        let diagnostics = diagnostics.as_mut().ok_or_else(Error::null)?;
+
+        *diagnostics = LaskeaBuffer::json(&laskea.db.diagnostics());
+        Ok(())
+    })
+}
+
+/// Free a buffer that was created by laskea.
+///
+/// # Safety
+///
+/// The buffer must have been populated by laskea and not already freed.
+#[no_mangle]
+pub unsafe extern "C" fn laskea_buffer_free(buffer: LaskeaBuffer) {
+    if !buffer.data.is_null() {
+        let data = ptr::slice_from_raw_parts_mut(buffer.data, buffer.len);
+        drop(Box::from_raw(data));
+    }
+}
+
+/// Get a message describing the last error that occurred on this thread, or
+/// null if the last call was successful.
+///
+/// The message is owned by laskea and is only valid until the next call to a
+/// laskea function.
+#[no_mangle]
+pub extern "C" fn laskea_last_error_message() -> *const c_char {
+    LAST_ERROR.with(|last| {
+        last.borrow()
+            .as_ref()
+            .map(|msg| msg.as_ptr())
+            .unwrap_or(ptr::null())
+    })
+}
+
+thread_local! {
+    static LAST_ERROR: RefCell<Option<CString>> = const { RefCell::new(None) };
+}
+
+struct Error {
+    status: LaskeaStatus,
+    message: String,
+}
+
+impl Error {
+    fn null() -> Self {
+        Error {
+            status: LaskeaStatus::NullPointer,
+            message: String::from("A required pointer was null"),
+        }
+    }
+}
+
+/// Run a function, recording any errors so they can be retrieved with
+/// [`laskea_last_error_message()`] and making sure panics don't unwind
+/// across the FFI boundary.
+fn guard(func: impl FnOnce() -> Result<(), Error>) -> LaskeaStatus {
+    let (status, message) = match panic::catch_unwind(AssertUnwindSafe(func)) {
+        Ok(Ok(())) => (LaskeaStatus::Ok, None),
+        Ok(Err(Error { status, message })) => (status, Some(message)),
+        Err(payload) => (LaskeaStatus::Panic, Some(panic_message(&*payload))),
+    };
+
+    let message =
+        message.map(|m| CString::new(m.replace('\0', "")).expect("All null bytes were removed"));
+    LAST_ERROR.with(|last| *last.borrow_mut() = message);
+
+    status
+}
+
+fn panic_message(payload: &(dyn Any + Send)) -> String {
+    if let Some(s) = payload.downcast_ref::<&str>() {
+        s.to_string()
+    } else if let Some(s) = payload.downcast_ref::<String>() {
+        s.clone()
+    } else {
+        String::from("A panic occurred")
+    }
+}
+
+unsafe fn bytes<'a>(data: *const u8, len: usize) -> Result<&'a [u8], Error> {
+    if data.is_null() {
+        Err(Error::null())
+    } else {
+        Ok(slice::from_raw_parts(data, len))
+    }
+}
+
+impl LaskeaBuffer {
+    fn json(value: &impl serde::Serialize) -> Self {
+        let bytes = serde_json::to_vec(value)
+            .expect("Serializing to JSON is infallible")
+            .into_boxed_slice();
+        let len = bytes.len();
+
+        LaskeaBuffer {
+            data: Box::into_raw(bytes).cast(),
+            len,
+        }
+    }
+}
+
+#[salsa::database(InputsStorage, EvaluateStorage)]
+#[derive(Default)]
+struct Database {
+    storage: salsa::Storage<Self>,
+}
+
+impl salsa::Database for Database {}
@@ -0,0 +1,170 @@
+//! Exercise the library through its `extern "C"` functions, the same way a C
+//! program would.
+
+use laskea_ffi::*;
+use serde_json::{json, Value};
+use std::{ffi::CStr, ptr};
+
+fn set_nodes(laskea: *mut Laskea, nodes: Value) -> LaskeaStatus {
+    let json = nodes.to_string();
+    unsafe { laskea_set_nodes(laskea, json.as_ptr(), json.len()) }
+}
+
+fn read_buffer(buffer: LaskeaBuffer) -> Value {
+    let bytes = unsafe { std::slice::from_raw_parts(buffer.data, buffer.len) };
+    let value = serde_json::from_slice(bytes).unwrap();
+    unsafe { laskea_buffer_free(buffer) };
+
+    value
+}
+
+fn last_error() -> Option<String> {
+    let msg = laskea_last_error_message();
+
+    if msg.is_null() {
+        None
+    } else {
+        let msg = unsafe { CStr::from_ptr(msg) };
+        Some(msg.to_str().unwrap().to_string())
+    }
+}
+
+fn empty_buffer() -> LaskeaBuffer {
+    LaskeaBuffer {
+        data: ptr::null_mut(),
+        len: 0,
+    }
+}
+
+#[test]
+fn evaluate_some_nodes() {
+    let laskea = laskea_new();
+    let nodes = json!([
+        { "name": "greeting", "expression": { "type": "string", "value": "Hello" } },
+        {
+            "name": "is-hello",
+            "expression": {
+                "type": "equals",
+                "target": "greeting",
+                "value": { "type": "string", "value": "Hello" },
+            },
+        },
+        {
+            "name": "broken",
+            "expression": {
+                "type": "get-property",
+                "target": "missing",
+                "field": "status",
+            },
+        },
+    ]);
+    assert_eq!(set_nodes(laskea, nodes), LaskeaStatus::Ok);
+
+    let mut results = empty_buffer();
+    let status = unsafe { laskea_evaluate(laskea, &mut results) };
+    assert_eq!(status, LaskeaStatus::Ok);
+    assert_eq!(last_error(), None);
+
+    let should_be = json!([
+        { "name": "greeting", "value": { "type": "string", "value": "Hello" } },
+        { "name": "is-hello", "value": { "type": "boolean", "value": true } },
+        {
+            "name": "broken",
+            "error": { "type": "failed", "message": "No \"missing\" input found" },
+        },
+    ]);
+    assert_eq!(read_buffer(results), should_be);
+
+    let mut diagnostics = empty_buffer();
+    let status = unsafe { laskea_diagnostics(laskea, &mut diagnostics) };
+    assert_eq!(status, LaskeaStatus::Ok);
+    assert_eq!(
+        read_buffer(diagnostics),
+        json!([{ "node": "broken", "message": "No \"missing\" input found" }])
+    );
+
+    unsafe { laskea_free(laskea) };
+}
+
+#[test]
+fn schema_files_are_loaded_from_the_schema_dir() {
+    let dir = std::env::temp_dir().join(format!("laskea-ffi-{}", std::process::id()));
+    std::fs::create_dir_all(&dir).unwrap();
+    std::fs::write(dir.join("greeting.json"), r#"{ "type": "integer" }"#).unwrap();
+    let laskea = laskea_new();
+    let nodes = json!([
+        { "name": "greeting", "expression": { "type": "string", "value": "Hello" } },
+        {
+            "name": "valid",
+            "expression": {
+                "type": "validate-schema",
+                "target": "greeting",
+                "schema": { "type": "file", "path": "greeting.json" },
+            },
+        },
+    ]);
+    assert_eq!(set_nodes(laskea, nodes), LaskeaStatus::Ok);
+
+    let path = dir.to_str().unwrap();
+    let status = unsafe { laskea_set_schema_dir(laskea, path.as_ptr(), path.len()) };
+    assert_eq!(status, LaskeaStatus::Ok);
+    std::fs::remove_dir_all(&dir).unwrap();
+
+    let mut results = empty_buffer();
+    let status = unsafe { laskea_evaluate(laskea, &mut results) };
+    assert_eq!(status, LaskeaStatus::Ok);
+    let results = read_buffer(results);
+    assert_eq!(results[1]["value"]["value"]["valid"]["value"], json!(false));
+
+    unsafe { laskea_free(laskea) };
+}
+
+#[test]
+fn schema_dirs_must_be_utf8() {
+    let laskea = laskea_new();
+    let path = b"schemas/\xff";
+
+    let status = unsafe { laskea_set_schema_dir(laskea, path.as_ptr(), path.len()) };
+
+    assert_eq!(status, LaskeaStatus::InvalidUtf8);
+    assert_eq!(last_error().unwrap(), "The path must be valid UTF-8");
+
+    unsafe { laskea_free(laskea) };
+}
+
+#[test]
+fn invalid_json_is_reported() {
+    let laskea = laskea_new();
+
+    let status = set_nodes(laskea, json!({ "not": "a list of nodes" }));
+
+    assert_eq!(status, LaskeaStatus::InvalidJson);
+    assert!(last_error()
+        .unwrap()
+        .starts_with("Unable to parse the nodes"));
+
+    // a successful call clears the error
+    assert_eq!(set_nodes(laskea, json!([])), LaskeaStatus::Ok);
+    assert_eq!(last_error(), None);
+
+    unsafe { laskea_free(laskea) };
+}
+
+#[test]
+fn null_pointers_are_rejected() {
+    let mut results = empty_buffer();
+
+    let status = unsafe { laskea_evaluate(ptr::null(), &mut results) };
+    assert_eq!(status, LaskeaStatus::NullPointer);
+
+    let laskea = laskea_new();
+    let status = unsafe { laskea_set_nodes(laskea, ptr::null(), 42) };
+    assert_eq!(status, LaskeaStatus::NullPointer);
+    assert!(results.data.is_null());
+
+    unsafe {
+        laskea_free(laskea);
+        laskea_free(ptr::null_mut());
+        laskea_buffer_free(results);
+    }
+}
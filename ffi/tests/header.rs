@@ -0,0 +1,44 @@
+//! Make sure the C header is kept in sync with the `extern "C"` functions.
+
+use cbindgen::{Builder, Config, EnumConfig, Language, RenameRule};
+use std::path::Path;
+
+#[test]
+fn c_header_is_up_to_date() {
+    let crate_dir = Path::new(env!("CARGO_MANIFEST_DIR"));
+    let path = crate_dir.join("include").join("laskea.h");
+
+    let config = Config {
+        language: Language::C,
+        include_guard: Some(String::from("LASKEA_H")),
+        autogen_warning: Some(String::from(
+            "/* Generated from ffi/src/lib.rs. Do not edit by hand. */",
+        )),
+        enumeration: EnumConfig {
+            rename_variants: RenameRule::QualifiedScreamingSnakeCase,
+            ..Default::default()
+        },
+        ..Default::default()
+    };
+
+    let mut header = Vec::new();
+    Builder::new()
+        .with_config(config)
+        .with_src(crate_dir.join("src").join("lib.rs"))
+        .generate()
+        .expect("Unable to generate the header")
+        .write(&mut header);
+    let should_be = String::from_utf8(header).unwrap();
+
+    if std::env::var_os("UPDATE_HEADER").is_some() {
+        std::fs::write(&path, &should_be).unwrap();
+    }
+
+    let got = std::fs::read_to_string(&path).unwrap();
+    assert_eq!(
+        got,
+        should_be,
+        "{} is out of date. Re-run this test with UPDATE_HEADER=1 to regenerate it.",
+        path.display()
+    );
+}
@@ -0,0 +1,17 @@
+import { Laskea } from "laskea-bindings";
+import { createContext, useContext } from "react";
+
+export const LaskeaContext = createContext<Laskea | undefined>(undefined);
+
+/**
+ * Get the engine the nodes are being evaluated by.
+ */
+export function useLaskea(): Laskea {
+    const laskea = useContext(LaskeaContext);
+
+    if (!laskea) {
+        throw new Error("useLaskea() must be used inside a LaskeaContext");
+    }
+
+    return laskea;
+}
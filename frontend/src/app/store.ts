@@ -4,16 +4,16 @@ import {
     PayloadAction,
     createSelector,
 } from "@reduxjs/toolkit";
-import { Expression, Node as LaskeaNode, Value } from "laskea-bindings";
+import { Change, EvaluationResult, Expression } from "laskea-bindings";
 
 type NodesState = {
     nodes: Node[];
 };
 
-type Node = {
+export type Node = {
     name: string;
     expression: Expression;
-    result: Value;
+    result: EvaluationResult;
 };
 
 const initialState: NodesState = {
@@ -31,7 +31,7 @@ const nodesSlice = createSlice({
                     type: "string",
                     value: "",
                 },
-                result: { "type": "indetermimate" },
+                result: { Ok: { type: "indeterminate" } },
             };
             state.nodes.push(emptyNode);
         },
@@ -52,17 +52,19 @@ const nodesSlice = createSlice({
             const { index, expr } = action.payload;
             state.nodes[index].expression = expr;
         },
-        setResults: (state, action: PayloadAction<Value[]>) => {
-            const results = action.payload;
+        applyChanges: (state, action: PayloadAction<Change[]>) => {
+            for (const change of action.payload) {
+                const node = state.nodes.find(n => n.name === change.name);
 
-            for (let i = 0; i < results.length; i++) {
-                state.nodes[i].result = results[i];
+                if (change.type === "updated" && node) {
+                    node.result = change.result;
+                }
             }
         },
     },
 });
 
-export const { addNode, removeNode, setName, setExpression, setResults } =
+export const { addNode, removeNode, setName, setExpression, applyChanges } =
     nodesSlice.actions;
 
 export const store = configureStore({ reducer: { nodes: nodesSlice.reducer } });
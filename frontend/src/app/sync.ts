@@ -0,0 +1,78 @@
+import { Laskea } from "laskea-bindings";
+import type { Node } from "./store";
+
+/**
+ * Tell laskea about the edits made between two versions of the node list.
+ *
+ * The editor only changes one thing at a time, so we can use laskea's
+ * targeted edits and only the affected nodes will be re-evaluated. Anything
+ * we can't map to a single edit (e.g. duplicate names) falls back to
+ * replacing every node.
+ */
+export function syncNodes(laskea: Laskea, previous: Node[], nodes: Node[]) {
+    try {
+        if (!applyEdits(laskea, previous, nodes)) {
+            laskea.evaluate(nodes);
+        }
+    } catch {
+        laskea.evaluate(nodes);
+    }
+}
+
+function applyEdits(laskea: Laskea, previous: Node[], nodes: Node[]): boolean {
+    const names = (list: Node[]) => list.map(n => n.name);
+    const isUnique = (list: Node[], name: string) =>
+        names(list).filter(n => n === name).length === 1;
+    const isSame = (a: Node, b: Node) =>
+        a.name === b.name && a.expression === b.expression;
+
+    if (nodes.length === previous.length + 1) {
+        // New nodes are always appended
+        if (!previous.every((n, i) => isSame(n, nodes[i]))) {
+            return false;
+        }
+
+        const added = nodes[nodes.length - 1];
+        laskea.addNode(added);
+        return true;
+    }
+
+    if (nodes.length < previous.length) {
+        const remaining = new Set(names(nodes));
+        const removed = previous.filter(n => !remaining.has(n.name));
+        if (
+            removed.length !== previous.length - nodes.length ||
+            !removed.every(n => isUnique(previous, n.name))
+        ) {
+            return false;
+        }
+
+        removed.forEach(n => laskea.removeNode(n.name));
+        return true;
+    }
+
+    if (nodes.length !== previous.length) {
+        return false;
+    }
+
+    for (let i = 0; i < nodes.length; i++) {
+        const before = previous[i];
+        const after = nodes[i];
+
+        if (before.name !== after.name) {
+            if (!isUnique(previous, before.name)) {
+                return false;
+            }
+            laskea.renameNode(before.name, after.name);
+        }
+
+        if (before.expression !== after.expression) {
+            if (!isUnique(nodes, after.name)) {
+                return false;
+            }
+            laskea.updateExpression(after.name, after.expression);
+        }
+    }
+
+    return true;
+}
@@ -0,0 +1,34 @@
+import { TextField } from "@mui/material";
+import { Expression } from "laskea-bindings";
+import { useAppDispatch } from "../app/hooks";
+import { setExpression } from "../app/store";
+
+type Props = {
+    index: number;
+    expr: Extract<Expression, { type: "assert" }>;
+};
+
+export default function AssertEditor({ index, expr }: Props) {
+    const dispatch = useAppDispatch();
+    const { target, message } = expr;
+
+    const setTarget = (target: string) =>
+        dispatch(setExpression({ index, expr: { ...expr, target } }));
+    const setMessage = (message: string) =>
+        dispatch(setExpression({ index, expr: { ...expr, message } }));
+
+    return (
+        <>
+            <TextField
+                value={target}
+                placeholder="Condition"
+                onChange={e => setTarget(e.target.value)}
+            />
+            <TextField
+                value={message}
+                placeholder="Message"
+                onChange={e => setMessage(e.target.value)}
+            />
+        </>
+    );
+}
@@ -1,32 +1,44 @@
 import { Button, Container } from "@mui/material";
 import AddCircle from "@mui/icons-material/AddCircle";
 import { useAppDispatch, useAppSelector } from "../app/hooks";
-import { addNode, setResults } from "../app/store";
+import { LaskeaContext } from "../app/laskea";
+import { addNode, applyChanges, Node } from "../app/store";
+import { syncNodes } from "../app/sync";
 import NodeEditor from "./NodeEditor";
-import { useEffect, useState } from "react";
-import { Laskea } from "laskea-bindings";
+import { useEffect, useRef, useState } from "react";
+import { Change, Laskea } from "laskea-bindings";
 
 export default function Body() {
     const dispatch = useAppDispatch();
     const { nodes } = useAppSelector(s => s.nodes);
     const [laskea] = useState(() => new Laskea());
+    const previous = useRef<Node[]>([]);
 
     useEffect(() => {
-        const results = laskea.evaluate(nodes);
-        dispatch(setResults(results));
-    }, [nodes, dispatch]);
+        const id = laskea.subscribe((changes: Change[]) =>
+            dispatch(applyChanges(changes))
+        );
+        return () => laskea.unsubscribe(id);
+    }, [laskea, dispatch]);
+
+    useEffect(() => {
+        syncNodes(laskea, previous.current, nodes);
+        previous.current = nodes;
+    }, [laskea, nodes]);
 
     const renderedNodes = nodes.map((n, i) => {
         return <NodeEditor key={i} index={i} node={n} />;
     });
 
     return (
-        <Container>
-            <Button onClick={() => dispatch(addNode())}>
-                <AddCircle fontSize="large" />
-            </Button>
+        <LaskeaContext.Provider value={laskea}>
+            <Container>
+                <Button onClick={() => dispatch(addNode())}>
+                    <AddCircle fontSize="large" />
+                </Button>
 
-            {renderedNodes}
-        </Container>
+                {renderedNodes}
+            </Container>
+        </LaskeaContext.Provider>
     );
 }
@@ -0,0 +1,98 @@
+import { TextField } from "@mui/material";
+import { Argument, Expression } from "laskea-bindings";
+import { useAppDispatch } from "../app/hooks";
+import { setExpression } from "../app/store";
+
+type Props = {
+    index: number;
+    expr: Extract<Expression, { type: "call" }>;
+};
+
+export default function CallEditor({ index, expr }: Props) {
+    const dispatch = useAppDispatch();
+
+    const setFunction = (name: string) =>
+        dispatch(setExpression({ index, expr: { ...expr, function: name } }));
+    const setArguments = (text: string) =>
+        dispatch(
+            setExpression({
+                index,
+                expr: { ...expr, arguments: parseArguments(text) },
+            }),
+        );
+
+    return (
+        <>
+            <TextField
+                value={expr.function}
+                placeholder="Function"
+                onChange={e => setFunction(e.target.value)}
+            />
+            <TextField
+                value={displayArguments(expr.arguments)}
+                placeholder='Arguments (e.g. name, "constant", 42)'
+                onChange={e => setArguments(e.target.value)}
+            />
+        </>
+    );
+}
+
+/**
+ * Parse a comma-separated list of arguments, where quoted strings, numbers,
+ * and booleans are constants and anything else is the name of a node.
+ */
+function parseArguments(text: string): Argument[] {
+    const pattern = /\s*("(?:[^"\\]|\\.)*"|[^,]*?)\s*(?:,|$)/g;
+    const args: Argument[] = [];
+
+    for (const [match, item] of text.matchAll(pattern)) {
+        if (match === "") {
+            break;
+        }
+        args.push(parseArgument(item));
+    }
+
+    return args;
+}
+
+function parseArgument(item: string): Argument {
+    if (/^".*"$/.test(item)) {
+        try {
+            const value: string = JSON.parse(item);
+            return { type: "constant", value: { type: "string", value } };
+        } catch {
+            // Fall through and treat it as a node name
+        }
+    }
+
+    if (/^-?\d+$/.test(item)) {
+        return {
+            type: "constant",
+            value: { type: "number", value: parseInt(item) },
+        };
+    } else if (item == "true" || item == "false") {
+        return {
+            type: "constant",
+            value: { type: "boolean", value: item == "true" },
+        };
+    }
+
+    return { type: "node", name: item };
+}
+
+function displayArguments(args: Argument[]): string {
+    return args
+        .map(arg => {
+            if (arg.type == "node") {
+                return arg.name;
+            }
+            switch (arg.value.type) {
+                case "number":
+                case "boolean":
+                    return arg.value.value.toString();
+                default:
+                    return JSON.stringify(arg.value.value);
+            }
+        })
+        .join(", ");
+}
@@ -0,0 +1,46 @@
+import { TextField } from "@mui/material";
+import { Expression } from "laskea-bindings";
+import { useAppDispatch } from "../app/hooks";
+import { setExpression } from "../app/store";
+
+type Props = {
+    index: number;
+    expr: Extract<Expression, { type: "capture" }>;
+};
+
+export default function CaptureEditor({ index, expr }: Props) {
+    const dispatch = useAppDispatch();
+    const { target, pattern, group } = expr;
+
+    const setTarget = (target: string) =>
+        dispatch(setExpression({ index, expr: { ...expr, target } }));
+    const setPattern = (pattern: string) =>
+        dispatch(setExpression({ index, expr: { ...expr, pattern } }));
+    const setGroup = (group: string) =>
+        dispatch(
+            setExpression({
+                index,
+                expr: { ...expr, group: group || undefined },
+            }),
+        );
+
+    return (
+        <>
+            <TextField
+                value={target}
+                placeholder="Target"
+                onChange={e => setTarget(e.target.value)}
+            />
+            <TextField
+                value={pattern}
+                placeholder="Pattern"
+                onChange={e => setPattern(e.target.value)}
+            />
+            <TextField
+                value={group ?? ""}
+                placeholder="Group (all matches if empty)"
+                onChange={e => setGroup(e.target.value)}
+            />
+        </>
+    );
+}
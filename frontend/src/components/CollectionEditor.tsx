@@ -0,0 +1,138 @@
+import { MenuItem, Select, TextField } from "@mui/material";
+import { Expression, Lambda, Operation } from "laskea-bindings";
+import { useAppDispatch } from "../app/hooks";
+import { setExpression } from "../app/store";
+
+type Props = {
+    index: number;
+    expr: Extract<Expression, { type: "collection" }>;
+};
+
+const operations: Record<Operation, string> = {
+    map: "Map",
+    filter: "Filter",
+    any: "Any",
+    all: "All",
+    count: "Count",
+    sum: "Sum",
+    min: "Min",
+    max: "Max",
+    "sort-by": "Sort By",
+    "group-by": "Group By",
+    unique: "Unique",
+    first: "First",
+    last: "Last",
+};
+
+/**
+ * Edit a collection operation, where the lambda reads a field from each item
+ * (e.g. `status`) or compares that field with a constant (`status == active`).
+ *
+ * Lambdas with a more complicated body can't be edited here, so they are
+ * displayed as read-only.
+ */
+export default function CollectionEditor({ index, expr }: Props) {
+    const dispatch = useAppDispatch();
+    const { operation, target, lambda } = expr;
+    const key = lambda ? displayLambda(lambda) : "";
+
+    const setOperation = (operation: Operation) =>
+        dispatch(setExpression({ index, expr: { ...expr, operation } }));
+    const setTarget = (target: string) =>
+        dispatch(setExpression({ index, expr: { ...expr, target } }));
+    const setLambda = (text: string) =>
+        dispatch(
+            setExpression({
+                index,
+                expr: { ...expr, lambda: parseLambda(text) },
+            }),
+        );
+
+    const menuItems = Object.entries(operations).map(([value, name]) => (
+        <MenuItem key={value} value={value}>
+            {name}
+        </MenuItem>
+    ));
+
+    return (
+        <>
+            <Select
+                value={operation}
+                onChange={e => setOperation(e.target.value as Operation)}
+            >
+                {menuItems}
+            </Select>
+            <TextField
+                value={target}
+                placeholder="Target"
+                onChange={e => setTarget(e.target.value)}
+            />
+            <TextField
+                value={key ?? "(custom lambda)"}
+                disabled={key === undefined}
+                placeholder="Field (e.g. status == active)"
+                onChange={e => setLambda(e.target.value)}
+            />
+        </>
+    );
+}
+
+const parameter = "item";
+
+function parseLambda(text: string): Lambda | undefined {
+    const [field, value] = text.split("==").map(s => s.trim());
+
+    if (!field) {
+        return undefined;
+    }
+
+    const body: Lambda["body"] = [
+        {
+            name: "field",
+            expression: { type: "get-property", target: parameter, field },
+        },
+    ];
+
+    if (value !== undefined) {
+        body.push({
+            name: "equal",
+            expression: {
+                type: "equals",
+                target: "field",
+                value: { type: "string", value },
+            },
+        });
+    }
+
+    return { parameter, body };
+}
+
+/**
+ * The text used to edit a lambda, or `undefined` if it wasn't created by
+ * {@link parseLambda}.
+ */
+function displayLambda(lambda: Lambda): string | undefined {
+    const [get, equals, ...rest] = lambda.body;
+
+    if (
+        rest.length > 0 ||
+        get?.expression.type != "get-property" ||
+        get.expression.target != lambda.parameter
+    ) {
+        return undefined;
+    }
+
+    if (!equals) {
+        return get.expression.field;
+    }
+
+    if (
+        equals.expression.type != "equals" ||
+        equals.expression.target != get.name ||
+        equals.expression.value.type != "string"
+    ) {
+        return undefined;
+    }
+
+    return `${get.expression.field} == ${equals.expression.value.value}`;
+}
@@ -1,5 +1,5 @@
 import { TextField } from "@mui/material";
-import { Expression } from "laskea-bindings";
+import { Expression, Value } from "laskea-bindings";
 import { useAppDispatch } from "../app/hooks";
 import { setExpression } from "../app/store";
 
@@ -14,8 +14,10 @@ export default function EqualsEditor({ index, expr }: Props) {
 
     const setTarget = (target: string) =>
         dispatch(setExpression({ index, expr: { ...expr, target } }));
-    const setValue = (value: string) =>
-        dispatch(setExpression({ index, expr: { ...expr, value } }));
+    const setValue = (text: string) =>
+        dispatch(
+            setExpression({ index, expr: { ...expr, value: parseValue(text) } })
+        );
 
     return (
         <>
@@ -25,10 +27,32 @@ export default function EqualsEditor({ index, expr }: Props) {
                 onChange={e => setTarget(e.target.value)}
             />
             <TextField
-                value={value}
+                value={displayValue(value)}
                 placeholder="Value"
                 onChange={e => setValue(e.target.value)}
             />
         </>
     );
 }
+
+function parseValue(text: string): Value {
+    if (/^-?\d+$/.test(text)) {
+        return { type: "number", value: parseInt(text) };
+    } else if (text == "true" || text == "false") {
+        return { type: "boolean", value: text == "true" };
+    } else {
+        return { type: "string", value: text };
+    }
+}
+
+function displayValue(value: Value): string {
+    switch (value.type) {
+        case "indeterminate":
+            return "";
+        case "object":
+        case "array":
+            return JSON.stringify(value.value);
+        default:
+            return value.value.toString();
+    }
+}
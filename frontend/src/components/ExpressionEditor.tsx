@@ -5,6 +5,16 @@ import StringConstantEditor from "./StringConstantEditor";
 import RequestEditor from "./RequestEditor";
 import EqualsEditor from "./EqualsEditor";
 import GetPropertyEditor from "./GetPropertyEditor";
+import AssertEditor from "./AssertEditor";
+import VariableEditor from "./VariableEditor";
+import SecretEditor from "./SecretEditor";
+import OAuth2TokenEditor from "./OAuth2TokenEditor";
+import CallEditor from "./CallEditor";
+import MatchesEditor from "./MatchesEditor";
+import CaptureEditor from "./CaptureEditor";
+import CollectionEditor from "./CollectionEditor";
+import ValidateSchemaEditor from "./ValidateSchemaEditor";
+import PreviousRunEditor from "./PreviousRunEditor";
 import { Expression } from "laskea-bindings";
 
 type Props = {
@@ -48,13 +58,81 @@ const options: Options = {
     equals: {
         name: "Equals",
         render: EqualsEditor,
-        defaultValue: () => ({ type: "equals", target: "", value: "" }),
+        defaultValue: () => ({
+            type: "equals",
+            target: "",
+            value: { type: "string", value: "" },
+        }),
     },
     "get-property": {
         name: "Property",
         render: GetPropertyEditor,
         defaultValue: () => ({ type: "get-property", target: "", field: "" }),
     },
+    assert: {
+        name: "Assert",
+        render: AssertEditor,
+        defaultValue: () => ({ type: "assert", target: "", message: "" }),
+    },
+    variable: {
+        name: "Variable",
+        render: VariableEditor,
+        defaultValue: () => ({ type: "variable", name: "" }),
+    },
+    secret: {
+        name: "Secret",
+        render: SecretEditor,
+        defaultValue: () => ({ type: "secret", name: "" }),
+    },
+    "oauth2-token": {
+        name: "OAuth2 Token",
+        render: OAuth2TokenEditor,
+        defaultValue: () => ({
+            type: "oauth2-token",
+            token_url: "",
+            client_id: "",
+            client_secret: "",
+        }),
+    },
+    matches: {
+        name: "Matches",
+        render: MatchesEditor,
+        defaultValue: () => ({ type: "matches", target: "", pattern: "" }),
+    },
+    capture: {
+        name: "Capture",
+        render: CaptureEditor,
+        defaultValue: () => ({ type: "capture", target: "", pattern: "" }),
+    },
+    call: {
+        name: "Function",
+        render: CallEditor,
+        defaultValue: () => ({ type: "call", function: "", arguments: [] }),
+    },
+    collection: {
+        name: "Collection",
+        render: CollectionEditor,
+        defaultValue: () => ({ type: "collection", operation: "map", target: "" }),
+    },
+    "validate-schema": {
+        name: "JSON Schema",
+        render: ValidateSchemaEditor,
+        defaultValue: () => ({
+            type: "validate-schema",
+            target: "",
+            schema: { type: "inline", schema: {} },
+        }),
+    },
+    previous: {
+        name: "Previous",
+        render: PreviousRunEditor,
+        defaultValue: () => ({ type: "previous", target: "" }),
+    },
+    changed: {
+        name: "Changed",
+        render: PreviousRunEditor,
+        defaultValue: () => ({ type: "changed", target: "" }),
+    },
 };
 
 export default function ExpressionEditor({ index, expr }: Props) {
@@ -0,0 +1,34 @@
+import { TextField } from "@mui/material";
+import { Expression } from "laskea-bindings";
+import { useAppDispatch } from "../app/hooks";
+import { setExpression } from "../app/store";
+
+type Props = {
+    index: number;
+    expr: Extract<Expression, { type: "matches" }>;
+};
+
+export default function MatchesEditor({ index, expr }: Props) {
+    const dispatch = useAppDispatch();
+    const { target, pattern } = expr;
+
+    const setTarget = (target: string) =>
+        dispatch(setExpression({ index, expr: { ...expr, target } }));
+    const setPattern = (pattern: string) =>
+        dispatch(setExpression({ index, expr: { ...expr, pattern } }));
+
+    return (
+        <>
+            <TextField
+                value={target}
+                placeholder="Target"
+                onChange={e => setTarget(e.target.value)}
+            />
+            <TextField
+                value={pattern}
+                placeholder="Pattern"
+                onChange={e => setPattern(e.target.value)}
+            />
+        </>
+    );
+}
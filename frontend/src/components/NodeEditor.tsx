@@ -1,4 +1,4 @@
-import { useState } from "react";
+import { memo, useState } from "react";
 import { Button, Paper, TextField } from "@mui/material";
 import RemoveCircle from "@mui/icons-material/RemoveCircle";
 import { Node } from "laskea-bindings";
@@ -11,7 +11,7 @@ type Props = {
     node: Node;
 };
 
-export default function NodeEditor({ index, node }: Props) {
+function NodeEditor({ index, node }: Props) {
     const dispatch = useAppDispatch();
     const [hover, setHover] = useState(false);
 
@@ -45,3 +45,6 @@ export default function NodeEditor({ index, node }: Props) {
         </Paper>
     );
 }
+
+// Only re-render when this particular node has changed
+export default memo(NodeEditor);
@@ -0,0 +1,55 @@
+import { TextField } from "@mui/material";
+import { Expression } from "laskea-bindings";
+import { useAppDispatch } from "../app/hooks";
+import { setExpression } from "../app/store";
+
+type Props = {
+    index: number;
+    expr: Extract<Expression, { type: "oauth2-token" }>;
+};
+
+export default function OAuth2TokenEditor({ index, expr }: Props) {
+    const dispatch = useAppDispatch();
+    const { token_url, client_id, client_secret, scope } = expr;
+
+    // Changing how the token is requested invalidates any previous response
+    const update = (fields: Partial<Props["expr"]>) =>
+        dispatch(
+            setExpression({
+                index,
+                expr: {
+                    type: "oauth2-token",
+                    token_url,
+                    client_id,
+                    client_secret,
+                    scope,
+                    ...fields,
+                },
+            }),
+        );
+
+    return (
+        <>
+            <TextField
+                value={token_url}
+                placeholder="Token URL"
+                onChange={e => update({ token_url: e.target.value })}
+            />
+            <TextField
+                value={client_id}
+                placeholder="Client ID"
+                onChange={e => update({ client_id: e.target.value })}
+            />
+            <TextField
+                value={client_secret}
+                placeholder="Client secret"
+                onChange={e => update({ client_secret: e.target.value })}
+            />
+            <TextField
+                value={scope ?? ""}
+                placeholder="Scope"
+                onChange={e => update({ scope: e.target.value || undefined })}
+            />
+        </>
+    );
+}
@@ -0,0 +1,24 @@
+import { TextField } from "@mui/material";
+import { Expression } from "laskea-bindings";
+import { useAppDispatch } from "../app/hooks";
+import { setExpression } from "../app/store";
+
+type Props = {
+    index: number;
+    expr: Extract<Expression, { type: "previous" | "changed" }>;
+};
+
+export default function PreviousRunEditor({ index, expr }: Props) {
+    const dispatch = useAppDispatch();
+
+    const setTarget = (target: string) =>
+        dispatch(setExpression({ index, expr: { ...expr, target } }));
+
+    return (
+        <TextField
+            value={expr.target}
+            placeholder="Target"
+            onChange={e => setTarget(e.target.value)}
+        />
+    );
+}
@@ -1,7 +1,13 @@
 import { Button, TextField } from "@mui/material";
-import { Expression } from "laskea-bindings";
-import { useState } from "react";
-import { useAppDispatch } from "../app/hooks";
+import {
+    Expression,
+    Laskea,
+    PreparedRequest,
+    Response,
+    Value,
+} from "laskea-bindings";
+import { useAppDispatch, useAppSelector } from "../app/hooks";
+import { useLaskea } from "../app/laskea";
 import { setExpression } from "../app/store";
 
 type Props = {
@@ -9,17 +15,55 @@ type Props = {
     expr: Extract<Expression, { type: "request" }>;
 };
 
+/**
+ * A node which is populated by sending a HTTP request.
+ */
+type Sendable = Extract<Expression, { type: "request" | "oauth2-token" }>;
+
 export default function RequestEditor({ index, expr }: Props) {
     const dispatch = useAppDispatch();
-    const { url } = expr;
+    const laskea = useLaskea();
+    const nodes = useAppSelector(s => s.nodes.nodes);
+    const { url, auth, refresh } = expr;
     const outdated = expr.response !== undefined && expr.error !== undefined;
 
     const setText = (url: string) => {
-        dispatch(setExpression({ index, expr: { type: "request", url } }));
+        dispatch(setExpression({ index, expr: { type: "request", url, auth, refresh } }));
+    };
+
+    // Send a node's request and store the outcome, returning the status code
+    // if the server responded
+    const fetchNode = async (name: string) => {
+        const i = nodes.findIndex(n => n.name === name);
+        const expression = nodes[i]?.expression;
+        if (expression?.type !== "request" && expression?.type !== "oauth2-token") {
+            return undefined;
+        }
+
+        const updated = await sendRequest(laskea, name, expression);
+        // Tell laskea straight away so requests using this node's credentials
+        // can be prepared before the store is synced
+        laskea.updateExpression(name, updated);
+        dispatch(setExpression({ index: i, expr: updated }));
+
+        return updated.response?.status;
     };
+
     const onClick = async () => {
-        const response = await sendRequest(url);
-        dispatch(setExpression({ index, expr: response }));
+        const name = nodes[index].name;
+        const token = auth?.type === "oauth2" ? auth.token : undefined;
+
+        if (token && isPending(nodes.find(n => n.name === token)?.expression)) {
+            await fetchNode(token);
+        }
+
+        const status = await fetchNode(name);
+
+        // The token may have expired, so get a new one and try again
+        if (token && status === 401) {
+            await fetchNode(token);
+            await fetchNode(name);
+        }
     };
 
     return (
@@ -36,31 +80,122 @@ export default function RequestEditor({ index, expr }: Props) {
     );
 }
 
-async function sendRequest(url: string): Promise<Expression> {
-    const options = {
-        method: "get",
-        headers: {
-            "Content-Type": "application/json",
-        },
-    };
+function isPending(expression: Expression | undefined): boolean {
+    return (
+        expression?.type === "oauth2-token" &&
+        expression.response === undefined &&
+        expression.error === undefined
+    );
+}
+
+/**
+ * Send the request laskea prepared for a node (which includes any
+ * credentials), and record the response or error on its expression.
+ */
+async function sendRequest<T extends Sendable>(
+    laskea: Laskea,
+    name: string,
+    expression: T,
+): Promise<T> {
+    const rest = { ...expression, response: undefined, error: undefined };
+    const prepared = laskea.prepareRequest(name);
+
+    if ("Err" in prepared) {
+        return { ...rest, error: prepared.Err } as T;
+    }
 
     try {
-        const response = await fetch(url, options);
-        const body = await response.json();
-
-        return {
-            type: "request",
-            url,
-            response: {
-                status: response.status,
-                status_text: response.statusText,
-                url: response.url,
-                body,
-            },
-        };
+        return { ...rest, response: await send(prepared.Ok) } as T;
     } catch (e) {
         console.error(e);
-        const error = e instanceof Error ? e.message : "Request failed";
-        return { type: "request", url, error };
+        const message = e instanceof Error ? e.message : "Request failed";
+        return { ...rest, error: { type: "failed", message } } as T;
+    }
+}
+
+async function send(request: PreparedRequest): Promise<Response> {
+    const url = new URL(request.url);
+    for (const [key, value] of request.query) {
+        url.searchParams.append(key, value);
+    }
+    const options = {
+        method: request.method,
+        headers: Object.fromEntries(request.headers),
+        body: request.body,
+    };
+
+    const timestamp = new Date().toISOString();
+    const start = performance.now();
+    const response = await fetch(url.toString(), options);
+    const ttfb = performance.now() - start;
+    const text = await response.text();
+    const total = performance.now() - start;
+
+    return {
+        status: response.status,
+        status_text: response.statusText,
+        url: response.url,
+        body: toValue(JSON.parse(text)),
+        metrics: {
+            timestamp,
+            total: Math.round(total),
+            ttfb: Math.round(ttfb),
+            ...connectionTimings(response.url),
+            size: new TextEncoder().encode(text).length,
+            // The browser only tells us whether we were redirected
+            redirects: response.redirected ? [url.toString()] : [],
+        },
+    };
+}
+
+/**
+ * Get the DNS and connection timings for a request from the Resource Timing
+ * API. Cross-origin servers need to send a `Timing-Allow-Origin` header for
+ * these to be available.
+ */
+function connectionTimings(url: string): { dns?: number; connect?: number } {
+    const entries = performance.getEntriesByName(url, "resource");
+    const entry = entries[entries.length - 1] as
+        | PerformanceResourceTiming
+        | undefined;
+
+    if (!entry || entry.connectEnd == 0) {
+        return {};
+    }
+
+    return {
+        dns: Math.round(entry.domainLookupEnd - entry.domainLookupStart),
+        connect: Math.round(entry.connectEnd - entry.connectStart),
+    };
+}
+
+/**
+ * Convert a parsed JSON document into a Value.
+ */
+function toValue(json: unknown): Value {
+    switch (typeof json) {
+        case "number":
+            return Number.isInteger(json)
+                ? { type: "number", value: json }
+                : { type: "string", value: json.toString() };
+        case "string":
+            return { type: "string", value: json };
+        case "boolean":
+            return { type: "boolean", value: json };
+        case "object": {
+            if (json === null) {
+                return { type: "indeterminate" };
+            }
+            if (Array.isArray(json)) {
+                return { type: "array", value: json.map(toValue) };
+            }
+            const entries = Object.entries(json).map(([key, value]) => [
+                key,
+                toValue(value),
+            ]);
+            return { type: "object", value: Object.fromEntries(entries) };
+        }
+        default:
+            return { type: "indeterminate" };
     }
 }
@@ -0,0 +1,24 @@
+import { TextField } from "@mui/material";
+import { Expression } from "laskea-bindings";
+import { useAppDispatch } from "../app/hooks";
+import { setExpression } from "../app/store";
+
+type Props = {
+    index: number;
+    expr: Extract<Expression, { type: "secret" }>;
+};
+
+export default function SecretEditor({ index, expr }: Props) {
+    const dispatch = useAppDispatch();
+
+    const setName = (name: string) =>
+        dispatch(setExpression({ index, expr: { ...expr, name } }));
+
+    return (
+        <TextField
+            value={expr.name}
+            placeholder="Secret"
+            onChange={e => setName(e.target.value)}
+        />
+    );
+}
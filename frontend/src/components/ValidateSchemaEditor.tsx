@@ -0,0 +1,79 @@
+import { MenuItem, Select, TextField } from "@mui/material";
+import { useState } from "react";
+import { Expression, SchemaSource } from "laskea-bindings";
+import { useAppDispatch } from "../app/hooks";
+import { setExpression } from "../app/store";
+
+type Props = {
+    index: number;
+    expr: Extract<Expression, { type: "validate-schema" }>;
+};
+
+export default function ValidateSchemaEditor({ index, expr }: Props) {
+    const dispatch = useAppDispatch();
+    const { target, schema } = expr;
+    const [text, setText] = useState(() =>
+        schema.type == "inline" ? JSON.stringify(schema.schema, null, 2) : ""
+    );
+
+    const setTarget = (target: string) =>
+        dispatch(setExpression({ index, expr: { ...expr, target } }));
+    const setSchema = (schema: SchemaSource) =>
+        dispatch(setExpression({ index, expr: { ...expr, schema } }));
+
+    const changeSource = (type: string) => {
+        if (type == schema.type) {
+            return;
+        }
+
+        setSchema(
+            type == "file"
+                ? { type: "file", path: "" }
+                : { type: "inline", schema: {} }
+        );
+        setText("{}");
+    };
+
+    const setInline = (text: string) => {
+        setText(text);
+
+        try {
+            setSchema({ type: "inline", schema: JSON.parse(text) });
+        } catch {
+            // Wait until the schema is valid JSON before updating it
+        }
+    };
+
+    return (
+        <>
+            <TextField
+                value={target}
+                placeholder="Target"
+                onChange={e => setTarget(e.target.value)}
+            />
+            <Select
+                value={schema.type}
+                onChange={e => changeSource(e.target.value)}
+            >
+                <MenuItem value="inline">Inline</MenuItem>
+                <MenuItem value="file">File</MenuItem>
+            </Select>
+            {schema.type == "file" ? (
+                <TextField
+                    value={schema.path}
+                    placeholder="Path"
+                    onChange={e =>
+                        setSchema({ type: "file", path: e.target.value })
+                    }
+                />
+            ) : (
+                <TextField
+                    value={text}
+                    placeholder="JSON Schema"
+                    multiline
+                    onChange={e => setInline(e.target.value)}
+                />
+            )}
+        </>
+    );
+}
@@ -0,0 +1,24 @@
+import { TextField } from "@mui/material";
+import { Expression } from "laskea-bindings";
+import { useAppDispatch } from "../app/hooks";
+import { setExpression } from "../app/store";
+
+type Props = {
+    index: number;
+    expr: Extract<Expression, { type: "variable" }>;
+};
+
+export default function VariableEditor({ index, expr }: Props) {
+    const dispatch = useAppDispatch();
+
+    const setName = (name: string) =>
+        dispatch(setExpression({ index, expr: { ...expr, name } }));
+
+    return (
+        <TextField
+            value={expr.name}
+            placeholder="Variable"
+            onChange={e => setName(e.target.value)}
+        />
+    );
+}
@@ -0,0 +1,383 @@
+//! Python bindings to the [`laskea_engine`].
+//!
+//! Expressions are passed in as dictionaries using the same representation
+//! as the engine's JSON format (e.g. `{"type": "string", "value": "Hello"}`)
+//! and values are converted to their native Python equivalents.
+
+use laskea_engine::{
+    EditError as RustEditError, Evaluate, EvaluateStorage, EvaluationError as RustEvaluationError,
+    Expression, Inputs, InputsExt, InputsStorage, Instant, Node, Secret, Text, Value,
+};
+use pyo3::{
+    create_exception,
+    exceptions::{PyException, PyValueError},
+    prelude::*,
+    types::{timezone_utc, PyDateTime, PyDelta, PyDict, PyList},
+    IntoPyObjectExt,
+};
+use serde::de::DeserializeOwned;
+use std::{collections::BTreeMap, path::PathBuf, sync::Arc};
+
+create_exception!(
+    laskea,
+    LaskeaError,
+    PyException,
+    "The base class for all laskea errors."
+);
+create_exception!(
+    laskea,
+    EvaluationError,
+    LaskeaError,
+    "A node could not be evaluated."
+);
+create_exception!(
+    laskea,
+    UpstreamFailedError,
+    EvaluationError,
+    "A node could not be evaluated because a node it depends on failed."
+);
+create_exception!(
+    laskea,
+    EditError,
+    LaskeaError,
+    "The nodes could not be updated."
+);
+create_exception!(
+    laskea,
+    UnknownNodeError,
+    EditError,
+    "There is no node with this name."
+);
+create_exception!(
+    laskea,
+    DuplicateNameError,
+    EditError,
+    "There is already a node with this name."
+);
+create_exception!(
+    laskea,
+    UnknownEnvironmentError,
+    EditError,
+    "There is no environment with this name."
+);
+create_exception!(
+    laskea,
+    UnknownFunctionError,
+    EditError,
+    "There is no function with this name."
+);
+
+/// A high-level wrapper around the [`laskea_engine`].
+#[pyclass(unsendable)]
+pub struct Laskea {
+    db: Database,
+    /// The directory JSON Schema files are read from.
+    schema_dir: PathBuf,
+}
+
+#[pymethods]
+impl Laskea {
+    /// Create an empty workspace which reads JSON Schema files relative to
+    /// `schema_dir` (the current directory by default).
+    #[new]
+    #[pyo3(signature = (schema_dir=None))]
+    pub fn new(schema_dir: Option<PathBuf>) -> Self {
+        let mut db = Database::default();
+        laskea_engine::init_inputs(&mut db);
+        db.set_now(Instant::now());
+
+        Laskea {
+            db,
+            schema_dir: schema_dir.unwrap_or_else(|| PathBuf::from(".")),
+        }
+    }
+
+    /// The names of every node, in order.
+    pub fn nodes(&self) -> Vec<String> {
+        self.db.nodes().iter().map(|n| n.name.to_string()).collect()
+    }
+
+    /// Add a node to the end of the list.
+    pub fn add_node(&mut self, name: &str, expression: &Bound<'_, PyAny>) -> PyResult<()> {
+        let node = Node {
+            name: name.into(),
+            expr: Arc::new(expression_from_python(expression)?),
+        };
+
+        self.db.add_node(node).map_err(edit_error)?;
+        self.load_schemas();
+        Ok(())
+    }
+
+    /// Replace the expression for an existing node.
+    pub fn update_node(&mut self, name: &str, expression: &Bound<'_, PyAny>) -> PyResult<()> {
+        let expr = Arc::new(expression_from_python(expression)?);
+        self.db.update_expression(name, expr).map_err(edit_error)?;
+        self.load_schemas();
+        Ok(())
+    }
+
+    pub fn rename_node(&mut self, name: &str, new_name: &str) -> PyResult<()> {
+        self.db
+            .rename_node(name, new_name.into())
+            .map_err(edit_error)
+    }
+
+    pub fn remove_node(&mut self, name: &str) -> PyResult<()> {
+        self.db.remove_node(name).map(drop).map_err(edit_error)
+    }
+
+    /// Move a node so it is at the specified position in the list.
+    pub fn move_node(&mut self, name: &str, index: usize) -> PyResult<()> {
+        self.db.move_node(name, index).map_err(edit_error)
+    }
+
+    /// Evaluate every node, returning a dictionary mapping node names to
+    /// their values.
+    ///
+    /// Nodes which failed to evaluate are mapped to an `EvaluationError`
+    /// instead of raising an exception.
+    pub fn evaluate<'py>(&self, py: Python<'py>) -> PyResult<Bound<'py, PyDict>> {
+        let results = PyDict::new(py);
+
+        for (node, result) in self.db.nodes().iter().zip(self.db.evaluate().iter()) {
+            let value = match result {
+                Ok(value) => value_to_python(py, value)?,
+                Err(e) => evaluation_error(e.clone()).into_value(py).into_any(),
+            };
+
+            if !results.contains(node.name.as_ref())? {
+                results.set_item(node.name.as_ref(), value)?;
+            }
+        }
+
+        Ok(results)
+    }
+
+    /// Evaluate a single node, raising an `EvaluationError` if it fails.
+    pub fn value(&self, py: Python<'_>, name: &str) -> PyResult<PyObject> {
+        let nodes = self.db.nodes();
+        let node = nodes
+            .iter()
+            .find(|n| &*n.name == name)
+            .ok_or_else(|| edit_error(RustEditError::UnknownNode(name.into())))?;
+
+        match self.db.eval(node.name.clone(), Arc::clone(&node.expr)) {
+            Ok(value) => value_to_python(py, &value),
+            Err(e) => Err(evaluation_error(e)),
+        }
+    }
+
+    /// The names of every environment.
+    pub fn environments(&self) -> Vec<String> {
+        self.db
+            .environments()
+            .keys()
+            .map(|name| name.to_string())
+            .collect()
+    }
+
+    /// Create or replace an environment's variables.
+    pub fn set_environment(&mut self, name: &str, variables: BTreeMap<String, String>) {
+        let variables: BTreeMap<Text, Text> = variables
+            .into_iter()
+            .map(|(k, v)| (k.into(), v.into()))
+            .collect();
+        self.db.set_environment(name.into(), variables.into());
+    }
+
+    pub fn remove_environment(&mut self, name: &str) -> PyResult<()> {
+        self.db
+            .remove_environment(name)
+            .map(drop)
+            .map_err(edit_error)
+    }
+
+    /// The environment variables are currently being read from.
+    #[getter]
+    pub fn active_environment(&self) -> Option<String> {
+        self.db.active_environment().map(|name| name.to_string())
+    }
+
+    /// Switch to another environment, or pass `None` to deactivate the
+    /// current one.
+    #[pyo3(signature = (name))]
+    pub fn select_environment(&mut self, name: Option<&str>) -> PyResult<()> {
+        self.db.select_environment(name).map_err(edit_error)
+    }
+
+    /// Create or replace a user-defined function, using the same
+    /// representation as the workspace file (i.e. `parameters` and `body`).
+    pub fn set_function(&mut self, name: &str, function: &Bound<'_, PyAny>) -> PyResult<()> {
+        let function = from_python(function, "function")?;
+        self.db
+            .set_function(name.into(), function)
+            .map_err(edit_error)?;
+        self.load_schemas();
+        Ok(())
+    }
+
+    pub fn remove_function(&mut self, name: &str) -> PyResult<()> {
+        self.db.remove_function(name).map(drop).map_err(edit_error)
+    }
+
+    /// Provide the value for a secret, which will be redacted whenever it is
+    /// returned from [`Laskea::value()`] or [`Laskea::evaluate()`].
+    pub fn set_secret(&mut self, name: &str, value: &str) {
+        let mut secrets = self.db.secrets();
+        secrets.insert(name.into(), Secret::new(value));
+        self.db.set_secrets(secrets);
+    }
+
+    /// Set the time returned by `now()`, which should be a timezone-aware
+    /// `datetime`. Passing `None` uses the current time.
+    #[pyo3(signature = (now=None))]
+    pub fn set_now(&mut self, now: Option<&Bound<'_, PyAny>>) -> PyResult<()> {
+        let instant = match now {
+            Some(now) => {
+                let timestamp: String = now.call_method0("isoformat")?.extract()?;
+                timestamp
+                    .parse()
+                    .map_err(|e| PyValueError::new_err(format!("Invalid time: {}", e)))?
+            }
+            None => Instant::now(),
+        };
+
+        self.db.set_now(instant);
+        Ok(())
+    }
+
+    pub fn __len__(&self) -> usize {
+        self.db.nodes().len()
+    }
+
+    pub fn __repr__(&self) -> String {
+        format!("Laskea(nodes={:?})", self.nodes())
+    }
+}
+
+impl Laskea {
+    /// Read any JSON Schema files the nodes or functions use.
+    fn load_schemas(&mut self) {
+        laskea_engine::schemas::load_files(&mut self.db, &self.schema_dir);
+    }
+}
+
+impl Default for Laskea {
+    fn default() -> Self {
+        Laskea::new(None)
+    }
+}
+
+fn expression_from_python(expression: &Bound<'_, PyAny>) -> PyResult<Expression> {
+    from_python(expression, "expression")
+}
+
+/// Round-trip the object through Python's `json` module so we can reuse
+/// the engine's `serde` representation.
+fn from_python<T: DeserializeOwned>(obj: &Bound<'_, PyAny>, what: &str) -> PyResult<T> {
+    let json: String = obj
+        .py()
+        .import("json")?
+        .call_method1("dumps", (obj,))?
+        .extract()?;
+
+    serde_json::from_str(&json)
+        .map_err(|e| PyValueError::new_err(format!("Invalid {}: {}", what, e)))
+}
+
+fn value_to_python(py: Python<'_>, value: &Value) -> PyResult<PyObject> {
+    match value {
+        Value::Number(n) => n.into_py_any(py),
+        Value::String(s) => s.as_ref().into_py_any(py),
+        Value::Boolean(b) => b.into_py_any(py),
+        Value::Instant(instant) => {
+            let timestamp = instant.unix_millis() as f64 / 1000.0;
+            PyDateTime::from_timestamp(py, timestamp, Some(&timezone_utc(py)))?.into_py_any(py)
+        }
+        Value::Duration(duration) => {
+            const MS_PER_DAY: i64 = 24 * 60 * 60 * 1000;
+            let ms = duration.as_millis();
+            let days = i32::try_from(ms.div_euclid(MS_PER_DAY))
+                .map_err(|_| PyValueError::new_err(format!("{} is too long", duration)))?;
+            let remainder = ms.rem_euclid(MS_PER_DAY) as i32;
+
+            PyDelta::new(py, days, remainder / 1000, remainder % 1000 * 1000, false)?
+                .into_py_any(py)
+        }
+        Value::Object(obj) => {
+            let dict = PyDict::new(py);
+
+            for (key, value) in obj.iter() {
+                dict.set_item(key.as_ref(), value_to_python(py, value)?)?;
+            }
+
+            dict.into_py_any(py)
+        }
+        Value::Array(items) => {
+            let items = items
+                .iter()
+                .map(|item| value_to_python(py, item))
+                .collect::<PyResult<Vec<_>>>()?;
+
+            PyList::new(py, items)?.into_py_any(py)
+        }
+        Value::Secret(secret) => secret.to_string().into_py_any(py),
+        Value::Indeterminate => Ok(py.None()),
+    }
+}
+
+fn evaluation_error(e: RustEvaluationError) -> PyErr {
+    let msg = e.to_string();
+
+    match e {
+        RustEvaluationError::Failed { .. } => EvaluationError::new_err(msg),
+        RustEvaluationError::UpstreamFailed { node, .. } => {
+            UpstreamFailedError::new_err((msg, node.to_string()))
+        }
+    }
+}
+
+fn edit_error(e: RustEditError) -> PyErr {
+    let msg = e.to_string();
+
+    match e {
+        RustEditError::UnknownNode(_) => UnknownNodeError::new_err(msg),
+        RustEditError::DuplicateName(_) => DuplicateNameError::new_err(msg),
+        RustEditError::OutOfBounds { .. } => EditError::new_err(msg),
+        RustEditError::UnknownEnvironment(_) => UnknownEnvironmentError::new_err(msg),
+        RustEditError::UnknownFunction(_) => UnknownFunctionError::new_err(msg),
+        RustEditError::BuiltinFunction(_) => EditError::new_err(msg),
+    }
+}
+
+#[pymodule]
+fn laskea(m: &Bound<'_, PyModule>) -> PyResult<()> {
+    let py = m.py();
+
+    m.add_class::<Laskea>()?;
+    m.add("LaskeaError", py.get_type::<LaskeaError>())?;
+    m.add("EvaluationError", py.get_type::<EvaluationError>())?;
+    m.add("UpstreamFailedError", py.get_type::<UpstreamFailedError>())?;
+    m.add("EditError", py.get_type::<EditError>())?;
+    m.add("UnknownNodeError", py.get_type::<UnknownNodeError>())?;
+    m.add("DuplicateNameError", py.get_type::<DuplicateNameError>())?;
+    m.add(
+        "UnknownEnvironmentError",
+        py.get_type::<UnknownEnvironmentError>(),
+    )?;
+    m.add(
+        "UnknownFunctionError",
+        py.get_type::<UnknownFunctionError>(),
+    )?;
+
+    Ok(())
+}
+
+#[salsa::database(InputsStorage, EvaluateStorage)]
+#[derive(Default)]
+struct Database {
+    storage: salsa::Storage<Self>,
+}
+
+impl salsa::Database for Database {}
@@ -0,0 +1,279 @@
+from datetime import datetime, timedelta, timezone
+
+import pytest
+
+import laskea
+from laskea import Laskea
+
+
+def string(value):
+    return {"type": "string", "value": value}
+
+
+def equals(target, value):
+    return {"type": "equals", "target": target, "value": value}
+
+
+def get_property(target, field):
+    return {"type": "get-property", "target": target, "field": field}
+
+
+def response(status, body):
+    return {
+        "type": "request",
+        "url": "http://example.com/",
+        "response": {
+            "status": status,
+            "status_text": "OK",
+            "url": "http://example.com/",
+            "body": body,
+        },
+    }
+
+
+def test_evaluate_constants():
+    db = Laskea()
+    db.add_node("greeting", string("Hello, World!"))
+    db.add_node(
+        "is-hello",
+        equals("greeting", {"type": "string", "value": "Hello, World!"}),
+    )
+
+    assert db.evaluate() == {"greeting": "Hello, World!", "is-hello": True}
+
+
+def test_values_are_native_python_types():
+    db = Laskea()
+    body = {
+        "type": "object",
+        "value": {"answer": {"type": "number", "value": 42}},
+    }
+    db.add_node("request", response(200, body))
+    db.add_node("status", get_property("request", "status"))
+    db.add_node("pending", {"type": "request", "url": "http://example.com/"})
+
+    assert db.value("request") == {
+        "status": 200,
+        "status_text": "OK",
+        "url": "http://example.com/",
+        "body": {"answer": 42},
+    }
+    assert db.value("status") == 200
+    assert db.value("pending") is None
+
+
+def test_string_functions():
+    db = Laskea()
+    db.add_node("csv", string(" a,b,c "))
+    db.add_node(
+        "trimmed",
+        {
+            "type": "call",
+            "function": "trim",
+            "arguments": [{"type": "node", "name": "csv"}],
+        },
+    )
+    db.add_node(
+        "items",
+        {
+            "type": "call",
+            "function": "split",
+            "arguments": [
+                {"type": "node", "name": "trimmed"},
+                {"type": "constant", "value": string(",")},
+            ],
+        },
+    )
+
+    assert db.value("items") == ["a", "b", "c"]
+
+
+def test_user_defined_functions():
+    db = Laskea()
+    db.set_function(
+        "shout",
+        {
+            "parameters": [{"name": "text", "type": {"type": "string"}}],
+            "body": [
+                {
+                    "name": "upper",
+                    "expression": {
+                        "type": "call",
+                        "function": "upper",
+                        "arguments": [{"type": "node", "name": "text"}],
+                    },
+                }
+            ],
+        },
+    )
+    db.add_node(
+        "greeting",
+        {
+            "type": "call",
+            "function": "shout",
+            "arguments": [{"type": "constant", "value": string("hello")}],
+        },
+    )
+
+    assert db.value("greeting") == "HELLO"
+
+    db.remove_function("shout")
+    with pytest.raises(laskea.UnknownFunctionError):
+        db.remove_function("shout")
+
+
+def test_instants_and_durations():
+    db = Laskea()
+    db.set_now(datetime(2024, 1, 31, 10, 0, tzinfo=timezone.utc))
+
+    def call(function, *arguments):
+        return {"type": "call", "function": function, "arguments": list(arguments)}
+
+    db.add_node("now", call("now"))
+    db.add_node(
+        "updated_at",
+        call(
+            "parse_instant",
+            {"type": "constant", "value": string("2024-01-31T09:30:00Z")},
+        ),
+    )
+    db.add_node(
+        "age",
+        call(
+            "subtract",
+            {"type": "node", "name": "now"},
+            {"type": "node", "name": "updated_at"},
+        ),
+    )
+
+    assert db.value("now") == datetime(2024, 1, 31, 10, 0, tzinfo=timezone.utc)
+    assert db.value("age") == timedelta(minutes=30)
+
+    with pytest.raises(ValueError):
+        db.set_now(datetime(2024, 1, 31, 10, 0))
+
+
+def test_update_rename_move_and_remove():
+    db = Laskea()
+    db.add_node("first", string("1"))
+    db.add_node("second", string("2"))
+
+    db.update_node("first", string("one"))
+    db.rename_node("second", "2nd")
+    db.move_node("2nd", 0)
+
+    assert db.nodes() == ["2nd", "first"]
+    assert db.evaluate() == {"2nd": "2", "first": "one"}
+
+    db.remove_node("first")
+
+    assert len(db) == 1
+    assert db.evaluate() == {"2nd": "2"}
+
+
+def test_evaluation_errors_are_exceptions():
+    db = Laskea()
+    db.add_node("first", equals("second", {"type": "number", "value": 1}))
+    db.add_node("second", equals("first", {"type": "number", "value": 1}))
+
+    with pytest.raises(laskea.EvaluationError, match="Cycle detected"):
+        db.value("first")
+
+    results = db.evaluate()
+    assert isinstance(results["second"], laskea.EvaluationError)
+
+
+def test_upstream_failures_are_reported_downstream():
+    db = Laskea()
+    db.add_node(
+        "login",
+        {
+            "type": "request",
+            "url": "http://example.com/",
+            "error": {"type": "failed", "message": "401 Unauthorized"},
+        },
+    )
+    db.add_node("status", get_property("login", "status"))
+
+    with pytest.raises(laskea.UpstreamFailedError) as e:
+        db.value("status")
+
+    assert str(e.value.args[0]) == "blocked by failing node `login`: 401 Unauthorized"
+    assert e.value.args[1] == "login"
+
+
+def test_environments():
+    db = Laskea()
+    db.set_environment("dev", {"base_url": "http://localhost/"})
+    db.set_environment("prod", {"base_url": "https://example.com/"})
+    db.add_node("url", {"type": "variable", "name": "base_url"})
+
+    assert db.environments() == ["dev", "prod"]
+    assert db.active_environment is None
+    assert isinstance(db.evaluate()["url"], laskea.EvaluationError)
+
+    db.select_environment("dev")
+    assert db.value("url") == "http://localhost/"
+
+    db.select_environment("prod")
+    assert db.active_environment == "prod"
+    assert db.value("url") == "https://example.com/"
+
+    with pytest.raises(laskea.UnknownEnvironmentError):
+        db.select_environment("staging")
+
+
+def test_secrets_are_redacted():
+    db = Laskea()
+    db.add_node("token", {"type": "secret", "name": "api_token"})
+    db.set_secret("api_token", "hunter2")
+
+    assert db.value("token") == "[REDACTED]"
+    assert "hunter2" not in repr(db.evaluate())
+
+
+def test_schema_files_are_loaded(tmp_path):
+    (tmp_path / "greeting.json").write_text('{"type": "integer"}')
+    db = Laskea(schema_dir=str(tmp_path))
+    db.add_node("greeting", string("Hello"))
+
+    db.add_node(
+        "valid",
+        {
+            "type": "validate-schema",
+            "target": "greeting",
+            "schema": {"type": "file", "path": "greeting.json"},
+        },
+    )
+
+    assert db.value("valid")["valid"] is False
+
+
+def test_edit_errors():
+    db = Laskea()
+    db.add_node("first", string("1"))
+
+    with pytest.raises(laskea.DuplicateNameError):
+        db.add_node("first", string("1"))
+
+    with pytest.raises(laskea.UnknownNodeError):
+        db.remove_node("missing")
+
+    with pytest.raises(laskea.EditError):
+        db.move_node("first", 42)
+
+
+def test_exception_hierarchy():
+    assert issubclass(laskea.EvaluationError, laskea.LaskeaError)
+    assert issubclass(laskea.UpstreamFailedError, laskea.EvaluationError)
+    assert issubclass(laskea.EditError, laskea.LaskeaError)
+    assert issubclass(laskea.UnknownNodeError, laskea.EditError)
+    assert issubclass(laskea.DuplicateNameError, laskea.EditError)
+    assert issubclass(laskea.UnknownEnvironmentError, laskea.EditError)
+
+
+def test_invalid_expressions_are_rejected():
+    db = Laskea()
+
+    with pytest.raises(ValueError):
+        db.add_node("broken", {"type": "not-a-real-expression"})
@@ -0,0 +1,67 @@
+//! A HTTP/JSON API which lets other services host named workspaces and
+//! evaluate them using the [`laskea_engine`].
+
+#[cfg(test)]
+#[macro_use]
+extern crate pretty_assertions;
+
+mod routes;
+mod workspace;
+
+pub use crate::{
+    routes::{handle, Reply},
+    workspace::Workspace,
+};
+
+use std::collections::BTreeMap;
+use tiny_http::{Header, Request, Response};
+
+/// The server's state, a set of named [`Workspace`]s.
+#[derive(Default)]
+pub struct Server {
+    workspaces: BTreeMap<String, Workspace>,
+}
+
+impl Server {
+    pub fn new() -> Self {
+        Server::default()
+    }
+
+    pub fn workspaces(&self) -> &BTreeMap<String, Workspace> {
+        &self.workspaces
+    }
+
+    /// Handle a single request.
+    pub fn handle(&mut self, method: &tiny_http::Method, url: &str, body: &[u8]) -> Reply {
+        handle(&mut self.workspaces, method, url, body)
+    }
+
+    /// Serve requests until the underlying listener is closed.
+    pub fn serve(&mut self, server: &tiny_http::Server) {
+        for request in server.incoming_requests() {
+            self.respond(request);
+        }
+    }
+
+    fn respond(&mut self, mut request: Request) {
+        let mut body = Vec::new();
+
+        let reply = match request.as_reader().read_to_end(&mut body) {
+            Ok(_) => self.handle(request.method(), request.url(), &body),
+            Err(e) => Reply {
+                status: 400,
+                body: serde_json::json!({ "error": e.to_string() }),
+            },
+        };
+
+        let content_type: Header = "Content-Type: application/json"
+            .parse()
+            .expect("Always a valid header");
+        let response = Response::from_data(reply.body.to_string())
+            .with_status_code(reply.status)
+            .with_header(content_type);
+
+        // The client may have hung up, but there's nothing we can do about it
+        let _ = request.respond(response);
+    }
+}
@@ -0,0 +1,21 @@
+use laskea_server::Server;
+
+const DEFAULT_ADDRESS: &str = "127.0.0.1:8000";
+
+fn main() {
+    let address = std::env::args()
+        .nth(1)
+        .unwrap_or_else(|| DEFAULT_ADDRESS.to_string());
+
+    let listener = match tiny_http::Server::http(&address) {
+        Ok(l) => l,
+        Err(e) => {
+            eprintln!("Unable to listen on {}: {}", address, e);
+            std::process::exit(1);
+        }
+    };
+
+    println!("Listening on http://{}/", address);
+
+    Server::new().serve(&listener);
+}
@@ -0,0 +1,394 @@
+use crate::Workspace;
+use laskea_engine::{Evaluated, Expression, Instant, Redact, Text};
+use serde_json::json;
+use std::collections::BTreeMap;
+use tiny_http::Method;
+
+/// The status code and JSON body sent back for a request.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Reply {
+    pub status: u16,
+    pub body: serde_json::Value,
+}
+
+impl Reply {
+    fn ok(body: impl serde::Serialize) -> Self {
+        Reply::with_status(200, body)
+    }
+
+    fn created(body: impl serde::Serialize) -> Self {
+        Reply::with_status(201, body)
+    }
+
+    fn error(status: u16, msg: impl Into<String>) -> Self {
+        Reply::with_status(status, json!({ "error": msg.into() }))
+    }
+
+    fn not_found(what: &str, name: &str) -> Self {
+        Reply::error(404, format!("No {} called \"{}\"", what, name))
+    }
+
+    fn with_status(status: u16, body: impl serde::Serialize) -> Self {
+        let body = serde_json::to_value(body).expect("Serializing to JSON is infallible");
+        Reply { status, body }
+    }
+}
+
+/// Dispatch a request to the appropriate handler.
+///
+/// The following routes are available:
+///
+/// - `GET /workspaces` - list all workspaces
+/// - `PUT /workspaces/{ws}` - create a workspace
+/// - `DELETE /workspaces/{ws}` - delete a workspace
+/// - `GET /workspaces/{ws}/nodes` - list a workspace's nodes
+/// - `PUT /workspaces/{ws}/nodes/{name}` - create or update a node, where
+///   the body is a JSON-serialized [`Expression`]
+/// - `DELETE /workspaces/{ws}/nodes/{name}` - delete a node
+/// - `GET /workspaces/{ws}/nodes/{name}/value` - evaluate a single node
+/// - `GET /workspaces/{ws}/nodes/{name}/explain` - explain how a node's value
+///   was derived
+/// - `POST /workspaces/{ws}/evaluate` - evaluate every node
+/// - `GET /workspaces/{ws}/diagnostics` - get the workspace's diagnostics
+pub fn handle(
+    workspaces: &mut BTreeMap<String, Workspace>,
+    method: &Method,
+    url: &str,
+    body: &[u8],
+) -> Reply {
+    let path = url.split('?').next().unwrap_or_default();
+    let segments: Vec<String> = path
+        .split('/')
+        .filter(|s| !s.is_empty())
+        .map(percent_decode)
+        .collect();
+    let segments: Vec<&str> = segments.iter().map(|s| s.as_str()).collect();
+
+    match (method, segments.as_slice()) {
+        (Method::Get, ["workspaces"]) => Reply::ok(workspaces.keys().collect::<Vec<_>>()),
+        (Method::Put, ["workspaces", ws]) => {
+            if workspaces.contains_key(*ws) {
+                Reply::ok(json!({ "name": ws }))
+            } else {
+                workspaces.insert(ws.to_string(), Workspace::new());
+                Reply::created(json!({ "name": ws }))
+            }
+        }
+        (Method::Delete, ["workspaces", ws]) => match workspaces.remove(*ws) {
+            Some(_) => Reply::ok(json!({ "name": ws })),
+            None => Reply::not_found("workspace", ws),
+        },
+        (_, ["workspaces", ws, rest @ ..]) => match workspaces.get_mut(*ws) {
+            Some(workspace) => handle_workspace(workspace, method, rest, body),
+            None => Reply::not_found("workspace", ws),
+        },
+        (_, ["workspaces"]) => Reply::error(405, "Method not allowed"),
+        _ => Reply::error(404, format!("Nothing found at \"{}\"", path)),
+    }
+}
+
+fn handle_workspace(
+    workspace: &mut Workspace,
+    method: &Method,
+    segments: &[&str],
+    body: &[u8],
+) -> Reply {
+    // Nodes which use now() should see the time the request was made
+    workspace.set_now(Instant::now());
+
+    match (method, segments) {
+        (Method::Get, ["nodes"]) => Reply::ok(workspace.nodes()),
+        (Method::Put, ["nodes", name]) => {
+            let expr: Expression = match serde_json::from_slice(body) {
+                Ok(e) => e,
+                Err(e) => {
+                    return Reply::error(400, format!("Unable to parse the expression: {}", e))
+                }
+            };
+
+            let reply = json!({ "name": name });
+
+            if workspace.set_node(Text::from(*name), expr) {
+                Reply::created(reply)
+            } else {
+                Reply::ok(reply)
+            }
+        }
+        (Method::Delete, ["nodes", name]) => {
+            if workspace.remove_node(name) {
+                Reply::ok(json!({ "name": name }))
+            } else {
+                Reply::not_found("node", name)
+            }
+        }
+        (Method::Get, ["nodes", name, "value"]) => match workspace.value(name) {
+            Some(result) => Reply::ok(Evaluated::new(Text::from(*name), result).redacted()),
+            None => Reply::not_found("node", name),
+        },
+        (Method::Get, ["nodes", name, "explain"]) => match workspace.explain(name) {
+            Some(explanation) => Reply::ok(explanation.redacted()),
+            None => Reply::not_found("node", name),
+        },
+        (Method::Post, ["evaluate"]) => Reply::ok(
+            workspace
+                .evaluate()
+                .into_iter()
+                .map(|(name, result)| Evaluated::new(name, result).redacted())
+                .collect::<Vec<_>>(),
+        ),
+        (Method::Get, ["diagnostics"]) => Reply::ok(workspace.diagnostics()),
+        (_, ["nodes"])
+        | (_, ["nodes", _])
+        | (_, ["nodes", _, "value"])
+        | (_, ["nodes", _, "explain"])
+        | (_, ["evaluate"])
+        | (_, ["diagnostics"]) => Reply::error(405, "Method not allowed"),
+        _ => Reply::error(404, format!("Nothing found at \"{}\"", segments.join("/"))),
+    }
+}
+
+/// Decode `%XX` escapes in a path segment, leaving malformed escapes as-is.
+fn percent_decode(segment: &str) -> String {
+    let bytes = segment.as_bytes();
+    let mut decoded = Vec::with_capacity(bytes.len());
+    let mut i = 0;
+
+    while i < bytes.len() {
+        let escaped = bytes
+            .get(i + 1..i + 3)
+            .filter(|_| bytes[i] == b'%')
+            .and_then(|hex| std::str::from_utf8(hex).ok())
+            .and_then(|hex| u8::from_str_radix(hex, 16).ok());
+
+        match escaped {
+            Some(b) => {
+                decoded.push(b);
+                i += 3;
+            }
+            None => {
+                decoded.push(bytes[i]);
+                i += 1;
+            }
+        }
+    }
+
+    String::from_utf8_lossy(&decoded).into_owned()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn request(
+        workspaces: &mut BTreeMap<String, Workspace>,
+        method: Method,
+        url: &str,
+        body: serde_json::Value,
+    ) -> Reply {
+        let body = if body.is_null() {
+            Vec::new()
+        } else {
+            serde_json::to_vec(&body).unwrap()
+        };
+
+        handle(workspaces, &method, url, &body)
+    }
+
+    fn workspace_with_nodes() -> BTreeMap<String, Workspace> {
+        let mut workspaces = BTreeMap::new();
+        request(&mut workspaces, Method::Put, "/workspaces/ws", json!(null));
+        request(
+            &mut workspaces,
+            Method::Put,
+            "/workspaces/ws/nodes/greeting",
+            json!({ "type": "string", "value": "Hello, World!" }),
+        );
+        request(
+            &mut workspaces,
+            Method::Put,
+            "/workspaces/ws/nodes/is%20hello",
+            json!({
+                "type": "equals",
+                "target": "greeting",
+                "value": { "type": "string", "value": "Hello, World!" },
+            }),
+        );
+
+        workspaces
+    }
+
+    #[test]
+    fn create_and_list_workspaces() {
+        let mut workspaces = BTreeMap::new();
+
+        let got = request(&mut workspaces, Method::Put, "/workspaces/ws", json!(null));
+        assert_eq!(got.status, 201);
+        let got = request(&mut workspaces, Method::Put, "/workspaces/ws", json!(null));
+        assert_eq!(got.status, 200);
+
+        let got = request(&mut workspaces, Method::Get, "/workspaces", json!(null));
+        assert_eq!(got, Reply::ok(json!(["ws"])));
+    }
+
+    #[test]
+    fn evaluate_a_workspace() {
+        let mut workspaces = workspace_with_nodes();
+
+        let got = request(
+            &mut workspaces,
+            Method::Post,
+            "/workspaces/ws/evaluate",
+            json!(null),
+        );
+
+        let should_be = json!([
+            { "name": "greeting", "value": { "type": "string", "value": "Hello, World!" } },
+            { "name": "is hello", "value": { "type": "boolean", "value": true } },
+        ]);
+        assert_eq!(got, Reply::ok(should_be));
+    }
+
+    #[test]
+    fn update_and_get_a_single_value() {
+        let mut workspaces = workspace_with_nodes();
+
+        let got = request(
+            &mut workspaces,
+            Method::Put,
+            "/workspaces/ws/nodes/greeting",
+            json!({ "type": "string", "value": "Goodbye" }),
+        );
+        assert_eq!(got.status, 200);
+
+        let got = request(
+            &mut workspaces,
+            Method::Get,
+            "/workspaces/ws/nodes/is%20hello/value",
+            json!(null),
+        );
+        let should_be =
+            json!({ "name": "is hello", "value": { "type": "boolean", "value": false } });
+        assert_eq!(got, Reply::ok(should_be));
+    }
+
+    #[test]
+    fn schema_files_are_loaded() {
+        let dir = std::env::temp_dir().join(format!("laskea-server-{}", std::process::id()));
+        std::fs::create_dir_all(&dir).unwrap();
+        std::fs::write(dir.join("greeting.json"), r#"{ "type": "string" }"#).unwrap();
+        let mut workspaces = workspace_with_nodes();
+        workspaces.insert("ws".into(), Workspace::with_schema_dir(&dir));
+        request(
+            &mut workspaces,
+            Method::Put,
+            "/workspaces/ws/nodes/greeting",
+            json!({ "type": "string", "value": "Hello, World!" }),
+        );
+
+        let got = request(
+            &mut workspaces,
+            Method::Put,
+            "/workspaces/ws/nodes/valid",
+            json!({
+                "type": "validate-schema",
+                "target": "greeting",
+                "schema": { "type": "file", "path": "greeting.json" },
+            }),
+        );
+        assert_eq!(got.status, 201);
+        std::fs::remove_dir_all(&dir).unwrap();
+
+        let got = request(
+            &mut workspaces,
+            Method::Get,
+            "/workspaces/ws/nodes/valid/value",
+            json!(null),
+        );
+        assert_eq!(got.body["value"]["value"]["valid"]["value"], json!(true));
+    }
+
+    #[test]
+    fn deleting_a_node_creates_diagnostics() {
+        let mut workspaces = workspace_with_nodes();
+
+        let got = request(
+            &mut workspaces,
+            Method::Delete,
+            "/workspaces/ws/nodes/greeting",
+            json!(null),
+        );
+        assert_eq!(got.status, 200);
+
+        let got = request(
+            &mut workspaces,
+            Method::Get,
+            "/workspaces/ws/diagnostics",
+            json!(null),
+        );
+        let should_be = json!([
+            { "node": "is hello", "message": "No \"greeting\" input found" },
+        ]);
+        assert_eq!(got, Reply::ok(should_be));
+    }
+
+    #[test]
+    fn explain_a_node() {
+        let mut workspaces = workspace_with_nodes();
+
+        let got = request(
+            &mut workspaces,
+            Method::Get,
+            "/workspaces/ws/nodes/is%20hello/explain",
+            json!(null),
+        );
+
+        assert_eq!(got.status, 200);
+        assert_eq!(
+            got.body["derivation"],
+            json!({ "type": "compared", "equal": true })
+        );
+        assert_eq!(got.body["inputs"][0]["node"], json!("greeting"));
+    }
+
+    #[test]
+    fn invalid_expressions_are_rejected() {
+        let mut workspaces = workspace_with_nodes();
+
+        let got = request(
+            &mut workspaces,
+            Method::Put,
+            "/workspaces/ws/nodes/broken",
+            json!({ "type": "unknown", "value": 42 }),
+        );
+
+        assert_eq!(got.status, 400);
+    }
+
+    #[test]
+    fn unknown_workspaces_and_nodes() {
+        let mut workspaces = workspace_with_nodes();
+
+        let got = request(
+            &mut workspaces,
+            Method::Post,
+            "/workspaces/missing/evaluate",
+            json!(null),
+        );
+        assert_eq!(got.status, 404);
+
+        let got = request(
+            &mut workspaces,
+            Method::Get,
+            "/workspaces/ws/nodes/missing/value",
+            json!(null),
+        );
+        assert_eq!(got.status, 404);
+    }
+
+    #[test]
+    fn decode_escaped_segments() {
+        assert_eq!(percent_decode("is%20hello"), "is hello");
+        assert_eq!(percent_decode("100%"), "100%");
+        assert_eq!(percent_decode("%zz"), "%zz");
+    }
+}
@@ -0,0 +1,103 @@
+use laskea_engine::{
+    Diagnostic, Evaluate, EvaluateStorage, EvaluationError, Explanation, Expression, Inputs,
+    InputsExt, InputsStorage, Instant, Node, Sequence, Text, Value,
+};
+use std::{path::PathBuf, sync::Arc};
+
+/// A named collection of nodes, backed by its own salsa database so
+/// repeated updates are evaluated incrementally.
+pub struct Workspace {
+    db: Database,
+    schema_dir: PathBuf,
+}
+
+impl Workspace {
+    /// Create an empty workspace which reads JSON Schema files relative to
+    /// the current directory.
+    pub fn new() -> Self {
+        Workspace::with_schema_dir(".")
+    }
+
+    /// Create an empty workspace which reads JSON Schema files relative to
+    /// `schema_dir`.
+    pub fn with_schema_dir(schema_dir: impl Into<PathBuf>) -> Self {
+        let mut db = Database::default();
+        laskea_engine::init_inputs(&mut db);
+        db.set_now(Instant::now());
+
+        Workspace {
+            db,
+            schema_dir: schema_dir.into(),
+        }
+    }
+
+    /// Set the time returned by `now()`.
+    pub fn set_now(&mut self, now: Instant) {
+        self.db.set_now(now);
+    }
+
+    pub fn nodes(&self) -> Sequence<Node> {
+        self.db.nodes()
+    }
+
+    /// Create or update the node with this name, returning `true` if a new
+    /// node was created.
+    pub fn set_node(&mut self, name: Text, expr: Expression) -> bool {
+        let expr = Arc::new(expr);
+
+        let created = match self.db.update_expression(&name, Arc::clone(&expr)) {
+            Ok(_) => false,
+            Err(_) => {
+                self.db
+                    .add_node(Node { name, expr })
+                    .expect("We just checked the node doesn't exist");
+                true
+            }
+        };
+
+        laskea_engine::schemas::load_files(&mut self.db, &self.schema_dir);
+        created
+    }
+
+    /// Remove a node, returning `true` if it existed.
+    pub fn remove_node(&mut self, name: &str) -> bool {
+        self.db.remove_node(name).is_ok()
+    }
+
+    pub fn evaluate(&self) -> Vec<(Text, Result<Value, EvaluationError>)> {
+        let nodes = self.db.nodes();
+        let names = nodes.iter().map(|n| n.name.clone());
+        names.zip(self.db.evaluate().iter().cloned()).collect()
+    }
+
+    /// Evaluate a single node, returning `None` if it doesn't exist.
+    pub fn value(&self, name: &str) -> Option<Result<Value, EvaluationError>> {
+        let expr = self.db.expression_of(name.into())?;
+
+        Some(self.db.eval(name.into(), expr))
+    }
+
+    /// Explain how a node's value was derived, returning `None` if it doesn't
+    /// exist.
+    pub fn explain(&self, name: &str) -> Option<Explanation> {
+        self.db.explain(name.into())
+    }
+
+    pub fn diagnostics(&self) -> Sequence<Diagnostic> {
+        self.db.diagnostics()
+    }
+}
+
+impl Default for Workspace {
+    fn default() -> Self {
+        Workspace::new()
+    }
+}
+
+#[salsa::database(InputsStorage, EvaluateStorage)]
+#[derive(Default)]
+struct Database {
+    storage: salsa::Storage<Self>,
+}
+
+impl salsa::Database for Database {}